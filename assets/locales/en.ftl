@@ -0,0 +1,213 @@
+# English strings. Other locales live next to this file as <lang>.ftl and
+# only need the entries they translate; anything missing falls back to these.
+
+## Tiles
+
+tile-empty = empty
+tile-straight = straight
+tile-corner = corner
+tile-t-junction = t-junction
+tile-crossing = crossing
+tile-dead-end = dead-end
+tile-key = key
+tile-gate = gate
+tile-pillar = pillar
+
+## HUD
+
+hud-turn-player = turn: player { $number }
+hud-turn-bot = turn: bot { $number }
+hud-nerve = nerve: { $count }
+hud-keys = keys: { $counts }
+hud-actions = actions: { $count }
+hud-seed = seed: { $seed }
+hud-floor = floor: { $number } of { $count }
+
+## Hand
+
+hand-held = also holding: { $tiles }
+hand-tile = in hand: { $tile }, turned { $degrees }°
+hand-bag = { $count } left
+hand-discard = { $tile }
+    { $count } discarded
+hand-discard-empty = no discards
+
+## Odds
+
+odds-title = in the bag: { $count }
+odds-line = { $tile }: { $count } ({ $percent }%)
+odds-fresh-bag = the next draw starts a fresh bag
+
+## Hints
+
+hint = hint: ({ $x }, { $y }) { $reason }
+reason-connects-to = connects to the { $tile } corridor
+reason-joins-corridor = joins a lit corridor
+reason-joins-corridors = joins { $count } lit corridors
+reason-heads-towards = heads towards the { $tile }
+reason-opens-corridors = opens new corridors
+reason-avoids-monsters = keeps away from the wax eaters
+
+## Event log
+
+log-placed = P{ $player } placed a { $tile } at ({ $x },{ $y })
+log-collapsed = Tile at ({ $x },{ $y }) fell into darkness
+log-stirred = Something stirs in the dark at ({ $x },{ $y })
+log-emerged = A wax eater crawls out at ({ $x },{ $y })
+log-hit = A wax eater knocked P{ $player }'s keys into the dark
+log-hit-survived = P{ $player } kept their nerve as a wax eater lunged
+log-key-passed = P{ $from } handed a key to P{ $to }
+log-ping = P{ $player } pinged ({ $x },{ $y })
+log-turn = P{ $player } to play
+
+## Pings
+
+ping-place-here = place here
+
+## Stats
+
+stats-screen = Stats for { $profile }
+    games played: { $played }
+    wins: { $wins }
+    best clear time: { $best }
+    best timed run: { $timed }
+    tiles placed: { $tiles }
+    candles lost: { $candles }
+    achievements: { $unlocked } of { $total }
+stats-no-best = none yet
+
+## Timed mode
+
+timed-clock = turn: { $turn }s   total: { $total }
+timed-won = Escaped in { $time }. Best time: { $best }
+timed-new-best = Escaped in { $time }, a new best time!
+timed-lost = Beaten to the gate after { $time }
+
+## Daily challenge
+
+daily-best = Escaped in { $turns } turns. Today's best: { $best } turns
+daily-new-best = Escaped in { $turns } turns, today's best yet!
+daily-no-best = No escape today yet
+daily-share-won = nightcage daily { $date }: escaped in { $turns } turns with { $keys } keys and { $candles } candles lost
+daily-share-lost = nightcage daily { $date }: beaten to the gate after { $turns } turns
+
+## Autosave
+
+autosave-offer = Your last game didn't close properly. Press F9 to pick it up where it was left
+
+## Sharing
+
+share-code = Share this game with the code { $code }
+share-scenario = Games set up from a scenario can't be shared
+share-resumed = Games picked up from an autosave can't be shared
+share-eased = Games eased by the assistant can't be shared
+replay-watching = Watching a shared game, F10 to skip to where it ends
+
+## Achievements
+
+achievement-unlocked = Achievement unlocked: { $name } ({ $description })
+achievement-first-escape = Out of the Cage
+achievement-first-escape-description = win a game
+achievement-untouched = Untouched
+achievement-untouched-description = win without losing a candle
+achievement-key-ring = Key Ring
+achievement-key-ring-description = escape carrying all four keys
+achievement-swift = Swift
+achievement-swift-description = win in under five minutes
+achievement-mason = Mason
+achievement-mason-description = place fifty tiles in one game
+
+## Console
+
+console-help = give_key [count]: keys for whoever's turn it is
+    set_tile <x> <y> <tile> [n|e|s|w]: put a tile down
+    reveal_all: lift the fog off the board
+    seed <number>: start over from a seed
+
+## Debug overlay
+
+debug-fps = fps: { $fps }
+debug-no-tile = no tile hovered
+debug-tile = tile { $x }, { $y } on floor { $floor } (entity { $entity })
+debug-kind = { $tile }, texture { $texture }, rotation { $rotation }
+debug-passages = passages { $mask }
+debug-lit = lit, brightness { $light }
+debug-unlit = unlit, brightness { $light }
+debug-pawns = pawns of players { $players }
+
+## Pause menu
+
+menu-resume = Resume
+menu-settings = Settings
+menu-restart = Restart
+menu-new-game = New game
+menu-quit = Quit
+menu-palette = Palette: { $palette }
+menu-pack = Tile pack: { $pack }
+menu-language = Language: { $lang }
+menu-announcements-on = Announcements: on
+menu-announcements-off = Announcements: off
+menu-screen-effects = Screen effects: { $level }
+juice-off = off
+juice-subtle = subtle
+juice-full = full
+menu-assistant-on = Assistant: on
+menu-assistant-off = Assistant: off
+menu-back = Back
+menu-house-rules = House rules
+menu-rule-on = { $rule }: on
+menu-rule-off = { $rule }: off
+rule-friendly-monsters = Friendly monsters
+rule-no-collapse = No collapse
+rule-unlimited-rotation = Unlimited rotation
+rule-larger-hands = Larger hands
+rule-wrap-around = Wrap-around edges
+menu-rematch = Rematch
+menu-ease-on = Ease the next game: yes
+menu-ease-off = Ease the next game: no
+
+## Game over
+
+summary-won = You escaped!
+summary-lost = Player { $number } escaped first
+summary-details = turns: { $turns }
+    tiles placed: { $tiles }
+    keys collected: { $keys }
+    candles lost: { $candles }
+summary-ease = That's { $losses } games lost in a row. The next could be played with { $easing }
+ease-extra-key = an extra key in every bag
+ease-slow-wax-eaters = wax eaters slower to crawl out
+ease-and = { $first } and { $second }
+
+## Editor
+
+editor-tool-paint = paint
+editor-tool-erase = erase
+editor-tool-player-start = player start
+editor-tool-monster = monster
+editor-overlay = editor: { $tool } ({ $tile }) { $selected }
+    1 paint, 2 erase, 3 player start, 4 monster, enter: export
+    shift + click/drag: select, delete: clear selection, esc: deselect
+    { $status }
+editor-selected = { $count } selected
+editor-exported = exported { $path }
+editor-export-failed = export failed: { $error }
+
+## Tutorial
+
+tutorial-hover = Move the mouse over the board, or step across it with the arrow keys. The highlighted cell shows the tile in hand.
+tutorial-rotate = Right click or press q to turn the tile in hand.
+tutorial-place = Left click the marked cell or press enter on it to put the tile down at the end of the corridor.
+tutorial-light = The tile in hand holds a candle. Hover around the marked cell until its light spills through the open sides onto the corridor.
+tutorial-pick-up-key = Walk to the key: press m to mark where your pawn can go, then click the key or press enter on it. Each step costs an action, and the turn passes once they run out.
+tutorial-escape = Carry the key to the gate to escape.
+tutorial-complete = You escaped the night cage. That's everything you need to play.
+
+## Announcements
+
+announce-on = announcements on
+announce-off = announcements off
+announce-tile = row { $row }, column { $column }, { $tile }
+announce-unplaced = unplaced
+announce-adjacent-lit = adjacent to lit corridor
+announce-monster = wax eater lurking
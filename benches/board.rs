@@ -0,0 +1,153 @@
+// Benchmarks of the work that grows with the board, on boards from the usual
+// size up to mega cages, run with `cargo bench`. The game's systems share
+// their rules with the simulation and the bots, so these time the same
+// lighting, placement and pathfinding the game does each time a tile is
+// hovered or placed.
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+
+use bevy_ecs_tilemap::prelude::*;
+use nightcage::ai::{placements, BoardView};
+use nightcage::board::{rotated_passages, BoardConfig, BoardShape};
+use nightcage::difficulty::Difficulty;
+use nightcage::house_rules::RuleSet;
+use nightcage::path::board_distances;
+use nightcage::rng::Seed;
+use nightcage::scenario::{Scenario, ScenarioTile};
+use nightcage::simulation::{Action, Simulation};
+use nightcage::tiles::TileKind;
+
+const SIDES: [u32; 3] = [9, 25, 40];
+
+// A square board with tiles on about half of it, dealt the same every run.
+fn half_placed(side: u32) -> Scenario {
+    let mut rng = fastrand::Rng::with_seed(u64::from(side));
+    let mut tiles = Vec::new();
+    for y in 0..side {
+        for x in 0..side {
+            if rng.bool() {
+                tiles.push(ScenarioTile {
+                    pos: TilePos { x, y },
+                    floor: 0,
+                    kind: TileKind::PLACEABLE[rng.usize(..TileKind::PLACEABLE.len())],
+                    rotation: rng.u8(0..4),
+                });
+            }
+        }
+    }
+    let start = TilePos {
+        x: side / 2,
+        y: side / 2,
+    };
+    tiles.retain(|tile| tile.pos != start);
+    tiles.push(ScenarioTile {
+        pos: start,
+        floor: 0,
+        kind: TileKind::Crossing,
+        rotation: 0,
+    });
+    Scenario {
+        size: TilemapSize { x: side, y: side },
+        shape: BoardShape::Square,
+        floors: 1,
+        tiles,
+        stairs: Vec::new(),
+        player_starts: vec![start],
+        monsters: Vec::new(),
+        next: None,
+        bag: Vec::new(),
+    }
+}
+
+fn simulation(scenario: &Scenario) -> Simulation {
+    Simulation::new(
+        BoardConfig::default(),
+        Some(scenario),
+        2,
+        Difficulty::Normal,
+        RuleSet::default(),
+        Seed(0),
+    )
+}
+
+fn cells(side: u32) -> Vec<TilePos> {
+    (0..side)
+        .flat_map(|y| (0..side).map(move |x| TilePos { x, y }))
+        .collect()
+}
+
+// Hovering the candle over every cell of the board in turn.
+fn lighting(c: &mut Criterion) {
+    let mut group = c.benchmark_group("lighting");
+    for side in SIDES {
+        let sim = simulation(&half_placed(side));
+        let cells = cells(side);
+        group.bench_with_input(BenchmarkId::from_parameter(side), &cells, |b, cells| {
+            b.iter(|| {
+                cells
+                    .iter()
+                    .map(|pos| sim.illuminated(pos).len())
+                    .sum::<usize>()
+            })
+        });
+    }
+    group.finish();
+}
+
+// A whole turn's placements, with the draws, collapses, falls and wax eaters
+// waking that follow each.
+fn placement(c: &mut Criterion) {
+    let mut group = c.benchmark_group("placement");
+    for side in SIDES {
+        let scenario = half_placed(side);
+        let targets = [
+            TilePos { x: 0, y: 0 },
+            TilePos { x: side - 1, y: 0 },
+            TilePos { x: 0, y: side - 1 },
+        ];
+        group.bench_with_input(BenchmarkId::from_parameter(side), &targets, |b, targets| {
+            b.iter_batched(
+                || simulation(&scenario),
+                |mut sim| sim.run(targets.iter().map(|pos| Action::Place(*pos))),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+// Weighing up every placement of a tile, as the bots and hints do.
+fn bot_placements(c: &mut Criterion) {
+    let mut group = c.benchmark_group("bot_placements");
+    for side in SIDES {
+        let scenario = half_placed(side);
+        let map_type = TilemapType::Square;
+        let mut view = BoardView::empty(scenario.size, map_type);
+        for tile in &scenario.tiles {
+            let exits = rotated_passages(tile.kind, &map_type, tile.rotation);
+            view.insert_tile(tile.pos, tile.kind, exits);
+        }
+        group.bench_with_input(BenchmarkId::from_parameter(side), &view, |b, view| {
+            b.iter(|| placements(view, TileKind::TJunction).len())
+        });
+    }
+    group.finish();
+}
+
+// How far every cell is from one, as the bots and wax eaters measure it.
+fn distances(c: &mut Criterion) {
+    let mut group = c.benchmark_group("distances");
+    for side in SIDES {
+        let size = TilemapSize { x: side, y: side };
+        let center = TilePos {
+            x: side / 2,
+            y: side / 2,
+        };
+        group.bench_with_input(BenchmarkId::from_parameter(side), &size, |b, size| {
+            b.iter(|| board_distances([center], size, &TilemapType::Square, false).steps(&center))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, lighting, placement, bot_placements, distances);
+criterion_main!(benches);
@@ -0,0 +1,264 @@
+use bevy::prelude::*;
+
+use crate::locale::Strings;
+use crate::pawn::{Escaped, Keys};
+use crate::rules::{Bot, Player};
+use crate::save::Format;
+use crate::stats::GameTally;
+
+// A win quicker than this many seconds counts as swift.
+const SWIFT_SECONDS: f32 = 300.0;
+const MASON_TILES: u32 = 50;
+const KEY_RING_KEYS: u32 = 4;
+// How long an unlock stays on screen, in seconds.
+const TOAST_SECONDS: f32 = 4.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Achievement {
+    // Win a game.
+    FirstEscape,
+    // Win without losing a candle.
+    Untouched,
+    // Escape carrying all four keys.
+    KeyRing,
+    // Win in under five minutes.
+    Swift,
+    // Place fifty tiles in a single game.
+    Mason,
+}
+
+impl Achievement {
+    pub const ALL: [Achievement; 5] = [
+        Achievement::FirstEscape,
+        Achievement::Untouched,
+        Achievement::KeyRing,
+        Achievement::Swift,
+        Achievement::Mason,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Achievement::FirstEscape => "first-escape",
+            Achievement::Untouched => "untouched",
+            Achievement::KeyRing => "key-ring",
+            Achievement::Swift => "swift",
+            Achievement::Mason => "mason",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Achievement> {
+        Achievement::ALL
+            .into_iter()
+            .find(|achievement| achievement.name() == name)
+    }
+}
+
+// Each profile's achievements are kept beside its stats, one name per line.
+const ACHIEVEMENTS_FORMAT: Format = Format {
+    name: "achievements",
+    migrations: &[],
+};
+
+fn achievements_path(profile: &str) -> String {
+    format!("nightcage-achievements-{}.txt", profile)
+}
+
+// Achievements the profile has unlocked, in the order they were.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct Achievements {
+    pub profile: String,
+    pub unlocked: Vec<Achievement>,
+}
+
+impl Achievements {
+    // Loads the profile's unlocked achievements, with none unlocked when they
+    // can't be read, as on the web.
+    pub fn load(profile: &str) -> Achievements {
+        Achievements::read(profile).unwrap_or_else(|err| {
+            warn!("{}", err);
+            Achievements::none(profile)
+        })
+    }
+
+    fn none(profile: &str) -> Achievements {
+        Achievements {
+            profile: profile.to_string(),
+            ..default()
+        }
+    }
+
+    fn read(profile: &str) -> Result<Achievements, String> {
+        let mut achievements = Achievements::none(profile);
+        let path = achievements_path(profile);
+        let source = match std::fs::read_to_string(&path) {
+            Ok(source) => source,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(achievements),
+            Err(err) => return Err(format!("failed to read {}: {}", path, err)),
+        };
+        let source = ACHIEVEMENTS_FORMAT
+            .upgrade(&source)
+            .map_err(|err| format!("{}: {}", path, err))?;
+        for line in source.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let achievement = Achievement::from_name(line)
+                .ok_or_else(|| format!("unknown achievement {} in {}", line, path))?;
+            if !achievements.unlocked.contains(&achievement) {
+                achievements.unlocked.push(achievement);
+            }
+        }
+        Ok(achievements)
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let mut source = ACHIEVEMENTS_FORMAT.header();
+        for achievement in &self.unlocked {
+            source.push_str(&format!("{}\n", achievement.name()));
+        }
+        let path = achievements_path(&self.profile);
+        std::fs::write(&path, source).map_err(|err| format!("failed to write {}: {}", path, err))
+    }
+}
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct AchievementUnlocked(pub Achievement);
+
+// Unlocks come and go in a column at the top of the screen.
+#[derive(Component)]
+pub struct ToastList;
+
+#[derive(Component)]
+pub struct Toast {
+    timer: Timer,
+}
+
+pub fn spawn_toast_list(mut commands: Commands) {
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(8.0),
+                width: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(4.0),
+                ..default()
+            },
+            ..default()
+        },
+        ToastList,
+    ));
+}
+
+// Achievements are checked against the game in progress as it goes, and
+// against how it ended once somebody escapes. Only the local human earns them.
+pub fn unlock_achievements(
+    time: Res<Time>,
+    tally: Res<GameTally>,
+    mut achievements: ResMut<Achievements>,
+    players_q: Query<(&Player, &Keys, Has<Bot>)>,
+    mut escaped_events: EventReader<Escaped>,
+    mut unlocked_events: EventWriter<AchievementUnlocked>,
+) {
+    let escaped = escaped_events.read().next().copied();
+    if tally.ended() {
+        return;
+    }
+
+    let mut earned = Vec::new();
+    if tally.tiles_placed >= MASON_TILES {
+        earned.push(Achievement::Mason);
+    }
+    let winner = escaped.and_then(|escaped| {
+        players_q
+            .iter()
+            .find(|(player, _, bot)| player.number == escaped.player && !bot)
+    });
+    if let Some((_, keys, _)) = winner {
+        earned.push(Achievement::FirstEscape);
+        if tally.candles_lost == 0 {
+            earned.push(Achievement::Untouched);
+        }
+        if keys.0 >= KEY_RING_KEYS {
+            earned.push(Achievement::KeyRing);
+        }
+        if time.elapsed_seconds() - tally.started < SWIFT_SECONDS {
+            earned.push(Achievement::Swift);
+        }
+    }
+
+    earned.retain(|achievement| !achievements.unlocked.contains(achievement));
+    if earned.is_empty() {
+        return;
+    }
+    for achievement in earned {
+        achievements.unlocked.push(achievement);
+        unlocked_events.send(AchievementUnlocked(achievement));
+    }
+    if let Err(err) = achievements.save() {
+        warn!("{}", err);
+    }
+}
+
+pub fn show_unlock_toasts(
+    mut commands: Commands,
+    strings: Res<Strings>,
+    list_q: Query<Entity, With<ToastList>>,
+    mut unlocked_events: EventReader<AchievementUnlocked>,
+) {
+    for AchievementUnlocked(achievement) in unlocked_events.read() {
+        let text = strings.format(
+            "achievement-unlocked",
+            &[
+                (
+                    "name",
+                    strings.get(&format!("achievement-{}", achievement.name())),
+                ),
+                (
+                    "description",
+                    strings.get(&format!("achievement-{}-description", achievement.name())),
+                ),
+            ],
+        );
+        for list in list_q.iter() {
+            commands.entity(list).with_children(|list| {
+                list.spawn((
+                    NodeBundle {
+                        style: Style {
+                            padding: UiRect::all(Val::Px(8.0)),
+                            ..default()
+                        },
+                        background_color: Color::BLACK.with_a(0.8).into(),
+                        ..default()
+                    },
+                    Toast {
+                        timer: Timer::from_seconds(TOAST_SECONDS, TimerMode::Once),
+                    },
+                ))
+                .with_children(|toast| {
+                    toast.spawn(TextBundle::from_section(
+                        text.clone(),
+                        TextStyle {
+                            font_size: 20.0,
+                            color: Color::GOLD,
+                            ..default()
+                        },
+                    ));
+                });
+            });
+        }
+    }
+}
+
+pub fn expire_toasts(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut toasts_q: Query<(Entity, &mut Toast)>,
+) {
+    for (entity, mut toast) in toasts_q.iter_mut() {
+        if toast.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
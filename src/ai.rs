@@ -0,0 +1,283 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use bevy_ecs_tilemap::prelude::*;
+
+use crate::board::{lit_neighbors, neighbors, rotated_passages, rotation_steps, NextTileKind};
+use crate::inspect::BoardInspector;
+use crate::locale::Strings;
+use crate::path::{board_distances, Paths};
+use crate::pawn::{pawn_floor, Pawn};
+use crate::rules::{Bot, PlaceTile, Player, Turn, TurnBudget, TurnPassed};
+use crate::tiles::{Passages, TileKind};
+
+// Pause before each bot move so the other players can follow it.
+const BOT_THINK_SECONDS: f32 = 0.6;
+
+const CONNECTION_SCORE: f32 = 3.0;
+const OPENING_SCORE: f32 = 1.0;
+const BROKEN_CORRIDOR_SCORE: f32 = -2.0;
+const TARGET_DISTANCE_SCORE: f32 = -0.5;
+const MONSTER_SCORE: f32 = -4.0;
+
+// What the AI needs to know about the board to weigh up a placement.
+pub struct BoardView {
+    pub size: TilemapSize,
+    pub map_type: TilemapType,
+    // Whether the board's edges wrap round to the opposite ones.
+    pub wrap: bool,
+    tiles: HashMap<TilePos, (TileKind, Passages)>,
+    // Keys and gates, which the escape route has to reach.
+    targets: Vec<(TilePos, TileKind)>,
+    monsters: Vec<TilePos>,
+}
+
+impl BoardView {
+    pub fn on_floor(inspector: &BoardInspector, floor: u32) -> BoardView {
+        let board_config = inspector.board_config();
+        let mut view = BoardView::empty(board_config.size, board_config.map_type());
+        view.wrap = inspector.wraps();
+        for info in inspector.tiles_on(floor) {
+            view.insert_tile(info.pos, info.kind, info.exits);
+            if info.monster {
+                view.insert_monster(info.pos);
+            }
+        }
+        view
+    }
+
+    pub fn empty(size: TilemapSize, map_type: TilemapType) -> BoardView {
+        BoardView {
+            size,
+            map_type,
+            wrap: false,
+            tiles: HashMap::new(),
+            targets: Vec::new(),
+            monsters: Vec::new(),
+        }
+    }
+
+    pub fn insert_tile(&mut self, pos: TilePos, kind: TileKind, exits: Passages) {
+        if kind != TileKind::Empty {
+            self.tiles.insert(pos, (kind, exits));
+        }
+        if matches!(kind, TileKind::Key | TileKind::Gate) {
+            self.targets.push((pos, kind));
+        }
+    }
+
+    pub fn insert_monster(&mut self, pos: TilePos) {
+        self.monsters.push(pos);
+    }
+
+    fn passages_at(&self, pos: &TilePos) -> Option<Passages> {
+        self.tiles.get(pos).map(|(_, passages)| *passages)
+    }
+}
+
+// The main thing a placement has going for it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Reason {
+    ConnectsTo(TileKind),
+    JoinsCorridors(usize),
+    HeadsTowards(TileKind),
+    OpensCorridors,
+    AvoidsMonsters,
+}
+
+impl Reason {
+    pub fn describe(self, strings: &Strings) -> String {
+        match self {
+            Reason::ConnectsTo(kind) => {
+                strings.format("reason-connects-to", &[("tile", strings.tile(kind))])
+            }
+            Reason::JoinsCorridors(1) => strings.get("reason-joins-corridor"),
+            Reason::JoinsCorridors(count) => {
+                strings.format("reason-joins-corridors", &[("count", count.to_string())])
+            }
+            Reason::HeadsTowards(kind) => {
+                strings.format("reason-heads-towards", &[("tile", strings.tile(kind))])
+            }
+            Reason::OpensCorridors => strings.get("reason-opens-corridors"),
+            Reason::AvoidsMonsters => strings.get("reason-avoids-monsters"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Placement {
+    pub pos: TilePos,
+    pub rotation: u8,
+    pub score: f32,
+    pub reason: Reason,
+}
+
+// Every legal placement of `kind`, best first. Tiles go on empty cells, and
+// once the board has tiles on it, next to one of them.
+pub fn placements(view: &BoardView, kind: TileKind) -> Vec<Placement> {
+    let distances = |pos: TilePos| board_distances([pos], &view.size, &view.map_type, view.wrap);
+    let targets: Vec<(Paths, TileKind)> = view
+        .targets
+        .iter()
+        .map(|(pos, kind)| (distances(*pos), *kind))
+        .collect();
+    let monsters: Vec<Paths> = view.monsters.iter().map(|pos| distances(*pos)).collect();
+    let mut placements = Vec::new();
+    for y in 0..view.size.y {
+        for x in 0..view.size.x {
+            let pos = TilePos { x, y };
+            if view.tiles.contains_key(&pos) {
+                continue;
+            }
+            let touches_tile = neighbors(&pos, &view.size, &view.map_type, view.wrap)
+                .iter()
+                .any(|neighbor_pos| view.tiles.contains_key(neighbor_pos));
+            if !view.tiles.is_empty() && !touches_tile {
+                continue;
+            }
+
+            for rotation in 0..rotation_steps(&view.map_type) {
+                let passages = rotated_passages(kind, &view.map_type, rotation);
+                let (score, reason) = score(view, (&targets, &monsters), &pos, passages);
+                placements.push(Placement {
+                    pos,
+                    rotation,
+                    score,
+                    reason,
+                });
+            }
+        }
+    }
+    placements.sort_by(|a, b| b.score.total_cmp(&a.score));
+    placements
+}
+
+// Favours joining up lit corridors, heading for keys and gates, and keeping
+// away from monsters, going by the distances from each target and monster.
+fn score(
+    view: &BoardView,
+    (targets, monsters): (&[(Paths, TileKind)], &[Paths]),
+    pos: &TilePos,
+    passages: Passages,
+) -> (f32, Reason) {
+    let mut score = 0.0;
+
+    let lit = lit_neighbors(
+        pos,
+        passages,
+        &view.size,
+        &view.map_type,
+        view.wrap,
+        |neighbor_pos| view.passages_at(neighbor_pos),
+    );
+    let mut connections = 0;
+    let mut connected_target = None;
+    for neighbor_pos in &lit {
+        match view.tiles.get(neighbor_pos) {
+            Some((kind, _)) => {
+                score += CONNECTION_SCORE;
+                connections += 1;
+                if matches!(kind, TileKind::Key | TileKind::Gate) {
+                    connected_target = Some(*kind);
+                }
+            }
+            None => score += OPENING_SCORE,
+        }
+    }
+
+    // Corridors from neighbors that run into this tile's walls are wasted.
+    for neighbor_pos in neighbors(pos, &view.size, &view.map_type, view.wrap) {
+        let Some(neighbor_passages) = view.passages_at(&neighbor_pos) else {
+            continue;
+        };
+        let opens_here = lit_neighbors(
+            &neighbor_pos,
+            neighbor_passages,
+            &view.size,
+            &view.map_type,
+            view.wrap,
+            |_| None,
+        )
+        .contains(pos);
+        if opens_here && !lit.contains(&neighbor_pos) {
+            score += BROKEN_CORRIDOR_SCORE;
+        }
+    }
+
+    let nearest_target = targets
+        .iter()
+        .map(|(distances, kind)| (distance(distances, pos), *kind))
+        .min_by(|a, b| a.0.total_cmp(&b.0));
+    if let Some((target_distance, _)) = nearest_target {
+        score += TARGET_DISTANCE_SCORE * target_distance;
+    }
+    let mut monster_score = 0.0;
+    for distances in monsters {
+        monster_score += MONSTER_SCORE / (1.0 + distance(distances, pos));
+    }
+    score += monster_score;
+
+    let reason = match (connected_target, connections, nearest_target) {
+        (Some(kind), ..) => Reason::ConnectsTo(kind),
+        (None, connections, _) if connections > 0 => Reason::JoinsCorridors(connections),
+        (None, _, Some((_, kind))) => Reason::HeadsTowards(kind),
+        _ if !view.monsters.is_empty() && monster_score > MONSTER_SCORE / 4.0 => {
+            Reason::AvoidsMonsters
+        }
+        _ => Reason::OpensCorridors,
+    };
+    (score, reason)
+}
+
+// Every cell of the board can be reached from every other.
+fn distance(distances: &Paths, pos: &TilePos) -> f32 {
+    distances.steps(pos).unwrap_or(0) as f32
+}
+
+pub fn play_bot_turns(
+    time: Res<Time>,
+    mut thinking: Local<f32>,
+    turn: Res<Turn>,
+    mut players_q: Query<(&Player, Has<Bot>, &mut TurnBudget, &Pawn)>,
+    next_tile_kind: Res<NextTileKind>,
+    inspector: BoardInspector,
+    (mut place_events, mut passed_events): (EventWriter<PlaceTile>, EventWriter<TurnPassed>),
+) {
+    let bots = players_q
+        .iter()
+        .filter_map(|(player, bot, ..)| bot.then_some(player));
+    if !turn.is_bot(bots) {
+        *thinking = 0.0;
+        return;
+    }
+
+    *thinking += time.delta_seconds();
+    if *thinking < BOT_THINK_SECONDS {
+        return;
+    }
+    *thinking = 0.0;
+
+    // bots plan on the floor their own pawn is on, whichever is on show
+    let pawns = players_q.iter().map(|(player, .., pawn)| (player, pawn));
+    let floor = pawn_floor(pawns, turn.player);
+    let view = BoardView::on_floor(&inspector, floor);
+    match placements(&view, next_tile_kind.0).first() {
+        Some(placement) => place_events.send(PlaceTile {
+            pos: placement.pos,
+            floor,
+            kind: next_tile_kind.0,
+            rotation: placement.rotation,
+        }),
+        // the board is full, so there is nothing to do but pass
+        None => {
+            for (player, _, mut budget, _) in players_q.iter_mut() {
+                if player.number == turn.player {
+                    budget.remaining = 0;
+                    passed_events.send(TurnPassed {
+                        player: player.number,
+                    });
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use bevy::prelude::*;
+
+use crate::board::{lit_neighbors, neighbors, HoverEvent};
+use crate::event_log::EventLog;
+use crate::inspect::{BoardInspector, TileInfo};
+use crate::locale::Strings;
+use crate::tiles::TileKind;
+
+// Text descriptions of what happens on the board, for playing with a screen
+// reader. Announcements go to standard output one per line, so a terminal
+// screen reader reads them out as they arrive.
+#[derive(Resource, Default)]
+pub struct Announcer {
+    pub enabled: bool,
+    // Event log entries already announced.
+    seen: usize,
+}
+
+impl Announcer {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            ..default()
+        }
+    }
+
+    fn say(&self, line: &str) {
+        if self.enabled {
+            println!("{}", line);
+        }
+    }
+}
+
+// toggle announcements on pressing k
+pub fn toggle_announcements(
+    keyboard_input: Res<Input<KeyCode>>,
+    strings: Res<Strings>,
+    mut announcer: ResMut<Announcer>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::K) {
+        return;
+    }
+    // say it either way, so turning them off is confirmed too
+    let key = if announcer.enabled {
+        "announce-off"
+    } else {
+        "announce-on"
+    };
+    println!("{}", strings.get(key));
+    announcer.enabled = !announcer.enabled;
+}
+
+// Everything that makes it into the event log is read out as well.
+pub fn announce_events(log: Res<EventLog>, mut announcer: ResMut<Announcer>) {
+    if !log.is_changed() || announcer.seen == log.recorded {
+        return;
+    }
+    for entry in log.since(announcer.seen) {
+        announcer.say(entry);
+    }
+    announcer.seen = log.recorded;
+}
+
+pub fn announce_hovered_tile(
+    strings: Res<Strings>,
+    inspector: BoardInspector,
+    announcer: Res<Announcer>,
+    mut hover_events: EventReader<HoverEvent>,
+) {
+    for event in hover_events.read() {
+        let HoverEvent::Entered(pos) = event else {
+            continue;
+        };
+        if let Some(info) = inspector.tile_info(pos) {
+            announcer.say(&describe_tile(&info, &inspector, &strings));
+        }
+    }
+}
+
+// For example "row 3, column 5, unplaced, adjacent to lit corridor".
+pub fn describe_tile(info: &TileInfo, inspector: &BoardInspector, strings: &Strings) -> String {
+    let tile = match info.kind {
+        TileKind::Empty => strings.get("announce-unplaced"),
+        kind => strings.tile(kind),
+    };
+    let mut parts = vec![strings.format(
+        "announce-tile",
+        &[
+            ("row", info.pos.y.to_string()),
+            ("column", info.pos.x.to_string()),
+            ("tile", tile),
+        ],
+    )];
+
+    // a placed neighbour with a passage leading here
+    let board_config = inspector.board_config();
+    let map_type = board_config.map_type();
+    let adjacent_lit = neighbors(&info.pos, &board_config.size, &map_type, inspector.wraps())
+        .iter()
+        .filter_map(|neighbor_pos| inspector.tile_info(neighbor_pos))
+        .filter(|neighbor| neighbor.kind != TileKind::Empty)
+        .any(|neighbor| {
+            lit_neighbors(
+                &neighbor.pos,
+                neighbor.exits,
+                &board_config.size,
+                &map_type,
+                inspector.wraps(),
+                |_| None,
+            )
+            .contains(&info.pos)
+        });
+    if adjacent_lit {
+        parts.push(strings.get("announce-adjacent-lit"));
+    }
+    if info.monster {
+        parts.push(strings.get("announce-monster"));
+    }
+    parts.join(", ")
+}
@@ -0,0 +1,122 @@
+use bevy::prelude::*;
+
+use crate::daily::Daily;
+use crate::difficulty::Difficulty;
+use crate::rules::TileBag;
+use crate::share::Watching;
+use crate::stats::{GameTally, Stats};
+use crate::tiles::TileKind;
+
+// Games lost in a row before the assistant offers to ease the next one.
+const LOSSES_BEFORE_OFFER: u32 = 3;
+
+// Eased wax eaters stir this many times as long before they crawl out.
+const EASED_STIRRING: f32 = 2.0;
+
+// Changes to the rules of a single game that make it kinder.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Easing {
+    // Keys put into every fresh bag on top of the mix.
+    pub extra_keys: usize,
+    // Wax eaters stir for longer before crawling out, giving more warning.
+    pub slow_wax_eaters: bool,
+}
+
+impl Easing {
+    // How long a wax eater that would stir for `seconds` stirs in this game.
+    pub fn stirring_seconds(self, seconds: f32) -> f32 {
+        if self.slow_wax_eaters {
+            seconds * EASED_STIRRING
+        } else {
+            seconds
+        }
+    }
+}
+
+// What to ease the next game with after losing `losing_streak` games in a
+// row at `difficulty`, if anything. An extra key comes first, then slower wax
+// eaters as the losses go on, or straight away in a nightmare.
+pub fn suggest(losing_streak: u32, difficulty: Difficulty) -> Option<Easing> {
+    if losing_streak < LOSSES_BEFORE_OFFER {
+        return None;
+    }
+    Some(Easing {
+        extra_keys: 1,
+        slow_wax_eaters: losing_streak >= LOSSES_BEFORE_OFFER * 2
+            || difficulty == Difficulty::Nightmare,
+    })
+}
+
+// Looks back over each lost game and offers to ease the next one, but never
+// eases a game unasked.
+#[derive(Resource)]
+pub struct Assistant {
+    pub enabled: bool,
+    // Suggested after the last game, for the next one set up.
+    pub offer: Option<Easing>,
+    // Whether the offer was taken up.
+    pub accepted: bool,
+}
+
+impl Default for Assistant {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            offer: None,
+            accepted: false,
+        }
+    }
+}
+
+impl Assistant {
+    // Weighs up a game just finished at `difficulty`, won or lost after
+    // `losing_streak` losses in a row counting it, and offers to ease the next
+    // if it's worth it. Whatever was on offer before is withdrawn.
+    pub fn evaluate(&mut self, won: bool, losing_streak: u32, difficulty: Difficulty) {
+        self.accepted = false;
+        self.offer = None;
+        if self.enabled && !won {
+            self.offer = suggest(losing_streak, difficulty);
+        }
+    }
+
+    // The easing for the game being set up: what was offered, if it was taken
+    // up, and nothing otherwise. An offer only ever stands for one game.
+    pub fn take(&mut self) -> Easing {
+        let accepted = self.accepted;
+        self.accepted = false;
+        self.offer.take().filter(|_| accepted).unwrap_or_default()
+    }
+}
+
+// Weighs up the game just over for the summary to offer easing the next. A
+// daily challenge is played the same for everybody and a replay plays out
+// somebody else's game, so neither is ever eased.
+pub fn evaluate_game(
+    tally: Res<GameTally>,
+    stats: Res<Stats>,
+    difficulty: Res<Difficulty>,
+    (daily, watching): (Res<Daily>, Res<Watching>),
+    mut assistant: ResMut<Assistant>,
+) {
+    let Some(outcome) = tally.outcome else {
+        return;
+    };
+    if daily.challenge.is_some() || watching.is_replay() {
+        assistant.offer = None;
+        return;
+    }
+    assistant.evaluate(outcome.won, stats.losing_streak, *difficulty);
+}
+
+// Eases the game being set up if the offer was taken up, for that game only.
+pub fn ease_game(
+    mut assistant: ResMut<Assistant>,
+    mut easing: ResMut<Easing>,
+    mut bag: ResMut<TileBag>,
+) {
+    *easing = assistant.take();
+    if easing.extra_keys > 0 {
+        bag.extra.push((TileKind::Key, easing.extra_keys));
+    }
+}
@@ -0,0 +1,619 @@
+use std::fmt;
+use std::path::Path;
+
+use bevy::{app::AppExit, prelude::*};
+use bevy_ecs_tilemap::prelude::*;
+
+use crate::board::{
+    tile_rotation, BoardConfig, BoardShape, HexRotation, MonsterSpawn, NextTileKind, Rotation,
+    TileType,
+};
+use crate::darkness::Stirring;
+use crate::difficulty::Difficulty;
+use crate::floor::Floor;
+use crate::locale::Strings;
+use crate::nerve::Nerve;
+use crate::pawn::{Keys, Pawn};
+use crate::rng::Seed;
+use crate::rules::{HeldTiles, Player, PlayerConfig, TileBag, TilePlaced, Turn};
+use crate::save::Format;
+use crate::scenario::{ActiveScenario, Scenario, ScenarioTile};
+use crate::state::GameState;
+use crate::stats::{GameEnded, GameTally};
+use crate::tiles::{Quarter, TileKind};
+
+// Autosaves are kept in turn about this many files, so a save cut short by
+// a crash still leaves the one before it.
+const SLOTS: u64 = 3;
+
+// Every game is saved as each turn begins, one line to each thing in play,
+// with the scenario it was set up from, if any, at the end:
+//
+//     # nightcage autosave 1
+//     saved 12
+//     seed 1234
+//     difficulty normal
+//     board 7x7 square 1
+//     bots 1
+//     turn 0 5
+//     nerve 3
+//     pawn 0 3 4 0 1
+//     hand corner
+//     held straight
+//     bag corner straight dead-end
+//     discard key
+//     tile 3 2 0 straight 1
+//     monster 4 4 0
+//     scenario
+//     # nightcage scenario 1
+//     board 7x7 square
+//
+// The turn line has whose turn it is and how many have begun, pawn lines the
+// player, position, floor and keys carried, and tile and monster lines the
+// position and then the floor.
+const AUTOSAVE_FORMAT: Format = Format {
+    name: "autosave",
+    migrations: &[],
+};
+
+fn slot_path(profile: &str, slot: u64) -> String {
+    format!("nightcage-autosave-{}-{}.txt", profile, slot)
+}
+
+// Kept for as long as the game runs, so finding it at launch means the last
+// run never got to close.
+fn session_path(profile: &str) -> String {
+    format!("nightcage-session-{}.txt", profile)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SavedPawn {
+    pub player: usize,
+    pub pos: TilePos,
+    pub floor: u32,
+    pub keys: u32,
+}
+
+// A game as it stood at the start of a turn. The board is set up again from
+// the seed, board and scenario, and everything played since is laid over it.
+// The randomness isn't kept, so the bag is dealt in the order saved but
+// anything drawn after it runs out comes out differently.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Snapshot {
+    // Counts up across saves, so the newest slot is the highest.
+    pub saved: u64,
+    pub seed: Seed,
+    pub difficulty: Difficulty,
+    pub size: TilemapSize,
+    pub shape: BoardShape,
+    pub floors: u32,
+    pub bots: usize,
+    pub turn: usize,
+    pub turns: u32,
+    pub nerve: u32,
+    pub pawns: Vec<SavedPawn>,
+    pub hand: TileKind,
+    pub held: Vec<TileKind>,
+    pub bag: Vec<TileKind>,
+    pub discard: Vec<TileKind>,
+    pub tiles: Vec<ScenarioTile>,
+    pub monsters: Vec<(u32, TilePos)>,
+    pub scenario: Option<Scenario>,
+}
+
+impl Snapshot {
+    pub fn parse(source: &str) -> Result<Snapshot, String> {
+        let source = AUTOSAVE_FORMAT.upgrade(source)?;
+        let (game, scenario) = match source.split_once("\nscenario\n") {
+            Some((game, scenario)) => (game, Some(scenario)),
+            None => (source.as_str(), None),
+        };
+        let scenario = scenario
+            .map(Scenario::parse)
+            .transpose()
+            .map_err(|err| format!("scenario: {}", err))?;
+
+        let mut snapshot = Snapshot {
+            saved: 0,
+            seed: Seed(0),
+            difficulty: Difficulty::default(),
+            size: TilemapSize { x: 0, y: 0 },
+            shape: BoardShape::Square,
+            floors: 1,
+            bots: 0,
+            turn: 0,
+            turns: 0,
+            nerve: 0,
+            pawns: Vec::new(),
+            hand: TileKind::Empty,
+            held: Vec::new(),
+            bag: Vec::new(),
+            discard: Vec::new(),
+            tiles: Vec::new(),
+            monsters: Vec::new(),
+            scenario,
+        };
+        let mut has_board = false;
+        for (number, line) in game.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let at_line = |err: String| format!("line {}: {}", number + 1, err);
+            let size = snapshot.size;
+            let floors = snapshot.floors;
+            let cell = |x: &str, y: &str, floor: &str| {
+                parse_cell(x, y, floor, &size, floors).map_err(at_line)
+            };
+            match words.as_slice() {
+                ["saved", count] => snapshot.saved = parse_number(count).map_err(at_line)?,
+                ["seed", seed] => snapshot.seed = Seed(parse_number(seed).map_err(at_line)?),
+                ["difficulty", name] => {
+                    snapshot.difficulty = Difficulty::from_name(name)
+                        .ok_or_else(|| at_line(format!("unknown difficulty {}", name)))?
+                }
+                ["board", size, shape, floors] => {
+                    let (x, y) = size
+                        .split_once('x')
+                        .ok_or_else(|| at_line(format!("invalid board size {}", size)))?;
+                    snapshot.size = TilemapSize {
+                        x: parse_number(x).map_err(at_line)?,
+                        y: parse_number(y).map_err(at_line)?,
+                    };
+                    snapshot.shape = match *shape {
+                        "square" => BoardShape::Square,
+                        "hex" => BoardShape::Hex,
+                        _ => return Err(at_line(format!("invalid board shape {}", shape))),
+                    };
+                    snapshot.floors = parse_number(floors).map_err(at_line)?;
+                    has_board = true;
+                }
+                ["bots", bots] => snapshot.bots = parse_number(bots).map_err(at_line)?,
+                ["turn", player, turns] => {
+                    snapshot.turn = parse_number(player).map_err(at_line)?;
+                    snapshot.turns = parse_number(turns).map_err(at_line)?;
+                }
+                ["nerve", nerve] => snapshot.nerve = parse_number(nerve).map_err(at_line)?,
+                [..] if !has_board => return Err(at_line("expected the board first".to_string())),
+                ["pawn", player, x, y, floor, keys] => {
+                    let (floor, pos) = cell(x, y, floor)?;
+                    snapshot.pawns.push(SavedPawn {
+                        player: parse_number(player).map_err(at_line)?,
+                        pos,
+                        floor,
+                        keys: parse_number(keys).map_err(at_line)?,
+                    });
+                }
+                ["hand", kind] => snapshot.hand = parse_kind(kind).map_err(at_line)?,
+                ["held", kinds @ ..] => snapshot.held = parse_kinds(kinds).map_err(at_line)?,
+                ["bag", kinds @ ..] => snapshot.bag = parse_kinds(kinds).map_err(at_line)?,
+                ["discard", kinds @ ..] => {
+                    snapshot.discard = parse_kinds(kinds).map_err(at_line)?
+                }
+                ["tile", x, y, floor, kind, rotation] => {
+                    let (floor, pos) = cell(x, y, floor)?;
+                    snapshot.tiles.push(ScenarioTile {
+                        pos,
+                        floor,
+                        kind: parse_kind(kind).map_err(at_line)?,
+                        rotation: parse_number(rotation).map_err(at_line)?,
+                    });
+                }
+                ["monster", x, y, floor] => snapshot.monsters.push(cell(x, y, floor)?),
+                _ => return Err(at_line(format!("unrecognised entry {}", line))),
+            }
+        }
+        if !has_board {
+            return Err("missing board".to_string());
+        }
+        Ok(snapshot)
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value
+        .parse::<T>()
+        .map_err(|_| format!("invalid number {}", value))
+}
+
+fn parse_cell(
+    x: &str,
+    y: &str,
+    floor: &str,
+    size: &TilemapSize,
+    floors: u32,
+) -> Result<(u32, TilePos), String> {
+    match (x.parse::<u32>(), y.parse::<u32>(), floor.parse::<u32>()) {
+        (Ok(x), Ok(y), Ok(floor)) if x < size.x && y < size.y && floor < floors => {
+            Ok((floor, TilePos { x, y }))
+        }
+        _ => Err(format!("invalid position {} {} on floor {}", x, y, floor)),
+    }
+}
+
+fn parse_kind(name: &str) -> Result<TileKind, String> {
+    TileKind::from_name(name).ok_or_else(|| format!("unknown tile {}", name))
+}
+
+fn parse_kinds(names: &[&str]) -> Result<Vec<TileKind>, String> {
+    names.iter().map(|name| parse_kind(name)).collect()
+}
+
+fn kind_names(kinds: &[TileKind]) -> String {
+    kinds
+        .iter()
+        .map(|kind| format!(" {}", kind.name()))
+        .collect()
+}
+
+impl fmt::Display for Snapshot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let shape = match self.shape {
+            BoardShape::Square => "square",
+            BoardShape::Hex => "hex",
+        };
+        write!(f, "{}", AUTOSAVE_FORMAT.header())?;
+        writeln!(f, "saved {}", self.saved)?;
+        writeln!(f, "seed {}", self.seed.0)?;
+        writeln!(f, "difficulty {}", self.difficulty.name())?;
+        writeln!(
+            f,
+            "board {}x{} {} {}",
+            self.size.x, self.size.y, shape, self.floors
+        )?;
+        writeln!(f, "bots {}", self.bots)?;
+        writeln!(f, "turn {} {}", self.turn, self.turns)?;
+        writeln!(f, "nerve {}", self.nerve)?;
+        for pawn in &self.pawns {
+            writeln!(
+                f,
+                "pawn {} {} {} {} {}",
+                pawn.player, pawn.pos.x, pawn.pos.y, pawn.floor, pawn.keys
+            )?;
+        }
+        writeln!(f, "hand {}", self.hand.name())?;
+        writeln!(f, "held{}", kind_names(&self.held))?;
+        writeln!(f, "bag{}", kind_names(&self.bag))?;
+        writeln!(f, "discard{}", kind_names(&self.discard))?;
+        for tile in &self.tiles {
+            writeln!(
+                f,
+                "tile {} {} {} {} {}",
+                tile.pos.x,
+                tile.pos.y,
+                tile.floor,
+                tile.kind.name(),
+                tile.rotation
+            )?;
+        }
+        for (floor, pos) in &self.monsters {
+            writeln!(f, "monster {} {} {}", pos.x, pos.y, floor)?;
+        }
+        if let Some(scenario) = &self.scenario {
+            writeln!(f, "scenario")?;
+            write!(f, "{}", scenario)?;
+        }
+        Ok(())
+    }
+}
+
+// The autosave slots of a profile and whether the last run left a game to
+// pick up.
+#[derive(Resource, Default)]
+pub struct Autosave {
+    profile: String,
+    // Saves made, counting on from the newest found at launch. The next goes
+    // in slot `saved % SLOTS`.
+    saved: u64,
+    // The newest save of a run that ended without closing, while resuming it
+    // is on offer.
+    pub offer: Option<Snapshot>,
+    // A save being resumed, laid over the game once it's set up.
+    pending: Option<Snapshot>,
+}
+
+impl Autosave {
+    // Slots that can't be read are passed over, as they're only ever a
+    // fallback.
+    pub fn load(profile: &str) -> Autosave {
+        let newest = (0..SLOTS)
+            .filter_map(|slot| {
+                let path = slot_path(profile, slot);
+                let source = match std::fs::read_to_string(&path) {
+                    Ok(source) => source,
+                    Err(err) if err.kind() == std::io::ErrorKind::NotFound => return None,
+                    Err(err) => {
+                        warn!("failed to read {}: {}", path, err);
+                        return None;
+                    }
+                };
+                Snapshot::parse(&source)
+                    .map_err(|err| warn!("{}: {}", path, err))
+                    .ok()
+            })
+            .max_by_key(|snapshot| snapshot.saved);
+        let crashed = Path::new(&session_path(profile)).exists();
+        Autosave {
+            profile: profile.to_string(),
+            saved: newest.as_ref().map_or(0, |snapshot| snapshot.saved + 1),
+            offer: newest.filter(|_| crashed),
+            pending: None,
+        }
+    }
+
+    // Written to a file beside the slot and moved over it, so the slot is
+    // never left half written.
+    fn save(&mut self, mut snapshot: Snapshot) -> Result<(), String> {
+        snapshot.saved = self.saved;
+        let path = slot_path(&self.profile, self.saved % SLOTS);
+        let written = format!("{}.new", path);
+        std::fs::write(&written, snapshot.to_string())
+            .and_then(|_| std::fs::rename(&written, &path))
+            .map_err(|err| format!("failed to write {}: {}", path, err))?;
+        self.saved += 1;
+        Ok(())
+    }
+
+    // Whether the game being set up is a save picked up again.
+    pub fn resuming(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    fn clear(&self) {
+        for slot in 0..SLOTS {
+            let path = slot_path(&self.profile, slot);
+            match std::fs::remove_file(&path) {
+                Err(err) if err.kind() != std::io::ErrorKind::NotFound => {
+                    warn!("failed to remove {}: {}", path, err)
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+pub fn offered(autosave: Res<Autosave>) -> bool {
+    autosave.offer.is_some()
+}
+
+pub fn resuming(autosave: Res<Autosave>) -> bool {
+    autosave.resuming()
+}
+
+pub fn begin_session(autosave: Res<Autosave>) {
+    let path = session_path(&autosave.profile);
+    if let Err(err) = std::fs::write(&path, "") {
+        warn!("failed to write {}: {}", path, err);
+    }
+}
+
+pub fn end_session(autosave: Res<Autosave>, mut exit_events: EventReader<AppExit>) {
+    if exit_events.read().next().is_none() {
+        return;
+    }
+    let path = session_path(&autosave.profile);
+    if let Err(err) = std::fs::remove_file(&path) {
+        warn!("failed to remove {}: {}", path, err);
+    }
+}
+
+type SavedTileQuery = (
+    &'static TilePos,
+    &'static Floor,
+    Option<&'static TileType>,
+    &'static Rotation,
+    Option<&'static HexRotation>,
+    Has<MonsterSpawn>,
+    Has<Stirring>,
+);
+
+// A stirring wax eater is saved as though it had already crawled out.
+pub fn autosave_turn(
+    mut autosave: ResMut<Autosave>,
+    (turn, tally): (Res<Turn>, Res<GameTally>),
+    (nerve, bag, held, next_tile_kind): (
+        Res<Nerve>,
+        Res<TileBag>,
+        Res<HeldTiles>,
+        Res<NextTileKind>,
+    ),
+    (seed, difficulty, active_scenario): (Res<Seed>, Res<Difficulty>, Res<ActiveScenario>),
+    (board, players): (Res<BoardConfig>, Res<PlayerConfig>),
+    tiles_q: Query<SavedTileQuery>,
+    pawns_q: Query<(&Player, &Pawn, &Keys)>,
+) {
+    if !turn.is_changed() || tally.ended() {
+        return;
+    }
+    let mut pawns: Vec<SavedPawn> = pawns_q
+        .iter()
+        .map(|(player, pawn, keys)| SavedPawn {
+            player: player.number,
+            pos: pawn.pos,
+            floor: pawn.floor,
+            keys: keys.0,
+        })
+        .collect();
+    pawns.sort_by_key(|pawn| pawn.player);
+    let mut tiles = Vec::new();
+    let mut monsters = Vec::new();
+    for (pos, floor, tile_type, rotation, hex_rotation, monster, stirring) in tiles_q.iter() {
+        if let Some(tile_type) = tile_type {
+            tiles.push(ScenarioTile {
+                pos: *pos,
+                floor: floor.0,
+                kind: tile_type.kind,
+                rotation: tile_rotation(rotation, hex_rotation),
+            });
+        }
+        if monster || stirring {
+            monsters.push((floor.0, *pos));
+        }
+    }
+    // query order isn't stable, so saves of the same game come out the same
+    tiles.sort_by_key(|tile| (tile.floor, tile.pos.y, tile.pos.x));
+    monsters.sort_by_key(|(floor, pos)| (*floor, pos.y, pos.x));
+
+    let snapshot = Snapshot {
+        saved: 0,
+        seed: *seed,
+        difficulty: *difficulty,
+        size: board.size,
+        shape: board.shape,
+        floors: board.floors,
+        bots: players.bots,
+        turn: turn.player,
+        turns: tally.turns,
+        nerve: nerve.0,
+        pawns,
+        hand: next_tile_kind.0,
+        held: held.0.clone(),
+        bag: bag.tiles.clone(),
+        discard: bag.discard.clone(),
+        tiles,
+        monsters,
+        scenario: active_scenario.0.clone(),
+    };
+    if let Err(err) = autosave.save(snapshot) {
+        warn!("{}", err);
+    }
+}
+
+// A finished game has nothing to pick up again.
+pub fn clear_finished_game(autosave: Res<Autosave>, mut ended_events: EventReader<GameEnded>) {
+    if ended_events.read().next().is_some() {
+        autosave.clear();
+    }
+}
+
+#[derive(Component)]
+pub struct ResumeOfferText;
+
+pub fn spawn_resume_offer(mut commands: Commands, strings: Res<Strings>) {
+    commands.spawn((
+        TextBundle::from_section(
+            strings.get("autosave-offer"),
+            TextStyle {
+                font_size: 20.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(40.0),
+            left: Val::Px(8.0),
+            ..default()
+        }),
+        ResumeOfferText,
+    ));
+}
+
+// F9 takes up the offer, setting the saved game up afresh to lay the rest
+// of the save over. The offer stands until a tile is placed in the game
+// started in its place.
+pub fn resume_input(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut autosave: ResMut<Autosave>,
+    (mut seed, mut difficulty, mut active_scenario): (
+        ResMut<Seed>,
+        ResMut<Difficulty>,
+        ResMut<ActiveScenario>,
+    ),
+    (mut board, mut players): (ResMut<BoardConfig>, ResMut<PlayerConfig>),
+    mut next_state: ResMut<NextState<GameState>>,
+    mut placed_events: EventReader<TilePlaced>,
+) {
+    if placed_events.read().next().is_some() {
+        autosave.offer = None;
+    }
+    if !keyboard_input.just_pressed(KeyCode::F9) {
+        return;
+    }
+    let Some(snapshot) = autosave.offer.take() else {
+        return;
+    };
+    *seed = snapshot.seed;
+    *difficulty = snapshot.difficulty;
+    board.size = snapshot.size;
+    board.shape = snapshot.shape;
+    board.floors = snapshot.floors;
+    players.bots = snapshot.bots;
+    active_scenario.0 = snapshot.scenario.clone();
+    autosave.pending = Some(snapshot);
+    next_state.set(GameState::Setup);
+}
+
+pub fn withdraw_resume_offer(
+    mut commands: Commands,
+    autosave: Res<Autosave>,
+    text_q: Query<Entity, With<ResumeOfferText>>,
+) {
+    if autosave.offer.is_some() {
+        return;
+    }
+    for entity in text_q.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+// Lays a save being resumed over the game just set up from its seed.
+pub fn restore_pending(
+    mut commands: Commands,
+    mut autosave: ResMut<Autosave>,
+    (mut turn, mut nerve, mut tally): (ResMut<Turn>, ResMut<Nerve>, ResMut<GameTally>),
+    (mut bag, mut held, mut next_tile_kind): (
+        ResMut<TileBag>,
+        ResMut<HeldTiles>,
+        ResMut<NextTileKind>,
+    ),
+    tiles_q: Query<(Entity, &TilePos, &Floor)>,
+    mut pawns_q: Query<(&Player, &mut Pawn, &mut Keys)>,
+) {
+    let Some(snapshot) = autosave.pending.take() else {
+        return;
+    };
+    turn.player = snapshot.turn;
+    tally.turns = snapshot.turns;
+    nerve.0 = snapshot.nerve;
+    bag.tiles = snapshot.bag;
+    bag.discard = snapshot.discard;
+    held.0 = snapshot.held;
+    next_tile_kind.0 = snapshot.hand;
+
+    for (player, mut pawn, mut keys) in pawns_q.iter_mut() {
+        if let Some(saved) = snapshot
+            .pawns
+            .iter()
+            .find(|saved| saved.player == player.number)
+        {
+            pawn.pos = saved.pos;
+            pawn.floor = saved.floor;
+            keys.0 = saved.keys;
+        }
+    }
+
+    for (tile_entity, pos, floor) in tiles_q.iter() {
+        let mut tile = commands.entity(tile_entity);
+        tile.remove::<(TileType, MonsterSpawn, Stirring)>()
+            .insert(Rotation::default());
+        if snapshot.shape == BoardShape::Hex {
+            tile.insert(HexRotation::default());
+        }
+        let saved = snapshot
+            .tiles
+            .iter()
+            .find(|saved| saved.floor == floor.0 && saved.pos == *pos);
+        if let Some(saved) = saved {
+            tile.insert(TileType { kind: saved.kind });
+            match snapshot.shape {
+                BoardShape::Square => tile.insert(Rotation(Quarter::from_turns(saved.rotation))),
+                BoardShape::Hex => tile.insert(HexRotation(saved.rotation % 6)),
+            };
+        }
+        if snapshot.monsters.contains(&(floor.0, *pos)) {
+            tile.insert(MonsterSpawn);
+        }
+    }
+}
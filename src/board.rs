@@ -0,0 +1,343 @@
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+use bevy_ecs_tilemap::helpers::hex_grid::neighbors::{HexDirection, HexNeighbors};
+use bevy_ecs_tilemap::prelude::*;
+
+use crate::tiles::{Passages, Quarter, Side, TileKind};
+
+// The tile artwork, a grid of TEXTURE_TILE_SIZE squares in texture index order.
+pub const TILES_TEXTURE: &str = "tiles.png";
+
+// Size in pixels of a single tile in tiles.png.
+pub const TEXTURE_TILE_SIZE: f32 = 128.0;
+
+// Offset rows keep a hex board roughly rectangular, like the square one.
+const HEX_COORD_SYSTEM: HexCoordSystem = HexCoordSystem::RowOdd;
+
+const HEX_DIRECTIONS: [HexDirection; 6] = [
+    HexDirection::Zero,
+    HexDirection::One,
+    HexDirection::Two,
+    HexDirection::Three,
+    HexDirection::Four,
+    HexDirection::Five,
+];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BoardShape {
+    #[default]
+    Square,
+    Hex,
+}
+
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct BoardConfig {
+    pub size: TilemapSize,
+    // Size of a tile in world units. Tiles are scaled from the texture to fit.
+    pub tile_size: f32,
+    pub shape: BoardShape,
+    // Floors stacked on top of each other, joined by stairs.
+    pub floors: u32,
+}
+
+impl Default for BoardConfig {
+    fn default() -> Self {
+        Self {
+            size: TilemapSize { x: 7, y: 7 },
+            tile_size: TEXTURE_TILE_SIZE,
+            shape: BoardShape::Square,
+            floors: 1,
+        }
+    }
+}
+
+impl BoardConfig {
+    pub fn map_type(&self) -> TilemapType {
+        match self.shape {
+            BoardShape::Square => TilemapType::Square,
+            BoardShape::Hex => TilemapType::Hexagon(HEX_COORD_SYSTEM),
+        }
+    }
+
+    pub fn world_size(&self) -> Vec2 {
+        match self.shape {
+            BoardShape::Square => Vec2::new(
+                self.size.x as f32 * self.tile_size,
+                self.size.y as f32 * self.tile_size,
+            ),
+            // Odd rows are shifted half a tile across and rows overlap by a quarter.
+            BoardShape::Hex => Vec2::new(
+                (self.size.x as f32 + 0.5) * self.tile_size,
+                (0.75 * (self.size.y as f32 - 1.0) + 1.0) * self.tile_size,
+            ),
+        }
+    }
+
+    // Transform that centers the tilemap on the origin and scales the
+    // texture-sized grid up or down to the configured tile size.
+    pub fn tilemap_transform(
+        &self,
+        grid_size: &TilemapGridSize,
+        map_type: &TilemapType,
+    ) -> Transform {
+        let scale = self.tile_size / grid_size.x;
+        let mut transform = get_tilemap_center_transform(&self.size, grid_size, map_type, 0.0);
+        transform.translation *= scale;
+        transform.scale = Vec3::splat(scale);
+        transform
+    }
+}
+
+// The tile under the cursor.
+#[derive(Component)]
+pub struct HighlightedLabel;
+
+// Tiles reached by the candle's light.
+#[derive(Component)]
+pub struct IlluminatedLabel;
+
+// The tile under the cursor and the tiles the candle would light from it, on
+// the floor on show. It only changes when the cursor moves onto another tile,
+// something under the light does or another floor is shown, and the labels
+// above follow it.
+#[derive(Resource, Clone, Default, PartialEq)]
+pub struct HoverState {
+    pub floor: u32,
+    pub hovered: Option<TilePos>,
+    pub illuminated: HashSet<TilePos>,
+}
+
+// Sent as the labels follow the hover state.
+#[derive(Event, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HoverEvent {
+    Entered(TilePos),
+    Left(TilePos),
+    Lit(TilePos),
+    Unlit(TilePos),
+}
+
+// A tile that has been placed on the board.
+#[derive(Component)]
+pub struct TileType {
+    pub kind: TileKind,
+}
+
+// How much of a tile can be seen through the dark. Tiles remember being
+// explored so they stay dimly visible once the light moves on.
+#[derive(Component, Default)]
+pub struct TileFog {
+    pub explored: bool,
+    pub brightness: f32,
+}
+
+// Kinds previously placed on a cell, oldest first.
+#[derive(Component, Default)]
+pub struct TileHistory(pub Vec<TileKind>);
+
+// Numbers of the players whose pawns stand on a cell, lowest first.
+#[derive(Component, Default)]
+pub struct Occupants(pub Vec<usize>);
+
+// Where a player's pawn begins a scenario.
+#[derive(Component)]
+pub struct PlayerStart;
+
+// Where a monster appears when a scenario begins.
+#[derive(Component)]
+pub struct MonsterSpawn;
+
+// The kind of tile that will be placed next.
+#[derive(Resource)]
+pub struct NextTileKind(pub TileKind);
+impl Default for NextTileKind {
+    fn default() -> Self {
+        Self(TileKind::PLACEABLE[0])
+    }
+}
+
+impl NextTileKind {
+    pub fn next(&mut self) {
+        self.0 = self.0.next();
+    }
+}
+
+// How a tile is turned on a square board. Its passages, the light through it
+// and the flips its artwork is drawn with all follow from this. Every cell
+// has one, though hex boards turn tiles with `HexRotation` instead.
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Rotation(pub Quarter);
+
+// Rotation of a tile on a hex board, in sixth turns.
+#[derive(Component, Default)]
+pub struct HexRotation(pub u8);
+
+// Passages of a tile as it is currently turned on the board.
+pub fn tile_passages(
+    kind: TileKind,
+    rotation: &Rotation,
+    hex_rotation: Option<&HexRotation>,
+) -> Passages {
+    match hex_rotation {
+        Some(hex_rotation) => kind.hex_passages().rotated_hex(hex_rotation.0),
+        None => kind.passages_with(rotation.0),
+    }
+}
+
+// How many steps of a turn a tile is turned on either kind of board, as
+// scenarios and placements count them.
+pub fn tile_rotation(rotation: &Rotation, hex_rotation: Option<&HexRotation>) -> u8 {
+    hex_rotation.map_or(rotation.0.turns(), |hex_rotation| hex_rotation.0)
+}
+
+// Turns a tile one step clockwise, a quarter turn or on hex boards a sixth.
+pub fn turn_tile(rotation: &mut Rotation, hex_rotation: Option<&mut HexRotation>) {
+    match hex_rotation {
+        Some(hex_rotation) => hex_rotation.0 = (hex_rotation.0 + 1) % 6,
+        None => rotation.0 = rotation.0.next(),
+    }
+}
+
+// Cells the light of a candle at `pos` reaches: out through the candle's open
+// sides and into neighbors that are either empty or open back towards it.
+// With `wrap`, corridors leading off one edge of the board come back on at the
+// opposite one, as under the wrap-around house rule.
+pub fn lit_neighbors(
+    pos: &TilePos,
+    candle: Passages,
+    map_size: &TilemapSize,
+    map_type: &TilemapType,
+    wrap: bool,
+    passages_at: impl Fn(&TilePos) -> Option<Passages>,
+) -> Vec<TilePos> {
+    let lit: Vec<TilePos> = match map_type {
+        TilemapType::Hexagon(coord_system) => {
+            let neighbors = HexNeighbors::get_neighboring_positions(pos, map_size, coord_system);
+            HEX_DIRECTIONS
+                .into_iter()
+                .filter_map(|direction| {
+                    let side = direction as u8;
+                    let neighbor_pos = match neighbors.get(direction) {
+                        Some(neighbor_pos) => *neighbor_pos,
+                        None if wrap => wrapped_hex_neighbor(pos, side, map_size)?,
+                        None => return None,
+                    };
+                    let open = candle.contains_hex_direction(side)
+                        && passages_at(&neighbor_pos)
+                            .is_none_or(|passages| passages.contains_hex_direction((side + 3) % 6));
+                    open.then_some(neighbor_pos)
+                })
+                .collect()
+        }
+        _ => candle
+            .sides()
+            .filter_map(|side| {
+                let neighbor_pos = if wrap {
+                    side.wrapped_neighbor(pos, map_size)
+                } else {
+                    side.neighbor(pos, map_size)?
+                };
+                let open = passages_at(&neighbor_pos)
+                    .is_none_or(|passages| candle.connects(side, passages));
+                open.then_some(neighbor_pos)
+            })
+            .collect(),
+    };
+    // a board a single cell across wraps round onto the same cell
+    lit.into_iter()
+        .filter(|neighbor_pos| neighbor_pos != pos)
+        .collect()
+}
+
+// Every cell sharing a side with `pos`.
+pub fn neighbors(
+    pos: &TilePos,
+    map_size: &TilemapSize,
+    map_type: &TilemapType,
+    wrap: bool,
+) -> Vec<TilePos> {
+    let all = match map_type {
+        TilemapType::Hexagon(_) => Passages::HEX_ALL,
+        _ => Passages::ALL,
+    };
+    lit_neighbors(pos, all, map_size, map_type, wrap, |_| None)
+}
+
+// The cell off the edge of a hex board in `direction` from `pos`, brought
+// back on at the opposite edge. Odd rows sit half a cell to the right, so the
+// top and bottom rows only line up to wrap onto each other when there's an
+// even number of rows; otherwise the board only wraps from side to side.
+fn wrapped_hex_neighbor(pos: &TilePos, direction: u8, map_size: &TilemapSize) -> Option<TilePos> {
+    let (x, y) = (pos.x as i64, pos.y as i64);
+    let shift = y & 1;
+    // sixth turns counterclockwise from east
+    let (x, y) = match direction {
+        0 => (x + 1, y),
+        1 => (x + shift, y + 1),
+        2 => (x + shift - 1, y + 1),
+        3 => (x - 1, y),
+        4 => (x + shift - 1, y - 1),
+        _ => (x + shift, y - 1),
+    };
+    let (width, height) = (map_size.x as i64, map_size.y as i64);
+    if !(0..height).contains(&y) && height % 2 == 1 {
+        return None;
+    }
+    Some(TilePos {
+        x: x.rem_euclid(width) as u32,
+        y: y.rem_euclid(height) as u32,
+    })
+}
+
+// Number of distinct ways a tile can be turned on the board.
+pub fn rotation_steps(map_type: &TilemapType) -> u8 {
+    match map_type {
+        TilemapType::Hexagon(_) => 6,
+        _ => 4,
+    }
+}
+
+// Passages of `kind` turned by `rotation` steps, quarter turns on square
+// boards and sixth turns on hex boards.
+pub fn rotated_passages(kind: TileKind, map_type: &TilemapType, rotation: u8) -> Passages {
+    match map_type {
+        TilemapType::Hexagon(_) => kind.hex_passages().rotated_hex(rotation),
+        _ => kind.passages().rotated(rotation),
+    }
+}
+
+// World position of the center of the tile at `pos`.
+pub fn tile_center(
+    pos: &TilePos,
+    grid_size: &TilemapGridSize,
+    map_type: &TilemapType,
+    map_transform: &Transform,
+) -> Vec2 {
+    let center = pos.center_in_world(grid_size, map_type).extend(0.0);
+    map_transform.transform_point(center).xy()
+}
+
+// Unit vector from a hex tile's center out through the middle of one of its sides.
+pub fn hex_direction_vector(direction: u8) -> Vec2 {
+    let angle = direction as f32 * std::f32::consts::FRAC_PI_3;
+    Vec2::new(angle.cos(), angle.sin())
+}
+
+// Unit vectors from a tile's center out through each of its open sides.
+pub fn passage_vectors(passages: Passages, map_type: &TilemapType) -> Vec<Vec2> {
+    match map_type {
+        TilemapType::Hexagon(_) => passages
+            .hex_directions()
+            .map(hex_direction_vector)
+            .collect(),
+        _ => passages
+            .sides()
+            .map(|side| match side {
+                Side::North => Vec2::Y,
+                Side::East => Vec2::X,
+                Side::South => Vec2::NEG_Y,
+                Side::West => Vec2::NEG_X,
+            })
+            .collect(),
+    }
+}
@@ -1,45 +1,390 @@
-use bevy::{input::Input, math::Vec3, prelude::*, render::camera::Camera};
+use bevy::{
+    core_pipeline::clear_color::ClearColorConfig,
+    input::Input,
+    math::Vec3,
+    prelude::*,
+    render::camera::{Camera, Viewport},
+    window::PrimaryWindow,
+};
+use bevy_ecs_tilemap::prelude::*;
 
+use crate::board::{tile_center, BoardConfig};
+use crate::floor::ShownFloor;
+use crate::gesture::Gesture;
+use crate::hand::HandDrag;
+use crate::pawn::Pawn;
+use crate::rules::Player;
+
+// Top speed of the view, in screen pixels a second so it crosses the screen
+// as quickly however far it's zoomed out.
+const PAN_SPEED: f32 = 500.0;
+// How quickly the view gets up to speed and slows down again, and how quickly
+// it zooms in and out and glides back to the middle of the board.
+const PAN_EASING: f32 = 10.0;
+const ZOOM_EASING: f32 = 10.0;
+const RECENTER_EASING: f32 = 6.0;
+// The view zooms by up to this factor a second.
+const ZOOM_SPEED: f32 = 3.0;
+const MIN_ZOOM: f32 = 0.5;
+
+// With --split-screen two players sharing the machine each get half the
+// window, side by side. The keyboard and mouse move the left view and the
+// first gamepad the right one, and each view can follow its player's pawn.
+#[derive(Resource, Default)]
+pub struct SplitScreen(pub bool);
+
+// The view moved with the keyboard and mouse, and the only one unless the
+// screen is split. The hud is drawn over it.
+#[derive(Component)]
+pub struct MainView;
+
+// The right half of a split screen, moved with a gamepad.
+#[derive(Component)]
+pub struct GamepadView;
+
+// A view that keeps a player's pawn in the middle while `on`.
+#[derive(Component)]
+pub struct FollowPawn {
+    pub player: usize,
+    pub on: bool,
+}
+
+// How the view is moving, carried from frame to frame so it can ease in and
+// out.
+#[derive(Default)]
+pub struct CameraMotion {
+    // Screen pixels a second.
+    velocity: Vec2,
+    // From -1 zooming in at full speed to 1 zooming out.
+    zoom: f32,
+    recentering: bool,
+}
+
+pub fn spawn_views(mut commands: Commands, split_screen: Res<SplitScreen>) {
+    if !split_screen.0 {
+        commands.spawn((Camera2dBundle::default(), MainView));
+        return;
+    }
+    commands.spawn((
+        Camera2dBundle::default(),
+        MainView,
+        FollowPawn {
+            player: 0,
+            on: true,
+        },
+    ));
+    commands.spawn((
+        Camera2dBundle {
+            camera: Camera {
+                order: 1,
+                ..default()
+            },
+            // clearing would wipe the whole window rather than just this half
+            camera_2d: Camera2d {
+                clear_color: ClearColorConfig::None,
+            },
+            ..default()
+        },
+        UiCameraConfig { show_ui: false },
+        GamepadView,
+        FollowPawn {
+            player: 1,
+            on: true,
+        },
+    ));
+}
+
+// The views of a split screen are fitted to the halves of the window again
+// whenever it changes size.
+pub fn lay_out_split_views(
+    window_q: Query<&Window, With<PrimaryWindow>>,
+    mut laid_out: Local<UVec2>,
+    mut views_q: Query<(&mut Camera, Has<GamepadView>)>,
+) {
+    let Ok(window) = window_q.get_single() else {
+        return;
+    };
+    let size = UVec2::new(window.physical_width(), window.physical_height());
+    // a minimized window has no room for either half
+    if size == *laid_out || size.x < 2 || size.y == 0 {
+        return;
+    }
+    *laid_out = size;
+    let half = UVec2::new(size.x / 2, size.y);
+    for (mut camera, gamepad) in views_q.iter_mut() {
+        camera.viewport = Some(Viewport {
+            physical_position: UVec2::new(if gamepad { half.x } else { 0 }, 0),
+            physical_size: half,
+            ..default()
+        });
+    }
+}
+
+pub fn split_screen(split_screen: Res<SplitScreen>) -> bool {
+    split_screen.0
+}
+
+// The world position under a window position, seen through whichever view it
+// falls in. A view without a viewport of its own fills the window.
+pub fn window_to_world<'a>(
+    views: impl IntoIterator<Item = (&'a Camera, &'a GlobalTransform)>,
+    window_pos: Vec2,
+) -> Option<Vec2> {
+    views.into_iter().find_map(|(camera, transform)| {
+        let viewport = camera.logical_viewport_rect()?;
+        if !viewport.contains(window_pos) {
+            return None;
+        }
+        camera.viewport_to_world_2d(transform, window_pos - viewport.min)
+    })
+}
+
+// The fraction of the way to a target covered in `delta` seconds when closing
+// on it at `rate`, which comes to the same whatever the frame rate.
+fn ease(rate: f32, delta: f32) -> f32 {
+    1.0 - (-rate * delta).exp()
+}
+
+type ViewQuery = (
+    &'static mut Transform,
+    &'static mut OrthographicProjection,
+    Option<&'static mut FollowPawn>,
+);
+
+// wasd moves the view, z and x zoom it out and in, and home glides it back to
+// the middle of the board until it's moved again
 pub fn movement(
     time: Res<Time>,
     keyboard_input: Res<Input<KeyCode>>,
-    mut query: Query<(&mut Transform, &mut OrthographicProjection), With<Camera>>,
+    board_config: Res<BoardConfig>,
+    mut motion: Local<CameraMotion>,
+    mut query: Query<ViewQuery, With<MainView>>,
 ) {
-    for (mut transform, mut ortho) in query.iter_mut() {
-        let mut direction = Vec3::ZERO;
+    let delta = time.delta_seconds();
+    let mut direction = Vec2::ZERO;
 
-        if keyboard_input.pressed(KeyCode::A) {
-            direction -= Vec3::new(1.0, 0.0, 0.0);
-        }
+    if keyboard_input.pressed(KeyCode::A) {
+        direction -= Vec2::X;
+    }
 
-        if keyboard_input.pressed(KeyCode::D) {
-            direction += Vec3::new(1.0, 0.0, 0.0);
-        }
+    if keyboard_input.pressed(KeyCode::D) {
+        direction += Vec2::X;
+    }
+
+    if keyboard_input.pressed(KeyCode::W) {
+        direction += Vec2::Y;
+    }
+
+    if keyboard_input.pressed(KeyCode::S) {
+        direction -= Vec2::Y;
+    }
+
+    let mut zoom = 0.0;
 
-        if keyboard_input.pressed(KeyCode::W) {
-            direction += Vec3::new(0.0, 1.0, 0.0);
+    if keyboard_input.pressed(KeyCode::Z) {
+        zoom += 1.0;
+    }
+
+    if keyboard_input.pressed(KeyCode::X) {
+        zoom -= 1.0;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Home) {
+        motion.recentering = true;
+    }
+    let moved = direction != Vec2::ZERO || motion.recentering;
+    if direction != Vec2::ZERO {
+        motion.recentering = false;
+    }
+
+    let target = direction.normalize_or_zero() * PAN_SPEED;
+    motion.velocity = motion.velocity.lerp(target, ease(PAN_EASING, delta));
+    motion.zoom += (zoom - motion.zoom) * ease(ZOOM_EASING, delta);
+
+    for (mut transform, mut ortho, follow) in query.iter_mut() {
+        if let Some(mut follow) = follow.filter(|_| moved) {
+            follow.on = false;
+        }
+        ortho.scale = (ortho.scale * ZOOM_SPEED.powf(motion.zoom * delta)).max(MIN_ZOOM);
+        transform.translation += (motion.velocity * ortho.scale * delta).extend(0.0);
+        if motion.recentering {
+            let center = transform.translation.truncate();
+            let center = center.lerp(Vec2::ZERO, ease(RECENTER_EASING, delta));
+            transform.translation.x = center.x;
+            transform.translation.y = center.y;
+            // close enough to leave the view for dragging again
+            if center.length() < 1.0 {
+                motion.recentering = false;
+            }
         }
+        keep_over_board(&mut transform, &board_config);
+    }
+}
+
+// Keep the view centered somewhere over the board.
+fn keep_over_board(transform: &mut Transform, board_config: &BoardConfig) {
+    let z = transform.translation.z;
+    let half_size = (board_config.world_size() / 2.0).extend(0.0);
+    transform.translation = transform.translation.clamp(-half_size, half_size);
+    // Important! We need to restore the Z values when moving the camera around.
+    // Bevy has a specific camera setup and this can mess with how our layers are shown.
+    transform.translation.z = z;
+}
 
-        if keyboard_input.pressed(KeyCode::S) {
-            direction -= Vec3::new(0.0, 1.0, 0.0);
+// Dragging with the left mouse button pulls the board along under the cursor.
+// Drags with shift or alt held are the editor's and the ping's, and the tile in
+// hand is dragged to the board rather than the board to it.
+pub fn drag_camera(
+    mut gesture_events: EventReader<Gesture>,
+    keyboard_input: Res<Input<KeyCode>>,
+    (drag, board_config): (Res<HandDrag>, Res<BoardConfig>),
+    mut query: Query<ViewQuery, With<MainView>>,
+) {
+    let modified = keyboard_input.any_pressed([
+        KeyCode::ShiftLeft,
+        KeyCode::ShiftRight,
+        KeyCode::AltLeft,
+        KeyCode::AltRight,
+    ]);
+    for gesture in gesture_events.read() {
+        let Gesture::Drag {
+            button: MouseButton::Left,
+            delta,
+        } = *gesture
+        else {
+            continue;
+        };
+        if modified || drag.dragging {
+            continue;
         }
+        for (mut transform, ortho, follow) in query.iter_mut() {
+            if let Some(mut follow) = follow {
+                follow.on = false;
+            }
+            // window pixels count down the screen and the world counts up it
+            transform.translation += Vec3::new(-delta.x, delta.y, 0.0) * ortho.scale;
+            keep_over_board(&mut transform, &board_config);
+        }
+    }
+}
 
-        if keyboard_input.pressed(KeyCode::Z) {
-            ortho.scale += 0.1;
+// Double-clicking centers the view on the spot clicked.
+pub fn center_on_double_click(
+    mut gesture_events: EventReader<Gesture>,
+    board_config: Res<BoardConfig>,
+    window_q: Query<&Window, With<PrimaryWindow>>,
+    views_q: Query<(&Camera, &GlobalTransform)>,
+    mut query: Query<ViewQuery, With<MainView>>,
+) {
+    let double_clicked = gesture_events
+        .read()
+        .any(|gesture| *gesture == Gesture::DoubleClick(MouseButton::Left));
+    let Some(cursor) = window_q
+        .get_single()
+        .ok()
+        .and_then(|window| window.cursor_position())
+        .filter(|_| double_clicked)
+    else {
+        return;
+    };
+    let Some(pos) = window_to_world(views_q.iter(), cursor) else {
+        return;
+    };
+    for (mut transform, _, follow) in query.iter_mut() {
+        if let Some(mut follow) = follow {
+            follow.on = false;
         }
+        transform.translation.x = pos.x;
+        transform.translation.y = pos.y;
+        keep_over_board(&mut transform, &board_config);
+    }
+}
 
-        if keyboard_input.pressed(KeyCode::X) {
-            ortho.scale -= 0.1;
+// The left stick moves the gamepad's view and pushing the right stick up or
+// down zooms it in or out. The sticks already come and go gradually, so
+// there's no easing.
+pub fn gamepad_movement(
+    time: Res<Time>,
+    (gamepads, axes): (Res<Gamepads>, Res<Axis<GamepadAxis>>),
+    board_config: Res<BoardConfig>,
+    mut query: Query<ViewQuery, (With<GamepadView>, Without<MainView>)>,
+) {
+    let Some(gamepad) = gamepads.iter().next() else {
+        return;
+    };
+    let axis = |axis_type| {
+        axes.get(GamepadAxis::new(gamepad, axis_type))
+            .unwrap_or(0.0)
+    };
+    let direction = Vec2::new(
+        axis(GamepadAxisType::LeftStickX),
+        axis(GamepadAxisType::LeftStickY),
+    )
+    .clamp_length_max(1.0);
+    let zoom = -axis(GamepadAxisType::RightStickY);
+    if direction == Vec2::ZERO && zoom == 0.0 {
+        return;
+    }
+
+    let delta = time.delta_seconds();
+    for (mut transform, mut ortho, follow) in query.iter_mut() {
+        if let Some(mut follow) = follow.filter(|_| direction != Vec2::ZERO) {
+            follow.on = false;
         }
+        ortho.scale = (ortho.scale * ZOOM_SPEED.powf(zoom * delta)).max(MIN_ZOOM);
+        transform.translation += (direction * PAN_SPEED * ortho.scale * delta).extend(0.0);
+        keep_over_board(&mut transform, &board_config);
+    }
+}
 
-        if ortho.scale < 0.5 {
-            ortho.scale = 0.5;
+// f sets the main view following its player's pawn again, or lets it go, and
+// the gamepad's north button does the same for its view.
+pub fn toggle_follow(
+    keyboard_input: Res<Input<KeyCode>>,
+    (gamepads, gamepad_input): (Res<Gamepads>, Res<Input<GamepadButton>>),
+    mut views_q: Query<(&mut FollowPawn, Has<GamepadView>)>,
+) {
+    let main = keyboard_input.just_pressed(KeyCode::F);
+    let gamepad = gamepads.iter().next().is_some_and(|gamepad| {
+        gamepad_input.just_pressed(GamepadButton::new(gamepad, GamepadButtonType::North))
+    });
+    for (mut follow, of_gamepad) in views_q.iter_mut() {
+        if if of_gamepad { gamepad } else { main } {
+            follow.on = !follow.on;
         }
+    }
+}
+
+type FollowingViewQuery = (&'static mut Transform, &'static FollowPawn);
 
-        let z = transform.translation.z;
-        transform.translation += time.delta_seconds() * direction * 500.;
-        // Important! We need to restore the Z values when moving the camera around.
-        // Bevy has a specific camera setup and this can mess with how our layers are shown.
-        transform.translation.z = z;
+// Views following a pawn glide over to keep it in the middle, wherever it's
+// walked or fallen to. A pawn on another floor is followed all the same.
+pub fn follow_pawns(
+    time: Res<Time>,
+    board_config: Res<BoardConfig>,
+    tilemap_q: Query<(&TilemapGridSize, &TilemapType, &Transform), With<ShownFloor>>,
+    pawns_q: Query<(&Player, &Pawn)>,
+    mut views_q: Query<FollowingViewQuery, Without<ShownFloor>>,
+) {
+    let Ok((grid_size, map_type, map_transform)) = tilemap_q.get_single() else {
+        return;
+    };
+    let delta = time.delta_seconds();
+    for (mut transform, follow) in views_q.iter_mut() {
+        let Some((_, pawn)) = pawns_q
+            .iter()
+            .find(|(player, _)| player.number == follow.player)
+            .filter(|_| follow.on)
+        else {
+            continue;
+        };
+        let target = tile_center(&pawn.pos, grid_size, map_type, map_transform);
+        let center = transform
+            .translation
+            .truncate()
+            .lerp(target, ease(RECENTER_EASING, delta));
+        transform.translation.x = center.x;
+        transform.translation.y = center.y;
+        keep_over_board(&mut transform, &board_config);
     }
 }
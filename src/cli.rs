@@ -0,0 +1,182 @@
+use bevy_ecs_tilemap::prelude::*;
+
+use crate::board::{BoardConfig, BoardShape};
+use crate::difficulty::Difficulty;
+use crate::gesture::GestureSettings;
+use crate::palette::PaletteKind;
+use crate::rng::Seed;
+use crate::rules::PlayerConfig;
+use crate::timed::Timeout;
+use crate::tutorial::TUTORIAL_SCENARIO;
+
+// Largest board side and number of floors a game can be set up with.
+const MAX_BOARD_SIDE: u32 = 64;
+const MAX_FLOORS: u32 = 8;
+// One bot for each pawn colour left after the player's.
+const MAX_BOTS: usize = 3;
+
+pub const USAGE: &str = "usage: nightcage [--board <width>x<height>] [--tile-size <pixels>] [--hex]
+                 [--floors <count>]
+                 [--scenario <name>] [--bots <count>] [--difficulty <easy|normal|nightmare>]
+                 [--tutorial] [--lang <code>] [--palette <classic|colorblind|high-contrast>]
+                 [--announce] [--seed <number>] [--pack <name>] [--profile <name>]
+                 [--timed] [--timeout <pass|place>] [--daily] [--generate]
+                 [--log-events <path>] [--split-screen] [--replay <code>]
+                 [--drag-distance <pixels>] [--double-click <seconds>]";
+
+#[derive(Debug, Default)]
+pub struct Args {
+    pub board: BoardConfig,
+    // Name of a scenario in assets/scenarios to set the board up from.
+    pub scenario: Option<String>,
+    pub players: PlayerConfig,
+    pub difficulty: Difficulty,
+    // Walk through the basics on the tutorial scenario.
+    pub tutorial: bool,
+    // Locale to load strings from, as in assets/locales/<code>.ftl.
+    pub lang: Option<String>,
+    pub palette: PaletteKind,
+    // Tile artwork to draw with, from assets/packs/<name>.
+    pub pack: Option<String>,
+    // Describe events and the hovered tile on standard output.
+    pub announce: bool,
+    // Seed for the game's randomness, picked at random when not given.
+    pub seed: Option<Seed>,
+    // Whose stats finished games count towards.
+    pub profile: Option<String>,
+    // Play every turn against the clock.
+    pub timed: bool,
+    // What happens to a turn that runs out of time.
+    pub timeout: Timeout,
+    // Play the day's challenge, which picks its own board, seed and
+    // difficulty over any given.
+    pub daily: bool,
+    // Play a board generated from the seed and difficulty, rather than an
+    // empty one or a scenario.
+    pub generate: bool,
+    // File to add every event in play to, as JSON lines.
+    pub log_events: Option<String>,
+    // How clicks are told apart from drags and double clicks.
+    pub gestures: GestureSettings,
+    // Give two players half the window each, the second on a gamepad.
+    pub split_screen: bool,
+    // A shared game to watch, as its code or a link ending in it.
+    pub replay: Option<String>,
+}
+
+impl Args {
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Args, String> {
+        let mut parsed = Args::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value =
+                |flag: &str| args.next().ok_or_else(|| format!("{} needs a value", flag));
+            match arg.as_str() {
+                "--board" => parsed.board.size = parse_board_size(&value("--board")?)?,
+                "--tile-size" => parsed.board.tile_size = parse_tile_size(&value("--tile-size")?)?,
+                "--scenario" => parsed.scenario = Some(value("--scenario")?),
+                "--bots" => parsed.players.bots = parse_bots(&value("--bots")?)?,
+                "--difficulty" => {
+                    let name = value("--difficulty")?;
+                    parsed.difficulty = Difficulty::from_name(&name)
+                        .ok_or_else(|| format!("unknown difficulty {}", name))?;
+                }
+                "--hex" => parsed.board.shape = BoardShape::Hex,
+                "--floors" => parsed.board.floors = parse_floors(&value("--floors")?)?,
+                "--palette" => {
+                    let name = value("--palette")?;
+                    parsed.palette = PaletteKind::from_name(&name)
+                        .ok_or_else(|| format!("unknown palette {}", name))?;
+                }
+                "--seed" => parsed.seed = Some(parse_seed(&value("--seed")?)?),
+                "--announce" => parsed.announce = true,
+                "--timed" => parsed.timed = true,
+                "--daily" => parsed.daily = true,
+                "--generate" => parsed.generate = true,
+                "--split-screen" => parsed.split_screen = true,
+                "--timeout" => {
+                    let name = value("--timeout")?;
+                    parsed.timeout = Timeout::from_name(&name)
+                        .ok_or_else(|| format!("unknown timeout {}", name))?;
+                }
+                "--drag-distance" => {
+                    parsed.gestures.drag_distance = parse_threshold(&value("--drag-distance")?)?
+                }
+                "--double-click" => {
+                    parsed.gestures.double_click_time = parse_threshold(&value("--double-click")?)?
+                }
+                "--lang" => parsed.lang = Some(value("--lang")?),
+                "--pack" => parsed.pack = Some(value("--pack")?),
+                "--profile" => parsed.profile = Some(value("--profile")?),
+                "--log-events" => parsed.log_events = Some(value("--log-events")?),
+                "--replay" => parsed.replay = Some(value("--replay")?),
+                "--tutorial" => parsed.tutorial = true,
+                _ => return Err(format!("unknown argument {}", arg)),
+            }
+        }
+        if parsed.tutorial {
+            if parsed.scenario.is_some() {
+                return Err("--tutorial plays its own scenario, not --scenario".to_string());
+            }
+            parsed.scenario = Some(TUTORIAL_SCENARIO.to_string());
+        }
+        Ok(parsed)
+    }
+}
+
+// Accepts either `9` for a square board or `9x7`.
+fn parse_board_size(value: &str) -> Result<TilemapSize, String> {
+    let (x, y) = value.split_once('x').unwrap_or((value, value));
+    let parse = |n: &str| match n.trim().parse::<u32>() {
+        Ok(n) if (1..=MAX_BOARD_SIDE).contains(&n) => Ok(n),
+        _ => Err(format!(
+            "invalid board size {}, expected 1 to {} a side",
+            value, MAX_BOARD_SIDE
+        )),
+    };
+    Ok(TilemapSize {
+        x: parse(x)?,
+        y: parse(y)?,
+    })
+}
+
+fn parse_tile_size(value: &str) -> Result<f32, String> {
+    match value.parse::<f32>() {
+        Ok(size) if size > 0.0 => Ok(size),
+        _ => Err(format!("invalid tile size {}", value)),
+    }
+}
+
+fn parse_threshold(value: &str) -> Result<f32, String> {
+    match value.parse::<f32>() {
+        Ok(threshold) if threshold >= 0.0 => Ok(threshold),
+        _ => Err(format!("invalid threshold {}", value)),
+    }
+}
+
+fn parse_floors(value: &str) -> Result<u32, String> {
+    match value.parse::<u32>() {
+        Ok(floors) if (1..=MAX_FLOORS).contains(&floors) => Ok(floors),
+        _ => Err(format!(
+            "invalid floor count {}, expected 1 to {}",
+            value, MAX_FLOORS
+        )),
+    }
+}
+
+fn parse_seed(value: &str) -> Result<Seed, String> {
+    value
+        .parse::<u64>()
+        .map(Seed)
+        .map_err(|_| format!("invalid seed {}", value))
+}
+
+fn parse_bots(value: &str) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(bots) if bots <= MAX_BOTS => Ok(bots),
+        _ => Err(format!(
+            "invalid bot count {}, expected 0 to {}",
+            value, MAX_BOTS
+        )),
+    }
+}
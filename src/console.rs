@@ -0,0 +1,238 @@
+use bevy::{input::keyboard::KeyboardInput, prelude::*};
+use bevy_ecs_tilemap::prelude::*;
+
+use crate::board::TileFog;
+use crate::locale::Strings;
+use crate::pawn::{pawn_floor, KeyPickedUp, Keys, Pawn};
+use crate::rng::Seed;
+use crate::rules::{CurrentBudget, PlaceTile, Player};
+use crate::state::GameState;
+use crate::tiles::TileKind;
+
+// Lines of output kept on screen above the prompt.
+const CONSOLE_LINES: usize = 8;
+
+// A command typed into the developer console. Commands go through the same
+// events as play does wherever there is one, so they're followed by the
+// event log, the stats and everything else watching the game.
+#[derive(Event, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConsoleCommand {
+    // Keys for whoever's turn it is, as if picked up.
+    GiveKey(u32),
+    // A tile put down by whoever's turn it is, given an action to do it with.
+    // Rotation is in quarter turns on square boards and sixth turns on hex
+    // boards.
+    SetTile {
+        pos: TilePos,
+        kind: TileKind,
+        rotation: u8,
+    },
+    // Lift the fog off the whole board.
+    RevealAll,
+    // Start over, dealt from the given seed.
+    Seed(Seed),
+    Help,
+}
+
+// Commands are the command's name and its arguments, separated by spaces:
+//
+//     give_key 2
+//     set_tile 3 4 corner e
+//     reveal_all
+//     seed 1234
+//
+// A tile's facing is n, e, s or w for its quarter turns from the artwork, or
+// a number of turns.
+pub fn parse_command(line: &str) -> Result<ConsoleCommand, String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let number = |value: &str| {
+        value
+            .parse::<u32>()
+            .map_err(|_| format!("invalid number {}", value))
+    };
+    match words.as_slice() {
+        ["give_key"] => Ok(ConsoleCommand::GiveKey(1)),
+        ["give_key", count] => Ok(ConsoleCommand::GiveKey(number(count)?)),
+        ["set_tile", x, y, kind, facing @ ..] => {
+            let kind = TileKind::from_name(kind)
+                .filter(|kind| *kind != TileKind::Empty)
+                .ok_or_else(|| format!("unknown tile {}", kind))?;
+            let rotation = match facing {
+                [] | ["n"] => 0,
+                ["e"] => 1,
+                ["s"] => 2,
+                ["w"] => 3,
+                [turns] => turns
+                    .parse::<u8>()
+                    .map_err(|_| format!("invalid facing {}", turns))?,
+                _ => return Err(format!("expected one facing, got {}", facing.join(" "))),
+            };
+            Ok(ConsoleCommand::SetTile {
+                pos: TilePos {
+                    x: number(x)?,
+                    y: number(y)?,
+                },
+                kind,
+                rotation,
+            })
+        }
+        ["reveal_all"] => Ok(ConsoleCommand::RevealAll),
+        ["seed", value] => value
+            .parse::<u64>()
+            .map(|seed| ConsoleCommand::Seed(Seed(seed)))
+            .map_err(|_| format!("invalid seed {}", value)),
+        ["help"] => Ok(ConsoleCommand::Help),
+        _ => Err(format!("unknown command {}", line.trim())),
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct Console {
+    pub open: bool,
+    input: String,
+    // Commands typed and what came of them, oldest first.
+    lines: Vec<String>,
+}
+
+impl Console {
+    fn print(&mut self, line: String) {
+        self.lines.push(line);
+        let overflow = self.lines.len().saturating_sub(CONSOLE_LINES);
+        self.lines.drain(..overflow);
+    }
+}
+
+#[derive(Component)]
+pub struct ConsoleText;
+
+pub fn spawn_console(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 18.0,
+                color: Color::ORANGE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(0.0),
+            width: Val::Percent(100.0),
+            padding: UiRect::all(Val::Px(8.0)),
+            ..default()
+        })
+        .with_background_color(Color::BLACK.with_a(0.85)),
+        ConsoleText,
+        Visibility::Hidden,
+    ));
+}
+
+// Open and close the console on pressing `. While it's open it takes every
+// key typed, before anything else sees them, so commands can be typed
+// without setting off the game's own keys.
+pub fn console_input(
+    mut console: ResMut<Console>,
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    mut keyboard_input_events: ResMut<Events<KeyboardInput>>,
+    mut character_events: EventReader<ReceivedCharacter>,
+    mut command_events: EventWriter<ConsoleCommand>,
+) {
+    let toggled = keyboard_input.just_pressed(KeyCode::Grave);
+    if toggled {
+        console.open = !console.open;
+    }
+    if !console.open {
+        character_events.clear();
+        return;
+    }
+
+    for character in character_events.read() {
+        if !character.char.is_control() && !['`', '~'].contains(&character.char) {
+            console.input.push(character.char);
+        }
+    }
+    if keyboard_input.just_pressed(KeyCode::Back) {
+        console.input.pop();
+    }
+    if keyboard_input.just_pressed(KeyCode::Return) {
+        let line = std::mem::take(&mut console.input);
+        console.print(format!("> {}", line));
+        match parse_command(&line) {
+            Ok(command) => command_events.send(command),
+            Err(err) => console.print(err),
+        }
+    }
+    keyboard_input.reset_all();
+    keyboard_input_events.clear();
+}
+
+pub fn update_console_text(
+    console: Res<Console>,
+    mut text_q: Query<(&mut Text, &mut Visibility), With<ConsoleText>>,
+) {
+    if !console.is_changed() {
+        return;
+    }
+    for (mut text, mut visibility) in text_q.iter_mut() {
+        *visibility = if console.open {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        let mut lines = console.lines.clone();
+        lines.push(format!("> {}_", console.input));
+        text.sections[0].value = lines.join("\n");
+    }
+}
+
+pub fn run_console_commands(
+    mut command_events: EventReader<ConsoleCommand>,
+    (mut console, strings): (ResMut<Console>, Res<Strings>),
+    (mut seed, mut next_state): (ResMut<Seed>, ResMut<NextState<GameState>>),
+    mut budget: CurrentBudget,
+    (mut place_events, mut picked_up_events): (EventWriter<PlaceTile>, EventWriter<KeyPickedUp>),
+    mut pawns_q: Query<(&Player, &mut Keys, &Pawn)>,
+    mut fog_q: Query<&mut TileFog>,
+) {
+    for command in command_events.read() {
+        match *command {
+            ConsoleCommand::GiveKey(count) => {
+                let player = budget.player();
+                for (seat, mut keys, _) in pawns_q.iter_mut() {
+                    if seat.number == player {
+                        keys.0 += count;
+                    }
+                }
+                for _ in 0..count {
+                    picked_up_events.send(KeyPickedUp { player });
+                }
+            }
+            ConsoleCommand::SetTile {
+                pos,
+                kind,
+                rotation,
+            } => {
+                let pawns = pawns_q.iter().map(|(seat, _, pawn)| (seat, pawn));
+                let floor = pawn_floor(pawns, budget.player());
+                budget.grant(1);
+                place_events.send(PlaceTile {
+                    pos,
+                    floor,
+                    kind,
+                    rotation,
+                });
+            }
+            ConsoleCommand::RevealAll => {
+                for mut fog in fog_q.iter_mut() {
+                    fog.explored = true;
+                }
+            }
+            ConsoleCommand::Seed(new_seed) => {
+                *seed = new_seed;
+                next_state.set(GameState::Setup);
+            }
+            ConsoleCommand::Help => console.print(strings.get("console-help")),
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use bevy::{app::PluginGroupBuilder, prelude::*};
+
+use crate::assist::Easing;
+use crate::darkness;
+use crate::falling;
+use crate::floor::{self, CurrentFloor};
+use crate::nerve;
+use crate::pawn;
+use crate::rules::{self, HeldTiles, TileBag, Turn};
+use crate::state::GameState;
+use crate::tiles::TileKind;
+
+// Game content comes as plugins: the base game, and whichever expansions or
+// fan variants are compiled in beside it. Each adds its tiles and wax eaters
+// to `Content` and runs its rules in `RulesSet`, so none of them need the core
+// systems touched.
+
+// What the plugins in play add to a game.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct Content {
+    // Names of the plugins in play, in the order they were added.
+    pub plugins: Vec<&'static str>,
+    // Tiles put into every fresh bag on top of the difficulty's mix.
+    pub extra_tiles: Vec<(TileKind, usize)>,
+    // Wax eaters lurking at the start on top of the difficulty's.
+    pub extra_wax_eaters: usize,
+}
+
+impl Content {
+    // The content in play, for a plugin to add to while the app is built.
+    pub fn of(app: &mut App) -> Mut<'_, Content> {
+        app.init_resource::<Content>();
+        app.world.resource_mut::<Content>()
+    }
+}
+
+// The systems playing out the rules, which only run during play.
+#[derive(SystemSet, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct RulesSet;
+
+// The rules in the box: placing and collapsing tiles, turns, nerve, pawns and
+// their keys, falls, stairs and the wax eaters waking in the dark. Input and
+// everything drawn on screen is left to the app.
+pub struct BaseGamePlugin;
+
+impl Plugin for BaseGamePlugin {
+    fn build(&self, app: &mut App) {
+        Content::of(app).plugins.push("base-game");
+        app.configure_sets(Update, RulesSet.run_if(in_state(GameState::Playing)))
+            .init_resource::<Turn>()
+            .init_resource::<TileBag>()
+            .init_resource::<HeldTiles>()
+            .init_resource::<nerve::Nerve>()
+            .init_resource::<CurrentFloor>()
+            .init_resource::<Easing>()
+            .add_event::<rules::PlaceTile>()
+            .add_event::<rules::TileCollapsed>()
+            .add_event::<rules::TilePlaced>()
+            .add_event::<rules::TileDrawn>()
+            .add_event::<rules::SwapHand>()
+            .add_event::<rules::HandTurned>()
+            .add_event::<rules::TurnPassed>()
+            .add_event::<darkness::WaxEaterStirred>()
+            .add_event::<darkness::WaxEaterEmerged>()
+            .add_event::<darkness::PawnHit>()
+            .add_event::<nerve::SpendNerve>()
+            .add_event::<pawn::MovePawn>()
+            .add_event::<pawn::KeyPickedUp>()
+            .add_event::<pawn::PassKey>()
+            .add_event::<pawn::KeyPassed>()
+            .add_event::<pawn::Escaped>()
+            .add_event::<falling::PawnFell>()
+            .add_event::<floor::TakeStairs>()
+            .add_systems(
+                Update,
+                (
+                    rules::apply_placements,
+                    rules::swap_hands,
+                    rules::advance_turns,
+                    nerve::spend_nerve,
+                    pawn::move_pawns,
+                    pawn::pass_keys,
+                    pawn::pick_up_keys,
+                    (falling::resolve_falls, falling::land_fallen_pawns)
+                        .chain()
+                        .after(rules::apply_placements),
+                    pawn::detect_escapes,
+                    floor::take_stairs,
+                    // the board has to be up to date with the placements
+                    // before anything can wake beside them
+                    (apply_deferred, darkness::wake_wax_eaters)
+                        .chain()
+                        .after(rules::apply_placements)
+                        .after(falling::land_fallen_pawns),
+                    darkness::emerge_wax_eaters,
+                    darkness::hit_pawns
+                        .after(darkness::emerge_wax_eaters)
+                        .before(nerve::spend_nerve),
+                )
+                    .in_set(RulesSet),
+            )
+            .add_systems(Update, pawn::mark_occupants.after(RulesSet));
+    }
+}
+
+// Every expansion compiled in, each behind its own cargo feature.
+pub struct ExpansionPlugins;
+
+impl PluginGroup for ExpansionPlugins {
+    fn build(self) -> PluginGroupBuilder {
+        #[allow(unused_mut)]
+        let mut group = PluginGroupBuilder::start::<Self>();
+        #[cfg(feature = "miasma")]
+        {
+            group = group.add(crate::miasma::MiasmaExpansionPlugin);
+        }
+        group
+    }
+}
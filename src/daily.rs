@@ -0,0 +1,295 @@
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+use bevy_ecs_tilemap::prelude::*;
+
+use crate::board::{BoardConfig, BoardShape};
+use crate::difficulty::Difficulty;
+use crate::locale::{parse, Strings};
+use crate::rng::Seed;
+use crate::save::Format;
+use crate::stats::{GameEnded, GameTally};
+
+// Sides of the square daily boards, and of the hex ones, which fit more in.
+const DAILY_SIDES: std::ops::RangeInclusive<u32> = 7..=11;
+const DAILY_HEX_SIDES: std::ops::RangeInclusive<u32> = 6..=9;
+
+// A day of the calendar, in UTC so it's the same day for everyone.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Date {
+    // Days since 1970-01-01.
+    days: u64,
+}
+
+impl Date {
+    // The date by the system clock, which the web doesn't let std read.
+    pub fn today() -> Option<Date> {
+        if cfg!(target_arch = "wasm32") {
+            return None;
+        }
+        let since = SystemTime::now().duration_since(UNIX_EPOCH).ok()?;
+        Some(Date::from_days(since.as_secs() / 86_400))
+    }
+
+    pub fn from_days(days: u64) -> Date {
+        Date { days }
+    }
+
+    // The year, month and day of the month, counting months and days from 1.
+    pub fn civil(self) -> (i64, u32, u32) {
+        // Counted in 400 year eras from 0000-03-01, so leap days end the year.
+        let z = self.days as i64 + 719_468;
+        let era = z.div_euclid(146_097);
+        let day_of_era = z.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+        let month = if shifted_month < 10 {
+            shifted_month + 3
+        } else {
+            shifted_month - 9
+        };
+        let year = year_of_era + era * 400 + (month <= 2) as i64;
+        (year, month as u32, day as u32)
+    }
+}
+
+impl std::fmt::Display for Date {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (year, month, day) = self.civil();
+        write!(f, "{:04}-{:02}-{:02}", year, month, day)
+    }
+}
+
+// The game everyone gets on a given day: its seed, board and difficulty all
+// follow from the date alone. Daily games are played by the default rules,
+// whatever house rules are saved.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DailyChallenge {
+    pub date: Date,
+    pub seed: Seed,
+    pub board: BoardConfig,
+    pub difficulty: Difficulty,
+}
+
+impl DailyChallenge {
+    // `board` gives the tile size, which is only a matter of how the board
+    // is drawn.
+    pub fn new(date: Date, board: BoardConfig) -> DailyChallenge {
+        let mut rng = fastrand::Rng::with_seed(date.days);
+        let seed = Seed(rng.u64(..));
+        let (shape, side) = if rng.u32(0..4) == 0 {
+            (BoardShape::Hex, rng.u32(DAILY_HEX_SIDES))
+        } else {
+            (BoardShape::Square, rng.u32(DAILY_SIDES))
+        };
+        let difficulty = match rng.u32(0..7) {
+            0 | 1 => Difficulty::Easy,
+            2..=5 => Difficulty::Normal,
+            _ => Difficulty::Nightmare,
+        };
+        DailyChallenge {
+            date,
+            seed,
+            board: BoardConfig {
+                size: TilemapSize { x: side, y: side },
+                shape,
+                floors: 1,
+                ..board
+            },
+            difficulty,
+        }
+    }
+}
+
+// How a daily game went. Escaping beats being beaten to the gate, and
+// escaping in fewer turns beats taking more.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DailyResult {
+    pub won: bool,
+    pub turns: u32,
+}
+
+impl DailyResult {
+    pub fn beats(self, other: DailyResult) -> bool {
+        self.won && (!other.won || self.turns < other.turns)
+    }
+
+    fn parse(value: &str) -> Option<DailyResult> {
+        let (outcome, turns) = value.split_once(' ')?;
+        let won = match outcome {
+            "won" => true,
+            "lost" => false,
+            _ => return None,
+        };
+        let turns = turns.trim().parse::<u32>().ok()?;
+        Some(DailyResult { won, turns })
+    }
+}
+
+impl std::fmt::Display for DailyResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let outcome = if self.won { "won" } else { "lost" };
+        write!(f, "{} {}", outcome, self.turns)
+    }
+}
+
+// Each profile's best daily results are kept beside its stats, one day per
+// line:
+//
+//     # nightcage daily 1
+//     2026-10-14 = lost 31
+//     2026-10-15 = won 18
+const DAILY_FORMAT: Format = Format {
+    name: "daily",
+    migrations: &[],
+};
+
+fn daily_path(profile: &str) -> String {
+    format!("nightcage-daily-{}.txt", profile)
+}
+
+// Today's challenge, while one is being played, and the best results of
+// those played before.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct Daily {
+    pub challenge: Option<DailyChallenge>,
+    profile: String,
+    // Best results by date.
+    best: BTreeMap<String, DailyResult>,
+    // How this game went and the day's best before it, once it's over.
+    result: Option<(DailyResult, Option<DailyResult>)>,
+}
+
+impl Daily {
+    // Loads the profile's best results for the challenge being played, if
+    // any, with none kept when they can't be read, as on the web.
+    pub fn load(profile: &str, challenge: Option<DailyChallenge>) -> Daily {
+        Daily::read(profile, challenge).unwrap_or_else(|err| {
+            warn!("{}", err);
+            Daily::unplayed(profile, challenge)
+        })
+    }
+
+    fn unplayed(profile: &str, challenge: Option<DailyChallenge>) -> Daily {
+        Daily {
+            challenge,
+            profile: profile.to_string(),
+            ..default()
+        }
+    }
+
+    fn read(profile: &str, challenge: Option<DailyChallenge>) -> Result<Daily, String> {
+        let mut daily = Daily::unplayed(profile, challenge);
+        if challenge.is_none() {
+            return Ok(daily);
+        }
+        let path = daily_path(profile);
+        let source = match std::fs::read_to_string(&path) {
+            Ok(source) => source,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(daily),
+            Err(err) => return Err(format!("failed to read {}: {}", path, err)),
+        };
+        let source = DAILY_FORMAT
+            .upgrade(&source)
+            .map_err(|err| format!("{}: {}", path, err))?;
+        for (date, value) in parse(&source) {
+            let result = DailyResult::parse(&value)
+                .ok_or_else(|| format!("invalid result {} for {} in {}", value, date, path))?;
+            daily.best.insert(date, result);
+        }
+        Ok(daily)
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let mut source = DAILY_FORMAT.header();
+        for (date, result) in &self.best {
+            source.push_str(&format!("{} = {}\n", date, result));
+        }
+        let path = daily_path(&self.profile);
+        std::fs::write(&path, source).map_err(|err| format!("failed to write {}: {}", path, err))
+    }
+
+    // How the game went against the day's best, for the summary at its end.
+    pub fn results(&self, strings: &Strings) -> Option<String> {
+        let (result, best_before) = self.result?;
+        let turns = result.turns.to_string();
+        Some(match best_before {
+            Some(best) if best.won && !result.beats(best) => strings.format(
+                "daily-best",
+                &[("turns", turns), ("best", best.turns.to_string())],
+            ),
+            _ if result.won => strings.format("daily-new-best", &[("turns", turns)]),
+            _ => strings.get("daily-no-best"),
+        })
+    }
+
+    // A line to pass on to anyone else playing the day's challenge.
+    pub fn share(&self, tally: &GameTally, strings: &Strings) -> Option<String> {
+        let challenge = self.challenge?;
+        let (result, _) = self.result?;
+        let key = if result.won {
+            "daily-share-won"
+        } else {
+            "daily-share-lost"
+        };
+        Some(strings.format(
+            key,
+            &[
+                ("date", challenge.date.to_string()),
+                ("turns", result.turns.to_string()),
+                ("keys", tally.keys_collected.to_string()),
+                ("candles", tally.candles_lost.to_string()),
+            ],
+        ))
+    }
+}
+
+pub fn running(daily: Res<Daily>) -> bool {
+    daily.challenge.is_some()
+}
+
+// Keeps the game's result if it's the day's best yet, and prints the line to
+// share it with. Games started afresh from the menu aren't the day's
+// challenge and don't count.
+pub fn record_daily(
+    (seed, strings): (Res<Seed>, Res<Strings>),
+    tally: Res<GameTally>,
+    mut daily: ResMut<Daily>,
+    mut ended_events: EventReader<GameEnded>,
+) {
+    let Some(ended) = ended_events.read().next().copied() else {
+        return;
+    };
+    let Some(challenge) = daily.challenge else {
+        return;
+    };
+    if *seed != challenge.seed {
+        return;
+    }
+    let result = DailyResult {
+        won: ended.won,
+        turns: tally.turns,
+    };
+    let date = challenge.date.to_string();
+    let best_before = daily.best.get(&date).copied();
+    daily.result = Some((result, best_before));
+    if let Some(share) = daily.share(&tally, &strings) {
+        println!("{}", share);
+    }
+    if best_before.is_some_and(|best| !result.beats(best)) {
+        return;
+    }
+    daily.best.insert(date, result);
+    if let Err(err) = daily.save() {
+        warn!("{}", err);
+    }
+}
+
+// A new game starts with no result.
+pub fn reset_daily(mut daily: ResMut<Daily>) {
+    daily.result = None;
+}
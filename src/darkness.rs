@@ -0,0 +1,320 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use bevy_ecs_tilemap::prelude::*;
+
+use crate::assist::Easing;
+use crate::board::{neighbors, tile_center, BoardConfig, MonsterSpawn, TileType};
+use crate::difficulty::Difficulty;
+use crate::floor::{CurrentFloor, Floor, ShownFloor};
+use crate::house_rules::RuleSet;
+use crate::nerve::{Nerve, NerveAction, SpendNerve};
+use crate::path::{board_distances, Paths};
+use crate::pawn::{pawn_floor, Keys, Pawn};
+use crate::rules::{Player, TileCollapsed, TileDrawn, Turn};
+use crate::tiles::TileKind;
+
+// How long a wax eater stirs in the dark before it crawls out, in seconds.
+const STIRRING_SECONDS: f32 = 2.0;
+const STIRRING_RADIUS: f32 = 0.35;
+
+// A wax eater about to crawl out of the dark onto this cell.
+#[derive(Component)]
+pub struct Stirring {
+    timer: Timer,
+}
+
+impl Default for Stirring {
+    fn default() -> Self {
+        Stirring::lasting(STIRRING_SECONDS)
+    }
+}
+
+impl Stirring {
+    pub fn lasting(seconds: f32) -> Stirring {
+        Stirring {
+            timer: Timer::from_seconds(seconds, TimerMode::Once),
+        }
+    }
+}
+
+// Sent as a wax eater starts stirring, as a warning of where it will be.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct WaxEaterStirred {
+    pub pos: TilePos,
+}
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct WaxEaterEmerged {
+    pub pos: TilePos,
+    pub floor: u32,
+}
+
+// A wax eater lunged at a player's pawn as it crawled out beside it. The pawn
+// either shrugged it off with a nerve token or dropped the keys it carried.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct PawnHit {
+    pub player: usize,
+    pub survived: bool,
+}
+
+// The dark frontier wax eaters creep in from: empty cells beside a placed
+// tile, leaving out the `taken` cells a pawn or wax eater is already in.
+pub fn dark_frontier(
+    size: &TilemapSize,
+    map_type: &TilemapType,
+    wrap: bool,
+    placed: impl Fn(&TilePos) -> bool,
+    taken: &[TilePos],
+) -> Vec<TilePos> {
+    let mut frontier = Vec::new();
+    for y in 0..size.y {
+        for x in 0..size.x {
+            let pos = TilePos { x, y };
+            if placed(&pos) || taken.contains(&pos) {
+                continue;
+            }
+            if neighbors(&pos, size, map_type, wrap).iter().any(&placed) {
+                frontier.push(pos);
+            }
+        }
+    }
+    frontier
+}
+
+// Where wax eaters wake after something happens on the board: one beside each
+// corridor that went dark as its tile collapsed, then one as close to the
+// pawns as it can get for each pillar chamber drawn. No more wake once the
+// dark holds as many as the difficulty allows, counting the `lurking` ones.
+pub fn waking_cells(
+    (size, map_type, wrap): (&TilemapSize, &TilemapType, bool),
+    mut frontier: Vec<TilePos>,
+    collapsed: &[TilePos],
+    drawn: &[TileKind],
+    pawns: &[TilePos],
+    lurking: usize,
+    difficulty: Difficulty,
+) -> Vec<TilePos> {
+    let mut wakes: Vec<&[TilePos]> = collapsed.iter().map(std::slice::from_ref).collect();
+    for kind in drawn {
+        if *kind == TileKind::Pillar {
+            wakes.push(pawns);
+        }
+    }
+
+    let mut woken = Vec::new();
+    for near in wakes {
+        if lurking + woken.len() >= difficulty.max_wax_eaters() {
+            break;
+        }
+        let distances = board_distances(near.iter().copied(), size, map_type, wrap);
+        let Some(cell) = closest(&frontier, &distances) else {
+            break;
+        };
+        frontier.retain(|pos| *pos != cell);
+        woken.push(cell);
+    }
+    woken
+}
+
+// Lands the hits of wax eaters crawling out at the `emerged` cells on the
+// pawns beside them, given as each pawn's cell and keys. Every hit takes a
+// token from `nerve` while there are any left, and otherwise knocks the keys
+// out of the pawn's hands. Returns the pawns hit, in order, and whether each
+// shrugged its hit off.
+pub fn land_hits(
+    (size, map_type, wrap): (&TilemapSize, &TilemapType, bool),
+    emerged: &[TilePos],
+    pawns: &mut [(TilePos, u32)],
+    nerve: &mut u32,
+) -> Vec<(usize, bool)> {
+    let mut hits = Vec::new();
+    for pos in emerged {
+        let beside = neighbors(pos, size, map_type, wrap);
+        for (index, (pawn_pos, keys)) in pawns.iter_mut().enumerate() {
+            if !beside.contains(pawn_pos) {
+                continue;
+            }
+            let survived = *nerve > 0;
+            if survived {
+                *nerve -= 1;
+            } else {
+                *keys = 0;
+            }
+            hits.push((index, survived));
+        }
+    }
+    hits
+}
+
+// The cell fewest steps away, ties going to whichever comes first.
+fn closest(cells: &[TilePos], distances: &Paths) -> Option<TilePos> {
+    cells
+        .iter()
+        .copied()
+        .min_by_key(|cell| distances.steps(cell).unwrap_or(0))
+}
+
+type WakingTileQuery = (
+    Entity,
+    &'static TilePos,
+    &'static Floor,
+    Option<&'static TileType>,
+    Has<MonsterSpawn>,
+    Has<Stirring>,
+);
+
+// Wax eaters wake on the floor of the pawn whose turn it is, where tiles are
+// drawn for and collapse.
+pub fn wake_wax_eaters(
+    mut commands: Commands,
+    (board_config, difficulty, turn, rules): (
+        Res<BoardConfig>,
+        Res<Difficulty>,
+        Res<Turn>,
+        Res<RuleSet>,
+    ),
+    easing: Res<Easing>,
+    (mut collapse_events, mut drawn_events): (EventReader<TileCollapsed>, EventReader<TileDrawn>),
+    tiles_q: Query<WakingTileQuery>,
+    pawns_q: Query<(&Player, &Pawn)>,
+    mut stirred_events: EventWriter<WaxEaterStirred>,
+) {
+    let current = pawn_floor(&pawns_q, turn.player);
+    let collapsed: Vec<TilePos> = collapse_events
+        .read()
+        .filter(|event| event.floor == current)
+        .map(|event| event.pos)
+        .collect();
+    let drawn: Vec<TileKind> = drawn_events.read().map(|event| event.kind).collect();
+    // friendly wax eaters leave the players be
+    if rules.friendly_monsters || (collapsed.is_empty() && !drawn.contains(&TileKind::Pillar)) {
+        return;
+    }
+
+    let lurking = tiles_q
+        .iter()
+        .filter(|(.., monster, stirring)| *monster || *stirring)
+        .count();
+    let mut cells: HashMap<TilePos, (Entity, bool)> = HashMap::new();
+    let mut taken = Vec::new();
+    for (tile_entity, pos, floor, tile_type, monster, stirring) in tiles_q.iter() {
+        if floor.0 != current {
+            continue;
+        }
+        let placed = tile_type.is_some_and(|tile_type| tile_type.kind != TileKind::Empty);
+        cells.insert(*pos, (tile_entity, placed));
+        if monster || stirring {
+            taken.push(*pos);
+        }
+    }
+    let pawns: Vec<TilePos> = pawns_q
+        .iter()
+        .filter(|(_, pawn)| pawn.floor == current)
+        .map(|(_, pawn)| pawn.pos)
+        .collect();
+    taken.extend(&pawns);
+
+    let map_type = board_config.map_type();
+    let frontier = dark_frontier(
+        &board_config.size,
+        &map_type,
+        rules.wrap_around,
+        |pos| cells.get(pos).is_some_and(|(_, placed)| *placed),
+        &taken,
+    );
+    let board = (&board_config.size, &map_type, rules.wrap_around);
+    for pos in waking_cells(
+        board,
+        frontier,
+        &collapsed,
+        &drawn,
+        &pawns,
+        lurking,
+        *difficulty,
+    ) {
+        commands
+            .entity(cells[&pos].0)
+            .insert(Stirring::lasting(easing.stirring_seconds(STIRRING_SECONDS)));
+        stirred_events.send(WaxEaterStirred { pos });
+    }
+}
+
+pub fn emerge_wax_eaters(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut stirring_q: Query<(Entity, &TilePos, &Floor, &mut Stirring)>,
+    mut emerged_events: EventWriter<WaxEaterEmerged>,
+) {
+    for (tile_entity, pos, floor, mut stirring) in stirring_q.iter_mut() {
+        if !stirring.timer.tick(time.delta()).finished() {
+            continue;
+        }
+        commands
+            .entity(tile_entity)
+            .remove::<Stirring>()
+            .insert(MonsterSpawn);
+        emerged_events.send(WaxEaterEmerged {
+            pos: *pos,
+            floor: floor.0,
+        });
+    }
+}
+
+// Wax eaters lunge at the pawns beside them on their floor as they crawl out.
+// The hit is cancelled by spending nerve on surviving it.
+pub fn hit_pawns(
+    (board_config, rules, nerve): (Res<BoardConfig>, Res<RuleSet>, Res<Nerve>),
+    mut emerged_events: EventReader<WaxEaterEmerged>,
+    mut pawns_q: Query<(&Player, &Pawn, &mut Keys)>,
+    mut spend_events: EventWriter<SpendNerve>,
+    mut hit_events: EventWriter<PawnHit>,
+) {
+    let map_type = board_config.map_type();
+    let board = (&board_config.size, &map_type, rules.wrap_around);
+    let mut tokens = nerve.0;
+    for emerged in emerged_events.read() {
+        if rules.friendly_monsters {
+            continue;
+        }
+        let mut pawns: Vec<_> = pawns_q
+            .iter_mut()
+            .filter(|(_, pawn, _)| pawn.floor == emerged.floor)
+            .collect();
+        let mut cells: Vec<(TilePos, u32)> = pawns
+            .iter()
+            .map(|(_, pawn, keys)| (pawn.pos, keys.0))
+            .collect();
+        for (index, survived) in land_hits(board, &[emerged.pos], &mut cells, &mut tokens) {
+            let (player, _, keys) = &mut pawns[index];
+            keys.0 = cells[index].1;
+            if survived {
+                spend_events.send(SpendNerve(NerveAction::SurviveHit));
+            }
+            hit_events.send(PawnHit {
+                player: player.number,
+                survived,
+            });
+        }
+    }
+}
+
+// A stirring wax eater shows as a ring closing in on its cell.
+pub fn draw_stirring(
+    mut gizmos: Gizmos,
+    current_floor: Res<CurrentFloor>,
+    tilemap_q: Query<(&TilemapGridSize, &TilemapType, &Transform), With<ShownFloor>>,
+    stirring_q: Query<(&TilePos, &Floor, &Stirring)>,
+) {
+    for (grid_size, map_type, map_transform) in tilemap_q.iter() {
+        let radius = grid_size.x * map_transform.scale.x * STIRRING_RADIUS;
+        for (tile_pos, floor, stirring) in stirring_q.iter() {
+            if !current_floor.shows(floor) {
+                continue;
+            }
+            let center = tile_center(tile_pos, grid_size, map_type, map_transform);
+            let closing = 1.0 + stirring.timer.percent_left();
+            gizmos.circle_2d(center, radius * closing, Color::CRIMSON);
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use bevy::{
+    diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin},
+    prelude::*,
+};
+use bevy_ecs_tilemap::prelude::*;
+
+use crate::board::{BoardConfig, BoardShape};
+use crate::inspect::BoardInspector;
+use crate::locale::Strings;
+
+// F3 shows what's known about the hovered tile, the frame rate and the
+// board's bounds, for working on the game rather than playing it.
+#[derive(Resource, Default)]
+pub struct DebugOverlay {
+    pub shown: bool,
+}
+
+#[derive(Component)]
+pub struct DebugText;
+
+pub fn spawn_debug_text(mut commands: Commands) {
+    commands
+        .spawn((
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font_size: 16.0,
+                    color: Color::LIME_GREEN,
+                    ..default()
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(8.0),
+                left: Val::Percent(35.0),
+                padding: UiRect::all(Val::Px(8.0)),
+                ..default()
+            })
+            .with_background_color(Color::BLACK.with_a(0.6)),
+            DebugText,
+        ))
+        .insert(Visibility::Hidden);
+}
+
+pub fn shown(overlay: Res<DebugOverlay>) -> bool {
+    overlay.shown
+}
+
+pub fn toggle_debug_overlay(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut overlay: ResMut<DebugOverlay>,
+    mut text_q: Query<&mut Visibility, With<DebugText>>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::F3) {
+        return;
+    }
+    overlay.shown = !overlay.shown;
+    for mut visibility in text_q.iter_mut() {
+        *visibility = if overlay.shown {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+pub fn update_debug_text(
+    (strings, diagnostics): (Res<Strings>, Res<DiagnosticsStore>),
+    inspector: BoardInspector,
+    textures_q: Query<&TileTextureIndex>,
+    mut text_q: Query<&mut Text, With<DebugText>>,
+) {
+    let fps = diagnostics
+        .get(FrameTimeDiagnosticsPlugin::FPS)
+        .and_then(|fps| fps.smoothed())
+        .map_or(String::from("-"), |fps| format!("{:.0}", fps));
+    let mut lines = vec![strings.format("debug-fps", &[("fps", fps)])];
+    match inspector.hovered() {
+        Some(info) => {
+            let texture = textures_q
+                .get(info.entity)
+                .map_or(String::from("-"), |texture| texture.0.to_string());
+            // one bit a side, the first side on the right
+            let mask = match inspector.board_config().shape {
+                BoardShape::Square => format!("{:04b}", info.exits.bits()),
+                BoardShape::Hex => format!("{:06b}", info.exits.bits()),
+            };
+            let light = format!("{:.2}", info.light);
+            let lit = if info.illuminated {
+                "debug-lit"
+            } else {
+                "debug-unlit"
+            };
+            lines.extend([
+                strings.format(
+                    "debug-tile",
+                    &[
+                        ("x", info.pos.x.to_string()),
+                        ("y", info.pos.y.to_string()),
+                        ("floor", info.floor.to_string()),
+                        ("entity", format!("{:?}", info.entity)),
+                    ],
+                ),
+                strings.format(
+                    "debug-kind",
+                    &[
+                        ("tile", strings.tile(info.kind)),
+                        ("texture", texture),
+                        ("rotation", info.rotation.to_string()),
+                    ],
+                ),
+                strings.format("debug-passages", &[("mask", mask)]),
+                strings.format(lit, &[("light", light)]),
+            ]);
+            if !info.pawns.is_empty() {
+                let players: Vec<String> = info
+                    .pawns
+                    .iter()
+                    .map(|player| (player + 1).to_string())
+                    .collect();
+                lines.push(strings.format("debug-pawns", &[("players", players.join(", "))]));
+            }
+        }
+        None => lines.push(strings.get("debug-no-tile")),
+    }
+    let value = lines.join("\n");
+    for mut text in text_q.iter_mut() {
+        if text.sections[0].value != value {
+            text.sections[0].value = value.clone();
+        }
+    }
+}
+
+// The bounds the view is kept centered within.
+pub fn draw_board_bounds(mut gizmos: Gizmos, board_config: Res<BoardConfig>) {
+    gizmos.rect_2d(
+        Vec2::ZERO,
+        0.0,
+        board_config.world_size(),
+        Color::ORANGE_RED,
+    );
+}
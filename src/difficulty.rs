@@ -0,0 +1,108 @@
+use crate::tiles::TileKind;
+
+#[derive(bevy::prelude::Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Nightmare,
+}
+
+impl Difficulty {
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Nightmare => "nightmare",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Difficulty> {
+        match name {
+            "easy" => Some(Difficulty::Easy),
+            "normal" => Some(Difficulty::Normal),
+            "nightmare" => Some(Difficulty::Nightmare),
+            _ => None,
+        }
+    }
+
+    // How many of each kind go into a full bag.
+    pub fn bag_mix(self) -> [(TileKind, usize); 8] {
+        use TileKind::*;
+        match self {
+            Difficulty::Easy => [
+                (Straight, 8),
+                (Corner, 8),
+                (TJunction, 8),
+                (Crossing, 5),
+                (DeadEnd, 1),
+                (Key, 2),
+                (Gate, 1),
+                (Pillar, 1),
+            ],
+            Difficulty::Normal => [
+                (Straight, 8),
+                (Corner, 8),
+                (TJunction, 6),
+                (Crossing, 3),
+                (DeadEnd, 3),
+                (Key, 1),
+                (Gate, 1),
+                (Pillar, 2),
+            ],
+            Difficulty::Nightmare => [
+                (Straight, 6),
+                (Corner, 8),
+                (TJunction, 4),
+                (Crossing, 1),
+                (DeadEnd, 6),
+                (Key, 1),
+                (Gate, 1),
+                (Pillar, 4),
+            ],
+        }
+    }
+
+    // Wax eaters lurking on the board when a game starts without a scenario
+    // that places its own.
+    pub fn wax_eaters(self) -> usize {
+        match self {
+            Difficulty::Easy => 1,
+            Difficulty::Normal => 2,
+            Difficulty::Nightmare => 4,
+        }
+    }
+
+    // Most wax eaters the dark holds at once, past which no more wake.
+    pub fn max_wax_eaters(self) -> usize {
+        match self {
+            Difficulty::Easy => 3,
+            Difficulty::Normal => 5,
+            Difficulty::Nightmare => 8,
+        }
+    }
+
+    // Nerve tokens the players share at the start of a game.
+    pub fn nerve(self) -> u32 {
+        match self {
+            Difficulty::Easy => 5,
+            Difficulty::Normal => 3,
+            Difficulty::Nightmare => 1,
+        }
+    }
+
+    // Seconds each player has to take their turn in timed mode.
+    pub fn turn_seconds(self) -> f32 {
+        match self {
+            Difficulty::Easy => 45.0,
+            Difficulty::Normal => 30.0,
+            Difficulty::Nightmare => 15.0,
+        }
+    }
+
+    // Whether the discard pile is shuffled back in once the bag runs out, or
+    // discarded tiles stay out of the game.
+    pub fn discard_reshuffles(self) -> bool {
+        self != Difficulty::Nightmare
+    }
+}
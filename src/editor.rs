@@ -0,0 +1,441 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use bevy::{input::mouse::MouseButtonInput, prelude::*};
+use bevy_ecs_tilemap::prelude::*;
+
+use crate::board::{
+    tile_center, tile_rotation, BoardConfig, HexRotation, HighlightedLabel, HoverState,
+    MonsterSpawn, NextTileKind, PlayerStart, Rotation, TileHistory, TileType,
+};
+use crate::floor::{CurrentFloor, Floor, ShownFloor, Stairs};
+use crate::gesture::Gesture;
+use crate::locale::Strings;
+use crate::palette::Palette;
+use crate::scenario::{ActiveScenario, Scenario, ScenarioTile};
+use crate::state::GameState;
+
+const MARKER_RADIUS: f32 = 0.25;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EditorTool {
+    #[default]
+    Paint,
+    Erase,
+    PlayerStart,
+    Monster,
+}
+
+impl EditorTool {
+    fn string_key(self) -> &'static str {
+        match self {
+            EditorTool::Paint => "editor-tool-paint",
+            EditorTool::Erase => "editor-tool-erase",
+            EditorTool::PlayerStart => "editor-tool-player-start",
+            EditorTool::Monster => "editor-tool-monster",
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct Editor {
+    pub tool: EditorTool,
+    exports: u32,
+    // Result of the last export, shown in the overlay.
+    status: String,
+    // Tiles the current tool is applied to together.
+    pub selection: HashSet<TilePos>,
+    // Where a shift+drag box selection started.
+    drag_from: Option<TilePos>,
+}
+
+#[derive(Component)]
+pub struct EditorOverlay;
+
+type EditedTileQuery = (
+    Entity,
+    &'static TilePos,
+    &'static Floor,
+    &'static mut Rotation,
+    Option<&'static mut HexRotation>,
+    Option<&'static TileType>,
+    &'static mut TileHistory,
+    Has<PlayerStart>,
+    Has<MonsterSpawn>,
+);
+
+type ScenarioTileQuery = (
+    &'static TilePos,
+    &'static Floor,
+    Option<&'static TileType>,
+    &'static Rotation,
+    Option<&'static HexRotation>,
+    Option<&'static Stairs>,
+    Has<PlayerStart>,
+    Has<MonsterSpawn>,
+);
+
+// toggle the editor on pressing e
+pub fn toggle_editor(
+    keyboard_input: Res<Input<KeyCode>>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::E) {
+        match state.get() {
+            GameState::Playing => next_state.set(GameState::Editor),
+            GameState::Editor => next_state.set(GameState::Playing),
+            GameState::Setup | GameState::Photo | GameState::Paused | GameState::GameOver => {}
+        }
+    }
+}
+
+pub fn enter_editor(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 20.0,
+                color: Color::ORANGE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(8.0),
+            left: Val::Px(8.0),
+            ..default()
+        }),
+        EditorOverlay,
+    ));
+}
+
+pub fn exit_editor(mut commands: Commands, overlays_q: Query<Entity, With<EditorOverlay>>) {
+    for overlay_entity in overlays_q.iter() {
+        commands.entity(overlay_entity).despawn_recursive();
+    }
+}
+
+// pick a tool with the number keys
+pub fn select_tool(keyboard_input: Res<Input<KeyCode>>, mut editor: ResMut<Editor>) {
+    let tools = [
+        (KeyCode::Key1, EditorTool::Paint),
+        (KeyCode::Key2, EditorTool::Erase),
+        (KeyCode::Key3, EditorTool::PlayerStart),
+        (KeyCode::Key4, EditorTool::Monster),
+    ];
+    for (key, tool) in tools {
+        if keyboard_input.just_pressed(key) {
+            editor.tool = tool;
+        }
+    }
+}
+
+// shift+click toggles a tile in the selection and shift+drag adds a box of
+// tiles to it; escape clears it
+pub fn select_tiles(
+    keyboard_input: Res<Input<KeyCode>>,
+    hover: Res<HoverState>,
+    mut mouse_button_input_events: EventReader<MouseButtonInput>,
+    mut editor: ResMut<Editor>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        editor.selection.clear();
+    }
+    let shift = keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    for mouse_button_input in mouse_button_input_events.read() {
+        if mouse_button_input.button != MouseButton::Left {
+            continue;
+        }
+        if mouse_button_input.state.is_pressed() {
+            if shift {
+                editor.drag_from = hover.hovered;
+            }
+            continue;
+        }
+
+        let Some(from) = editor.drag_from.take() else {
+            continue;
+        };
+        let to = hover.hovered.unwrap_or(from);
+        if from == to {
+            if !editor.selection.remove(&from) {
+                editor.selection.insert(from);
+            }
+        } else {
+            editor.selection.extend(box_between(from, to));
+        }
+    }
+}
+
+// Every tile in the rectangle with corners `from` and `to`.
+fn box_between(from: TilePos, to: TilePos) -> impl Iterator<Item = TilePos> {
+    let xs = from.x.min(to.x)..=from.x.max(to.x);
+    (from.y.min(to.y)..=from.y.max(to.y))
+        .flat_map(move |y| xs.clone().map(move |x| TilePos { x, y }))
+}
+
+// apply the current tool to the highlighted tile when clicked, or to the whole
+// selection when one of its tiles is clicked; delete erases the selection and
+// shift+right-click erases the highlighted tile whatever the tool.
+// Only the floor on show is edited, and only the ground floor takes markers.
+pub fn apply_tool(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    editor: Res<Editor>,
+    next_tile_kind: Res<NextTileKind>,
+    hover: Res<HoverState>,
+    mut gesture_events: EventReader<Gesture>,
+    mut tiles_q: Query<EditedTileQuery>,
+) {
+    // shift+click is for selecting
+    let shift = keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let gestures: Vec<Gesture> = gesture_events.read().copied().collect();
+    let clicked = |button| gestures.contains(&Gesture::Click(button));
+
+    let mut uses = Vec::new();
+    if let Some(pos) = hover
+        .hovered
+        .filter(|_| clicked(MouseButton::Left) && !shift)
+    {
+        let targets = if editor.selection.contains(&pos) {
+            editor.selection.clone()
+        } else {
+            HashSet::from([pos])
+        };
+        uses.push((editor.tool, targets));
+    }
+    if let Some(pos) = hover
+        .hovered
+        .filter(|_| clicked(MouseButton::Right) && shift)
+    {
+        uses.push((EditorTool::Erase, HashSet::from([pos])));
+    }
+    if keyboard_input.any_just_pressed([KeyCode::Delete, KeyCode::Back]) {
+        uses.push((EditorTool::Erase, editor.selection.clone()));
+    }
+
+    for (tool, targets) in uses {
+        for (
+            tile_entity,
+            tile_pos,
+            floor,
+            mut rotation,
+            hex_rotation,
+            tile_type,
+            mut history,
+            player_start,
+            monster,
+        ) in tiles_q.iter_mut()
+        {
+            if floor.0 != hover.floor || !targets.contains(tile_pos) {
+                continue;
+            }
+            let markable = floor.0 == 0;
+            let mut tile = commands.entity(tile_entity);
+            match tool {
+                EditorTool::Paint => {
+                    if let Some(tile_type) = tile_type {
+                        history.0.push(tile_type.kind);
+                    }
+                    tile.insert(TileType {
+                        kind: next_tile_kind.0,
+                    });
+                }
+                EditorTool::Erase => {
+                    if let Some(tile_type) = tile_type {
+                        history.0.push(tile_type.kind);
+                    }
+                    tile.remove::<(TileType, PlayerStart, MonsterSpawn)>();
+                    *rotation = Rotation::default();
+                    if let Some(mut hex_rotation) = hex_rotation {
+                        hex_rotation.0 = 0;
+                    }
+                }
+                // markers are toggled so a misplaced one can be clicked away
+                EditorTool::PlayerStart if player_start => {
+                    tile.remove::<PlayerStart>();
+                }
+                EditorTool::PlayerStart if markable => {
+                    tile.insert(PlayerStart);
+                }
+                EditorTool::Monster if monster => {
+                    tile.remove::<MonsterSpawn>();
+                }
+                EditorTool::Monster if markable => {
+                    tile.insert(MonsterSpawn);
+                }
+                EditorTool::PlayerStart | EditorTool::Monster => {}
+            }
+        }
+    }
+}
+
+// The editor works on the whole board, so nothing is hidden by fog.
+pub fn reveal_tiles(
+    palette: Res<Palette>,
+    mut tiles_q: Query<(&mut TileColor, Has<HighlightedLabel>), With<TilePos>>,
+) {
+    for (mut color, highlighted) in tiles_q.iter_mut() {
+        let tint = if highlighted {
+            palette.highlight()
+        } else {
+            palette.unlit()
+        };
+        if color.0 != tint {
+            color.0 = tint;
+        }
+    }
+}
+
+// Outlines the selection, and the box a shift+drag would add to it.
+pub fn draw_selection(
+    mut gizmos: Gizmos,
+    editor: Res<Editor>,
+    palette: Res<Palette>,
+    hover: Res<HoverState>,
+    tilemap_q: Query<(&TilemapGridSize, &TilemapType, &Transform), With<ShownFloor>>,
+) {
+    let mut outlined: Vec<(TilePos, Color)> = editor
+        .selection
+        .iter()
+        .map(|pos| (*pos, palette.highlight()))
+        .collect();
+    if let (Some(from), Some(to)) = (editor.drag_from, hover.hovered) {
+        outlined.extend(box_between(from, to).map(|pos| (pos, palette.lit())));
+    }
+    for (grid_size, map_type, map_transform) in tilemap_q.iter() {
+        let size = Vec2::new(grid_size.x, grid_size.y) * map_transform.scale.xy() * 0.9;
+        for (tile_pos, color) in &outlined {
+            let center = tile_center(tile_pos, grid_size, map_type, map_transform);
+            if let TilemapType::Hexagon(_) = map_type {
+                gizmos.circle_2d(center, size.x / 2.0, *color);
+            } else {
+                gizmos.rect_2d(center, 0.0, size, *color);
+            }
+        }
+    }
+}
+
+pub fn draw_markers(
+    mut gizmos: Gizmos,
+    current_floor: Res<CurrentFloor>,
+    tilemap_q: Query<(&TilemapGridSize, &TilemapType, &Transform), With<ShownFloor>>,
+    markers_q: Query<(&TilePos, &Floor, Has<PlayerStart>, Has<MonsterSpawn>)>,
+) {
+    for (grid_size, map_type, map_transform) in tilemap_q.iter() {
+        let radius = grid_size.x * map_transform.scale.x * MARKER_RADIUS;
+        for (tile_pos, floor, player_start, monster) in markers_q.iter() {
+            if !current_floor.shows(floor) {
+                continue;
+            }
+            let center = tile_center(tile_pos, grid_size, map_type, map_transform);
+            if player_start {
+                gizmos.circle_2d(center, radius, Color::LIME_GREEN);
+            }
+            if monster {
+                gizmos.circle_2d(center, radius * 0.6, Color::CRIMSON);
+            }
+        }
+    }
+}
+
+pub fn update_overlay(
+    editor: Res<Editor>,
+    strings: Res<Strings>,
+    next_tile_kind: Res<NextTileKind>,
+    mut overlay_q: Query<&mut Text, With<EditorOverlay>>,
+) {
+    let selected = match editor.selection.len() {
+        0 => String::new(),
+        count => strings.format("editor-selected", &[("count", count.to_string())]),
+    };
+    for mut text in overlay_q.iter_mut() {
+        text.sections[0].value = strings.format(
+            "editor-overlay",
+            &[
+                ("tool", strings.get(editor.tool.string_key())),
+                ("tile", strings.tile(next_tile_kind.0)),
+                ("selected", selected.clone()),
+                ("status", editor.status.clone()),
+            ],
+        );
+    }
+}
+
+// write the board out as a scenario file on pressing enter
+pub fn export_scenario(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut editor: ResMut<Editor>,
+    strings: Res<Strings>,
+    (board_config, active_scenario): (Res<BoardConfig>, Res<ActiveScenario>),
+    tiles_q: Query<ScenarioTileQuery>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::Return) {
+        return;
+    }
+
+    let mut scenario = Scenario {
+        size: board_config.size,
+        shape: board_config.shape,
+        floors: board_config.floors,
+        tiles: Vec::new(),
+        stairs: Vec::new(),
+        player_starts: Vec::new(),
+        monsters: Vec::new(),
+        next: None,
+        // the bag isn't edited on the board, so a loaded scenario's is kept
+        bag: active_scenario
+            .0
+            .as_ref()
+            .map_or(Vec::new(), |scenario| scenario.bag.clone()),
+    };
+    for (pos, floor, tile_type, rotation, hex_rotation, stairs, player_start, monster) in
+        tiles_q.iter()
+    {
+        if let Some(tile_type) = tile_type {
+            scenario.tiles.push(ScenarioTile {
+                pos: *pos,
+                floor: floor.0,
+                kind: tile_type.kind,
+                rotation: tile_rotation(rotation, hex_rotation),
+            });
+        }
+        // each flight is written out once, from its foot
+        if stairs.is_some_and(|stairs| stairs.to > floor.0) {
+            scenario.stairs.push((floor.0, *pos));
+        }
+        if player_start {
+            scenario.player_starts.push(*pos);
+        }
+        if monster {
+            scenario.monsters.push(*pos);
+        }
+    }
+    // Query order isn't stable, so sort to keep exports of the same board identical.
+    scenario
+        .tiles
+        .sort_by_key(|tile| (tile.floor, tile.pos.y, tile.pos.x));
+    scenario
+        .stairs
+        .sort_by_key(|(floor, pos)| (*floor, pos.y, pos.x));
+    scenario.player_starts.sort_by_key(|pos| (pos.y, pos.x));
+    scenario.monsters.sort_by_key(|pos| (pos.y, pos.x));
+
+    // Don't overwrite scenarios from earlier sessions.
+    let path = loop {
+        editor.exports += 1;
+        let path = format!("nightcage-{}.scenario", editor.exports);
+        if !Path::new(&path).exists() {
+            break path;
+        }
+    };
+
+    editor.status = match std::fs::write(&path, scenario.to_string()) {
+        Ok(()) => strings.format("editor-exported", &[("path", path.clone())]),
+        Err(err) => {
+            warn!("failed to export {}: {}", path, err);
+            strings.format("editor-export-failed", &[("error", err.to_string())])
+        }
+    };
+}
@@ -0,0 +1,253 @@
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+
+use bevy::prelude::*;
+
+use crate::board::{BoardConfig, BoardShape, HoverEvent};
+use crate::darkness::{PawnHit, WaxEaterEmerged, WaxEaterStirred};
+use crate::difficulty::Difficulty;
+use crate::falling::PawnFell;
+use crate::house_rules::{HouseRule, RuleSet};
+use crate::pawn::{Escaped, KeyPassed, KeyPickedUp};
+use crate::rng::Seed;
+use crate::rules::{PlayerConfig, TileCollapsed, TileDrawn, TilePlaced, Turn};
+use crate::stats::{GameEnded, GameTally};
+
+// With --log-events, everything that happens in play is added to a file as
+// it happens, one JSON object to a line, for looking over games afterwards.
+// Every line has the event's name and the seconds since the game began:
+//
+//     {"event":"start","time":0.000,"seed":1234,"difficulty":"normal",...}
+//     {"event":"draw","time":0.016,"kind":"corner"}
+//     {"event":"place","time":3.210,"player":1,"x":3,"y":4,"kind":"corner","rotation":1}
+//     {"event":"end","time":412.950,"player":1,"won":true,"turns":31}
+//
+// Players are numbered from 1, as they are on screen. Runs add to the end of
+// the file, so it can hold every game a group plays.
+#[derive(Resource, Default)]
+pub struct EventFile {
+    writer: Option<BufWriter<File>>,
+}
+
+impl EventFile {
+    pub fn open(path: &str) -> Result<EventFile, String> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|err| format!("failed to open {}: {}", path, err))?;
+        Ok(EventFile {
+            writer: Some(BufWriter::new(file)),
+        })
+    }
+
+    fn write(&mut self, line: JsonLine) {
+        let Some(writer) = &mut self.writer else {
+            return;
+        };
+        if let Err(err) = writeln!(writer, "{}", line.finish()) {
+            warn!("failed to log an event: {}", err);
+            self.writer = None;
+        }
+    }
+
+    fn flush(&mut self) {
+        if let Some(Err(err)) = self.writer.as_mut().map(|writer| writer.flush()) {
+            warn!("failed to log events: {}", err);
+            self.writer = None;
+        }
+    }
+}
+
+pub fn enabled(file: Res<EventFile>) -> bool {
+    file.writer.is_some()
+}
+
+// A JSON object being written out a field at a time.
+struct JsonLine(String);
+
+impl JsonLine {
+    fn new(event: &str, time: f32) -> JsonLine {
+        JsonLine(String::from("{"))
+            .text("event", event)
+            .field("time", format!("{:.3}", time))
+    }
+
+    fn field(mut self, key: &str, value: impl std::fmt::Display) -> JsonLine {
+        if self.0.len() > 1 {
+            self.0.push(',');
+        }
+        self.0.push_str(&format!("\"{}\":{}", key, value));
+        self
+    }
+
+    fn text(self, key: &str, value: &str) -> JsonLine {
+        let mut quoted = String::from("\"");
+        for c in value.chars() {
+            match c {
+                '"' => quoted.push_str("\\\""),
+                '\\' => quoted.push_str("\\\\"),
+                '\n' => quoted.push_str("\\n"),
+                c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+                c => quoted.push(c),
+            }
+        }
+        quoted.push('"');
+        self.field(key, quoted)
+    }
+
+    fn finish(mut self) -> String {
+        self.0.push('}');
+        self.0
+    }
+
+    fn rules(self, rules: &RuleSet) -> JsonLine {
+        let on: Vec<String> = HouseRule::ALL
+            .into_iter()
+            .filter(|rule| rules.get(*rule))
+            .map(|rule| format!("\"{}\"", rule.name()))
+            .collect();
+        self.field("rules", format!("[{}]", on.join(",")))
+    }
+}
+
+pub fn log_game_start(
+    mut file: ResMut<EventFile>,
+    (seed, difficulty, rules): (Res<Seed>, Res<Difficulty>, Res<RuleSet>),
+    (board, players): (Res<BoardConfig>, Res<PlayerConfig>),
+) {
+    let shape = match board.shape {
+        BoardShape::Square => "square",
+        BoardShape::Hex => "hex",
+    };
+    let line = JsonLine::new("start", 0.0)
+        .field("seed", seed.0)
+        .text("difficulty", difficulty.name())
+        .field("width", board.size.x)
+        .field("height", board.size.y)
+        .text("shape", shape)
+        .field("floors", board.floors)
+        .field("bots", players.bots)
+        .rules(&rules);
+    file.write(line);
+    file.flush();
+}
+
+pub fn log_events(
+    (time, tally, turn, rules): (Res<Time>, Res<GameTally>, Res<Turn>, Res<RuleSet>),
+    // the house rules as last logged, as they can be changed mid game
+    (mut file, mut logged_rules): (ResMut<EventFile>, Local<Option<RuleSet>>),
+    (mut drawn_events, mut placed_events, mut collapse_events): (
+        EventReader<TileDrawn>,
+        EventReader<TilePlaced>,
+        EventReader<TileCollapsed>,
+    ),
+    mut hover_events: EventReader<HoverEvent>,
+    (mut fell_events, mut picked_up_events, mut passed_events, mut escaped_events): (
+        EventReader<PawnFell>,
+        EventReader<KeyPickedUp>,
+        EventReader<KeyPassed>,
+        EventReader<Escaped>,
+    ),
+    (mut stirred_events, mut emerged_events, mut hit_events): (
+        EventReader<WaxEaterStirred>,
+        EventReader<WaxEaterEmerged>,
+        EventReader<PawnHit>,
+    ),
+    mut ended_events: EventReader<GameEnded>,
+) {
+    let now = time.elapsed_seconds() - tally.started;
+    let line = |event: &str| JsonLine::new(event, now);
+    let mut lines = Vec::new();
+
+    if logged_rules.is_some_and(|logged| logged != *rules) {
+        lines.push(line("rules").rules(&rules));
+    }
+    *logged_rules = Some(*rules);
+    if turn.is_changed() {
+        lines.push(line("turn").field("player", turn.player + 1));
+    }
+    for drawn in drawn_events.read() {
+        lines.push(line("draw").text("kind", drawn.kind.name()));
+    }
+    for placed in placed_events.read() {
+        lines.push(
+            line("place")
+                .field("player", placed.player + 1)
+                .field("x", placed.pos.x)
+                .field("y", placed.pos.y)
+                .text("kind", placed.kind.name())
+                .field("rotation", placed.rotation),
+        );
+    }
+    for collapsed in collapse_events.read() {
+        lines.push(
+            line("collapse")
+                .field("x", collapsed.pos.x)
+                .field("y", collapsed.pos.y)
+                .text("kind", collapsed.kind.name()),
+        );
+    }
+    // the candle's light, following the cursor
+    for hover in hover_events.read() {
+        let (event, pos) = match hover {
+            HoverEvent::Lit(pos) => ("lit", pos),
+            HoverEvent::Unlit(pos) => ("unlit", pos),
+            _ => continue,
+        };
+        lines.push(line(event).field("x", pos.x).field("y", pos.y));
+    }
+    for fell in fell_events.read() {
+        lines.push(line("fell").field("player", fell.player + 1));
+    }
+    for picked_up in picked_up_events.read() {
+        lines.push(line("key-picked-up").field("player", picked_up.player + 1));
+    }
+    for passed in passed_events.read() {
+        lines.push(
+            line("key-passed")
+                .field("from", passed.from + 1)
+                .field("to", passed.to + 1),
+        );
+    }
+    for escaped in escaped_events.read() {
+        lines.push(line("escaped").field("player", escaped.player + 1));
+    }
+    for stirred in stirred_events.read() {
+        lines.push(
+            line("wax-eater-stirred")
+                .field("x", stirred.pos.x)
+                .field("y", stirred.pos.y),
+        );
+    }
+    for emerged in emerged_events.read() {
+        lines.push(
+            line("wax-eater-emerged")
+                .field("x", emerged.pos.x)
+                .field("y", emerged.pos.y),
+        );
+    }
+    for hit in hit_events.read() {
+        lines.push(
+            line("hit")
+                .field("player", hit.player + 1)
+                .field("survived", hit.survived),
+        );
+    }
+    for ended in ended_events.read() {
+        lines.push(
+            line("end")
+                .field("player", ended.player + 1)
+                .field("won", ended.won)
+                .field("turns", tally.turns),
+        );
+    }
+
+    if lines.is_empty() {
+        return;
+    }
+    for line in lines {
+        file.write(line);
+    }
+    file.flush();
+}
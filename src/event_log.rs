@@ -0,0 +1,205 @@
+use bevy::prelude::*;
+
+use crate::darkness::{PawnHit, WaxEaterEmerged, WaxEaterStirred};
+use crate::locale::Strings;
+use crate::pawn::KeyPassed;
+use crate::ping::PingTile;
+use crate::rules::{TileCollapsed, TilePlaced, Turn};
+
+// Oldest entries are dropped past this many.
+const MAX_ENTRIES: usize = 200;
+const VISIBLE_LINES: usize = 8;
+
+// What has happened so far this game, newest last.
+#[derive(Resource)]
+pub struct EventLog {
+    pub entries: Vec<String>,
+    // Entries pushed over the whole session, including any since dropped or
+    // cleared away.
+    pub recorded: usize,
+    // Lines scrolled back from the newest entry.
+    scroll: usize,
+    visible: bool,
+}
+
+impl Default for EventLog {
+    fn default() -> Self {
+        Self {
+            entries: Vec::new(),
+            recorded: 0,
+            scroll: 0,
+            visible: true,
+        }
+    }
+}
+
+impl EventLog {
+    pub fn push(&mut self, entry: String) {
+        self.entries.push(entry);
+        self.recorded += 1;
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.remove(0);
+        }
+        // keep what was being read in place while scrolled back
+        if self.scroll > 0 {
+            self.scroll = (self.scroll + 1).min(self.max_scroll());
+        }
+    }
+
+    // Empties the log for a new game.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.scroll = 0;
+    }
+
+    // Entries pushed after the first `seen`, as far back as the log still goes.
+    pub fn since(&self, seen: usize) -> &[String] {
+        let new = (self.recorded - seen).min(self.entries.len());
+        &self.entries[self.entries.len() - new..]
+    }
+
+    fn max_scroll(&self) -> usize {
+        self.entries.len().saturating_sub(VISIBLE_LINES)
+    }
+
+    fn visible_entries(&self) -> &[String] {
+        let end = self.entries.len() - self.scroll;
+        &self.entries[end.saturating_sub(VISIBLE_LINES)..end]
+    }
+}
+
+#[derive(Component)]
+pub struct EventLogPanel;
+
+pub fn spawn_event_log(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 16.0,
+                color: Color::ORANGE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(8.0),
+            right: Val::Px(8.0),
+            ..default()
+        }),
+        EventLogPanel,
+    ));
+}
+
+pub fn record_events(
+    mut log: ResMut<EventLog>,
+    strings: Res<Strings>,
+    turn: Res<Turn>,
+    mut placed_events: EventReader<TilePlaced>,
+    mut collapse_events: EventReader<TileCollapsed>,
+    (mut stirred_events, mut emerged_events, mut hit_events): (
+        EventReader<WaxEaterStirred>,
+        EventReader<WaxEaterEmerged>,
+        EventReader<PawnHit>,
+    ),
+    (mut passed_events, mut ping_events): (EventReader<KeyPassed>, EventReader<PingTile>),
+) {
+    for placed in placed_events.read() {
+        log.push(strings.format(
+            "log-placed",
+            &[
+                ("player", (placed.player + 1).to_string()),
+                ("tile", strings.tile(placed.kind)),
+                ("x", placed.pos.x.to_string()),
+                ("y", placed.pos.y.to_string()),
+            ],
+        ));
+    }
+    for collapsed in collapse_events.read() {
+        log.push(strings.format(
+            "log-collapsed",
+            &[
+                ("x", collapsed.pos.x.to_string()),
+                ("y", collapsed.pos.y.to_string()),
+            ],
+        ));
+    }
+    for stirred in stirred_events.read() {
+        log.push(strings.format(
+            "log-stirred",
+            &[
+                ("x", stirred.pos.x.to_string()),
+                ("y", stirred.pos.y.to_string()),
+            ],
+        ));
+    }
+    for emerged in emerged_events.read() {
+        log.push(strings.format(
+            "log-emerged",
+            &[
+                ("x", emerged.pos.x.to_string()),
+                ("y", emerged.pos.y.to_string()),
+            ],
+        ));
+    }
+    for hit in hit_events.read() {
+        let key = if hit.survived {
+            "log-hit-survived"
+        } else {
+            "log-hit"
+        };
+        log.push(strings.format(key, &[("player", (hit.player + 1).to_string())]));
+    }
+    for passed in passed_events.read() {
+        log.push(strings.format(
+            "log-key-passed",
+            &[
+                ("from", (passed.from + 1).to_string()),
+                ("to", (passed.to + 1).to_string()),
+            ],
+        ));
+    }
+    for ping in ping_events.read() {
+        log.push(strings.format(
+            "log-ping",
+            &[
+                ("player", (ping.player + 1).to_string()),
+                ("x", ping.pos.x.to_string()),
+                ("y", ping.pos.y.to_string()),
+            ],
+        ));
+    }
+    if turn.is_changed() {
+        log.push(strings.format("log-turn", &[("player", (turn.player + 1).to_string())]));
+    }
+}
+
+// toggle the log on pressing l, and scroll it with page up and page down
+pub fn event_log_input(keyboard_input: Res<Input<KeyCode>>, mut log: ResMut<EventLog>) {
+    if keyboard_input.just_pressed(KeyCode::L) {
+        log.visible = !log.visible;
+    }
+    if keyboard_input.just_pressed(KeyCode::PageUp) {
+        log.scroll = (log.scroll + 1).min(log.max_scroll());
+    }
+    if keyboard_input.just_pressed(KeyCode::PageDown) {
+        log.scroll = log.scroll.saturating_sub(1);
+    }
+}
+
+pub fn update_event_log_panel(
+    log: Res<EventLog>,
+    mut panel_q: Query<(&mut Text, &mut Visibility), With<EventLogPanel>>,
+) {
+    if !log.is_changed() {
+        return;
+    }
+    for (mut text, mut visibility) in panel_q.iter_mut() {
+        *visibility = if log.visible {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        text.sections[0].value = log.visible_entries().join("\n");
+    }
+}
@@ -0,0 +1,122 @@
+use bevy::prelude::*;
+use bevy_ecs_tilemap::prelude::*;
+
+use crate::ai::{placements, BoardView, Placement};
+use crate::board::{BoardShape, HexRotation, Rotation, TileType};
+use crate::inspect::BoardInspector;
+use crate::pawn::{Keys, Pawn};
+use crate::rules::{Dealer, Player, TileCollapsed};
+use crate::tiles::{Quarter, TileKind};
+
+// A player's pawn fell through the board, its candle going out.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct PawnFell {
+    pub player: usize,
+}
+
+// Pawns whose tile collapses, or who are left standing in darkness, fall.
+// They lose the keys they carry, and land on the floor below if there is one.
+pub fn resolve_falls(
+    mut collapse_events: EventReader<TileCollapsed>,
+    inspector: BoardInspector,
+    mut pawns_q: Query<(&Player, &mut Pawn, &mut Keys)>,
+    mut fell_events: EventWriter<PawnFell>,
+) {
+    let collapsed: Vec<(u32, TilePos)> = collapse_events
+        .read()
+        .map(|event| (event.floor, event.pos))
+        .collect();
+    for (player, mut pawn, mut keys) in pawns_q.iter_mut() {
+        let in_darkness = inspector
+            .tile_info_on(pawn.floor, &pawn.pos)
+            .is_none_or(|info| info.kind == TileKind::Empty);
+        let under_collapse = collapsed.contains(&(pawn.floor, pawn.pos));
+        if !in_darkness && !under_collapse {
+            continue;
+        }
+
+        keys.0 = 0;
+        pawn.floor = pawn.floor.saturating_sub(1);
+        fell_events.send(PawnFell {
+            player: player.number,
+        });
+    }
+}
+
+// A fresh tile from the bag is placed somewhere legal for each fallen pawn to
+// land on.
+pub fn land_fallen_pawns(
+    mut commands: Commands,
+    mut fell_events: EventReader<PawnFell>,
+    mut dealer: Dealer,
+    inspector: BoardInspector,
+    mut pawns_q: Query<(&Player, &mut Pawn)>,
+) {
+    let fallen: Vec<usize> = fell_events.read().map(|fell| fell.player).collect();
+    let pawns = pawns_q
+        .iter_mut()
+        .filter(|(player, _)| fallen.contains(&player.number))
+        .map(|(_, pawn)| pawn);
+    land(&mut commands, &mut dealer, &inspector, pawns);
+}
+
+// Pawns starting out in the dark, as they do on a board without player
+// starts, are given their first tile the same way, without having fallen.
+pub fn lay_starting_tiles(
+    mut commands: Commands,
+    mut dealer: Dealer,
+    inspector: BoardInspector,
+    mut pawns_q: Query<&mut Pawn>,
+) {
+    let pawns = pawns_q.iter_mut().filter(|pawn| {
+        inspector
+            .tile_info_on(pawn.floor, &pawn.pos)
+            .is_none_or(|info| info.kind == TileKind::Empty)
+    });
+    land(&mut commands, &mut dealer, &inspector, pawns);
+}
+
+// Lands each pawn on a tile drawn for it, on its floor, where the tile fits
+// best. Pawns landing together land on the board as it was before any of
+// them did, and don't share a tile.
+fn land<'a>(
+    commands: &mut Commands,
+    dealer: &mut Dealer,
+    inspector: &BoardInspector,
+    pawns: impl Iterator<Item = Mut<'a, Pawn>>,
+) {
+    let mut landed: Vec<(u32, TilePos)> = Vec::new();
+    for mut pawn in pawns {
+        let kind = dealer.draw_from_bag();
+        let floor = pawn.floor;
+        let view = BoardView::on_floor(inspector, floor);
+        let taken: Vec<TilePos> = landed
+            .iter()
+            .filter(|(landed_floor, _)| *landed_floor == floor)
+            .map(|(_, pos)| *pos)
+            .collect();
+        let Some(landing) = landing(&view, kind, &taken) else {
+            continue;
+        };
+        let Some(info) = inspector.tile_info_on(floor, &landing.pos) else {
+            continue;
+        };
+
+        let mut tile = commands.entity(info.entity);
+        tile.insert(TileType { kind });
+        match inspector.board_config().shape {
+            BoardShape::Square => tile.insert(Rotation(Quarter::from_turns(landing.rotation))),
+            BoardShape::Hex => tile.insert(HexRotation(landing.rotation)),
+        };
+        landed.push((floor, landing.pos));
+        pawn.pos = landing.pos;
+    }
+}
+
+// Where a fallen pawn lands with a fresh tile of `kind`: the best placement
+// that isn't already taken by another pawn landing at the same time.
+pub fn landing(view: &BoardView, kind: TileKind, landed: &[TilePos]) -> Option<Placement> {
+    placements(view, kind)
+        .into_iter()
+        .find(|placement| !landed.contains(&placement.pos))
+}
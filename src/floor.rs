@@ -0,0 +1,217 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+use bevy_ecs_tilemap::prelude::*;
+
+use crate::board::{tile_center, BoardConfig};
+use crate::inspect::BoardInspector;
+use crate::pawn::Pawn;
+use crate::rng::GameRng;
+use crate::rules::{Bot, Player, Turn, TurnBudget};
+use crate::tiles::TileKind;
+
+const STAIRS_SIZE: f32 = 0.2;
+
+// Which floor of the board a tilemap or tile is on, counting up from the
+// ground floor. Every floor is a tilemap of its own, laid out over the others,
+// and light never crosses from one to another.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Floor(pub u32);
+
+// The floor on show. It follows the pawn whose turn it is, and can be switched
+// to look around the others.
+#[derive(Resource, Default)]
+pub struct CurrentFloor(pub u32);
+
+impl CurrentFloor {
+    pub fn shows(&self, floor: &Floor) -> bool {
+        self.0 == floor.0
+    }
+}
+
+// The tilemap of the floor on show. Anything drawing over the board only
+// needs to draw over this one.
+#[derive(Component)]
+pub struct ShownFloor;
+
+// A flight of stairs from a cell to the same cell on floor `to`. Both ends of
+// the flight have one.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Stairs {
+    pub to: u32,
+}
+
+// Finds the cells of each floor.
+#[derive(SystemParam)]
+pub struct Floors<'w, 's> {
+    current: Res<'w, CurrentFloor>,
+    tilemap_q: Query<'w, 's, (&'static TileStorage, &'static Floor)>,
+}
+
+impl<'w, 's> Floors<'w, 's> {
+    pub fn current(&self) -> u32 {
+        self.current.0
+    }
+
+    // The cell at `pos` on the floor on show.
+    pub fn tile(&self, pos: &TilePos) -> Option<Entity> {
+        self.tile_on(self.current.0, pos)
+    }
+
+    pub fn tile_on(&self, floor: u32, pos: &TilePos) -> Option<Entity> {
+        self.tilemap_q
+            .iter()
+            .find(|(_, tilemap_floor)| tilemap_floor.0 == floor)
+            .and_then(|(tile_storage, _)| tile_storage.checked_get(pos))
+    }
+}
+
+// A request to take the stairs a player's pawn is standing on.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct TakeStairs {
+    pub player: usize,
+}
+
+// Where the stairs go when there's no scenario laying them out: one flight
+// between each floor and the next, somewhere random on the board. A cell only
+// has room for one end of a flight, so no flight starts where the one below
+// comes up, unless the board has only the one cell.
+pub fn random_stairs(board_config: &BoardConfig, rng: &mut GameRng) -> Vec<(u32, TilePos)> {
+    let size = board_config.size;
+    let mut stairs: Vec<(u32, TilePos)> = Vec::new();
+    for floor in 0..board_config.floors.saturating_sub(1) {
+        let below = stairs.last().map(|(_, pos)| *pos);
+        let pos = loop {
+            let pos = TilePos {
+                x: rng.0.u32(..size.x),
+                y: rng.0.u32(..size.y),
+            };
+            if below != Some(pos) || size.x * size.y == 1 {
+                break pos;
+            }
+        };
+        stairs.push((floor, pos));
+    }
+    stairs
+}
+
+// Whether two flights of stairs, each given by the floor it leads up from,
+// would end on the same cell.
+pub fn stairs_meet(a: &(u32, TilePos), b: &(u32, TilePos)) -> bool {
+    a.1 == b.1 && a.0.abs_diff(b.0) <= 1
+}
+
+// look at the floor below or above on pressing [ or ]
+pub fn view_floor_input(
+    keyboard_input: Res<Input<KeyCode>>,
+    board_config: Res<BoardConfig>,
+    mut current_floor: ResMut<CurrentFloor>,
+) {
+    if keyboard_input.just_pressed(KeyCode::BracketLeft) && current_floor.0 > 0 {
+        current_floor.0 -= 1;
+    }
+    if keyboard_input.just_pressed(KeyCode::BracketRight)
+        && current_floor.0 + 1 < board_config.floors
+    {
+        current_floor.0 += 1;
+    }
+}
+
+// take the stairs on pressing u
+pub fn stairs_input(
+    keyboard_input: Res<Input<KeyCode>>,
+    turn: Res<Turn>,
+    bots_q: Query<&Player, With<Bot>>,
+    mut stairs_events: EventWriter<TakeStairs>,
+) {
+    if keyboard_input.just_pressed(KeyCode::U) && !turn.is_bot(&bots_q) {
+        stairs_events.send(TakeStairs {
+            player: turn.player,
+        });
+    }
+}
+
+// Taking the stairs costs an action, and needs a tile placed at the other end
+// to step out onto.
+pub fn take_stairs(
+    mut stairs_events: EventReader<TakeStairs>,
+    inspector: BoardInspector,
+    mut pawns_q: Query<(&Player, &mut Pawn, &mut TurnBudget)>,
+) {
+    for take_stairs in stairs_events.read() {
+        let Some((_, mut pawn, mut budget)) = pawns_q
+            .iter_mut()
+            .find(|(player, ..)| player.number == take_stairs.player)
+        else {
+            continue;
+        };
+        let Some(to) = inspector
+            .tile_info_on(pawn.floor, &pawn.pos)
+            .and_then(|info| info.stairs)
+        else {
+            continue;
+        };
+        let landing_placed = inspector
+            .tile_info_on(to, &pawn.pos)
+            .is_some_and(|info| info.kind != TileKind::Empty);
+        if landing_placed && budget.spend() {
+            pawn.floor = to;
+        }
+    }
+}
+
+// Show the floor of the pawn whose turn it is, once the turn passes to it or
+// it changes floor.
+pub fn follow_turn(
+    turn: Res<Turn>,
+    mut current_floor: ResMut<CurrentFloor>,
+    pawns_q: Query<(&Player, Ref<Pawn>)>,
+) {
+    for (player, pawn) in pawns_q.iter() {
+        let moved = turn.is_changed() || pawn.is_changed();
+        if player.number == turn.player && moved && current_floor.0 != pawn.floor {
+            current_floor.0 = pawn.floor;
+        }
+    }
+}
+
+// Only the floor on show is drawn.
+pub fn show_current_floor(
+    mut commands: Commands,
+    current_floor: Res<CurrentFloor>,
+    mut tilemap_q: Query<(Entity, Ref<Floor>, &mut Visibility), With<TileStorage>>,
+) {
+    for (tilemap_entity, floor, mut visibility) in tilemap_q.iter_mut() {
+        if !current_floor.is_changed() && !floor.is_added() {
+            continue;
+        }
+        let mut tilemap = commands.entity(tilemap_entity);
+        if current_floor.shows(&floor) {
+            *visibility = Visibility::Inherited;
+            tilemap.insert(ShownFloor);
+        } else {
+            *visibility = Visibility::Hidden;
+            tilemap.remove::<ShownFloor>();
+        }
+    }
+}
+
+// Stairs are drawn as a chevron pointing the way they go.
+pub fn draw_stairs(
+    mut gizmos: Gizmos,
+    current_floor: Res<CurrentFloor>,
+    tilemap_q: Query<(&TilemapGridSize, &TilemapType, &Transform), With<ShownFloor>>,
+    stairs_q: Query<(&TilePos, &Floor, &Stairs)>,
+) {
+    for (grid_size, map_type, map_transform) in tilemap_q.iter() {
+        let reach = grid_size.x * map_transform.scale.x * STAIRS_SIZE;
+        for (tile_pos, floor, stairs) in stairs_q.iter() {
+            if !current_floor.shows(floor) {
+                continue;
+            }
+            let center = tile_center(tile_pos, grid_size, map_type, map_transform);
+            let tip = if stairs.to > floor.0 { reach } else { -reach };
+            let point = center + Vec2::new(0.0, tip);
+            gizmos.line_2d(center + Vec2::new(-reach, 0.0), point, Color::SILVER);
+            gizmos.line_2d(center + Vec2::new(reach, 0.0), point, Color::SILVER);
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use bevy_ecs_tilemap::prelude::*;
+
+use crate::board::{neighbors, rotation_steps, BoardConfig};
+use crate::difficulty::Difficulty;
+use crate::house_rules::RuleSet;
+use crate::path::{board_distances, Paths};
+use crate::pawn::can_move_between;
+use crate::rng::Seed;
+use crate::scenario::{Scenario, ScenarioTile};
+use crate::simulation::{Action, Simulation};
+use crate::tiles::TileKind;
+
+// Setups tried before giving up on a seed.
+const ATTEMPTS: usize = 64;
+// Most actions the solver takes over a setup before it's thrown out.
+const SOLVE_ACTIONS: usize = 400;
+// Kinds scattered over the board ahead of play.
+const CORRIDORS: [TileKind; 5] = [
+    TileKind::Straight,
+    TileKind::Corner,
+    TileKind::TJunction,
+    TileKind::Crossing,
+    TileKind::DeadEnd,
+];
+
+// With --generate the board is set up from the seed and difficulty rather
+// than from a scenario file: a crossing to start on, a key well away from it
+// and a gate as far again, corridors scattered about and a bag mixed a little
+// differently from the difficulty's. Each setup is played out by `solve` on
+// the seed it was made for, and only one a lone player can escape from on
+// the default rules is kept.
+pub fn generate(
+    seed: Seed,
+    difficulty: Difficulty,
+    board: &BoardConfig,
+) -> Result<Scenario, String> {
+    let mut rng = fastrand::Rng::with_seed(seed.0);
+    for _ in 0..ATTEMPTS {
+        let scenario = random_setup(&mut rng, difficulty, board);
+        let mut sim = Simulation::new(
+            *board,
+            Some(&scenario),
+            1,
+            difficulty,
+            RuleSet::default(),
+            seed,
+        );
+        if solve(&mut sim, SOLVE_ACTIONS).is_some() {
+            return Ok(scenario);
+        }
+    }
+    Err(format!(
+        "couldn't generate a board that can be won from seed {}",
+        seed.0
+    ))
+}
+
+// Cells scattered corridors go down on, one to every so many of the board's.
+fn cells_per_corridor(difficulty: Difficulty) -> u32 {
+    match difficulty {
+        Difficulty::Easy => 6,
+        Difficulty::Normal => 10,
+        Difficulty::Nightmare => 16,
+    }
+}
+
+fn random_setup(rng: &mut fastrand::Rng, difficulty: Difficulty, board: &BoardConfig) -> Scenario {
+    let size = board.size;
+    let map_type = board.map_type();
+    let steps = rotation_steps(&map_type);
+    let cells: Vec<TilePos> = (0..size.y)
+        .flat_map(|y| (0..size.x).map(move |x| TilePos { x, y }))
+        .collect();
+    // the cell with at least `apart` steps to spare from both, or failing
+    // that the furthest, picked at random between equals
+    let away_from = |rng: &mut fastrand::Rng, from: &[&Paths], apart: u32| {
+        let spare = |pos: &TilePos| {
+            from.iter()
+                .map(|paths| paths.steps(pos).unwrap_or(0))
+                .min()
+                .unwrap_or(0)
+        };
+        let furthest = cells.iter().map(spare).max().unwrap_or(0);
+        let wanted = apart.min(furthest).max(1);
+        let far: Vec<TilePos> = cells
+            .iter()
+            .copied()
+            .filter(|pos| spare(pos) >= wanted)
+            .collect();
+        far[rng.usize(..far.len())]
+    };
+
+    let start = cells[rng.usize(..cells.len())];
+    let apart = size.x.max(size.y) / 2;
+    let from_start = board_distances([start], &size, &map_type, false);
+    let key = away_from(rng, &[&from_start], apart);
+    let from_key = board_distances([key], &size, &map_type, false);
+    let gate = away_from(rng, &[&from_start, &from_key], apart);
+
+    let tile = |pos: TilePos, kind: TileKind, rotation: u8| ScenarioTile {
+        pos,
+        floor: 0,
+        kind,
+        rotation,
+    };
+    let mut tiles = vec![
+        tile(start, TileKind::Crossing, 0),
+        tile(key, TileKind::Key, rng.u8(0..steps)),
+        tile(gate, TileKind::Gate, rng.u8(0..steps)),
+    ];
+    let scattered = cells.len() as u32 / cells_per_corridor(difficulty);
+    for _ in 0..scattered {
+        let pos = cells[rng.usize(..cells.len())];
+        if tiles.iter().any(|tile| tile.pos == pos) {
+            continue;
+        }
+        let kind = CORRIDORS[rng.usize(..CORRIDORS.len())];
+        tiles.push(tile(pos, kind, rng.u8(0..steps)));
+    }
+
+    // one more or fewer of each corridor, keeping at least one of each
+    let bag = difficulty
+        .bag_mix()
+        .into_iter()
+        .map(|(kind, count)| {
+            if CORRIDORS.contains(&kind) {
+                (kind, (count + rng.usize(0..=2)).saturating_sub(1).max(1))
+            } else {
+                (kind, count)
+            }
+        })
+        .collect();
+
+    Scenario {
+        size,
+        shape: board.shape,
+        floors: 1,
+        tiles,
+        stairs: Vec::new(),
+        player_starts: vec![start],
+        monsters: Vec::new(),
+        next: None,
+        bag,
+    }
+}
+
+// Plays a game out with a simple plan: walk to a key, then to a gate, and
+// while neither can be reached put the tile in hand down next to where the
+// pawn can walk, as near them as it goes. Returns the actions taken if the
+// pawn escaped within `max_actions`.
+pub fn solve(sim: &mut Simulation, max_actions: usize) -> Option<Vec<Action>> {
+    let mut actions = Vec::new();
+    while !sim.escaped() {
+        if actions.len() >= max_actions {
+            return None;
+        }
+        let action = next_action(sim)?;
+        if !sim.apply(action) {
+            return None;
+        }
+        actions.push(action);
+    }
+    Some(actions)
+}
+
+fn next_action(sim: &Simulation) -> Option<Action> {
+    let pawn = sim.pawns()[sim.turn.player];
+    let goal = if pawn.keys == 0 {
+        TileKind::Key
+    } else {
+        TileKind::Gate
+    };
+    let mut targets: Vec<TilePos> = sim
+        .tiles()
+        .filter(|(_, kind, _)| *kind == goal)
+        .map(|(pos, ..)| pos)
+        .collect();
+    targets.sort_by_key(|pos| (pos.y, pos.x));
+
+    let walks = Paths::search([pawn.pos], u32::MAX, |pos| {
+        neighbors(pos, &sim.size, &sim.map_type, sim.wraps())
+            .into_iter()
+            .filter(|to| {
+                can_move_between(pos, to, &sim.size, &sim.map_type, sim.wraps(), |pos| {
+                    (sim.tile(pos) != TileKind::Empty).then(|| sim.passages(pos))
+                })
+            })
+            .collect()
+    });
+    let walk = targets
+        .iter()
+        .filter_map(|target| walks.path_to(target))
+        .min_by_key(|path| path.len());
+    if let Some(step) = walk.and_then(|path| path.first().copied()) {
+        return Some(Action::Move(step));
+    }
+
+    // sorted so ties always break the same way
+    let mut reached: Vec<TilePos> = walks.reached().copied().collect();
+    reached.sort_by_key(|pos| (pos.y, pos.x));
+    let to_targets = board_distances(targets, &sim.size, &sim.map_type, sim.wraps());
+    let rotations = rotation_steps(&sim.map_type);
+    let mut best: Option<(u32, TilePos, u8)> = None;
+    for from in &reached {
+        for pos in neighbors(from, &sim.size, &sim.map_type, sim.wraps()) {
+            if sim.tile(&pos) != TileKind::Empty {
+                continue;
+            }
+            let steps = to_targets.steps(&pos).unwrap_or(u32::MAX);
+            // the way the tile's already turned is tried first, to save turning it
+            for turns in 0..rotations {
+                let rotation = (sim.hand_rotation + turns) % rotations;
+                let joins = sim.illuminated_by(&pos, sim.hand, rotation).contains(from);
+                if joins && best.is_none_or(|(best_steps, ..)| steps < best_steps) {
+                    best = Some((steps, pos, rotation));
+                }
+            }
+        }
+    }
+    match best {
+        Some((_, _, rotation)) if rotation != sim.hand_rotation => Some(Action::Rotate),
+        Some((_, pos, _)) => Some(Action::Place(pos)),
+        // the tile in hand can't lead anywhere, so it's put out of the way
+        None => (0..sim.size.y)
+            .flat_map(|y| (0..sim.size.x).map(move |x| TilePos { x, y }))
+            .filter(|pos| sim.tile(pos) == TileKind::Empty)
+            .max_by_key(|pos| to_targets.steps(pos).unwrap_or(0))
+            .map(Action::Place),
+    }
+}
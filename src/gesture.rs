@@ -0,0 +1,141 @@
+use bevy::{prelude::*, window::PrimaryWindow};
+
+// Mouse buttons are read as gestures rather than as presses, so one button can
+// both place tiles and drag the view around without a drag ever placing one.
+// Only the tray's drag and drop, which starts from a UI press, and the
+// editor's shift+drag selection go by the buttons themselves.
+#[derive(Event, Clone, Copy, Debug, PartialEq)]
+pub enum Gesture {
+    // Pressed and let go without the cursor wandering, sent on letting go.
+    Click(MouseButton),
+    // A second click soon after the first and on the same spot, sent instead
+    // of its click.
+    DoubleClick(MouseButton),
+    // The cursor moved `delta` window pixels with the button held, once it's
+    // wandered too far for a click. Window pixels count down the screen.
+    Drag { button: MouseButton, delta: Vec2 },
+    // Let go after dragging.
+    DragEnded(MouseButton),
+}
+
+// Set from the command line with --drag-distance and --double-click.
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct GestureSettings {
+    // Window pixels the cursor can wander with a button held before the press
+    // is a drag rather than a click.
+    pub drag_distance: f32,
+    // Seconds a second click can come after the first and still make a double
+    // click.
+    pub double_click_time: f32,
+}
+
+impl Default for GestureSettings {
+    fn default() -> Self {
+        GestureSettings {
+            drag_distance: 6.0,
+            double_click_time: 0.3,
+        }
+    }
+}
+
+const BUTTONS: [MouseButton; 3] = [MouseButton::Left, MouseButton::Right, MouseButton::Middle];
+
+#[derive(Clone, Copy, Debug)]
+struct Press {
+    button: MouseButton,
+    from: Vec2,
+    last: Vec2,
+    dragging: bool,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Click {
+    button: MouseButton,
+    at: Vec2,
+    // Seconds since startup.
+    time: f32,
+}
+
+// The buttons held down and the last click, for telling the next one apart.
+#[derive(Resource, Default)]
+pub struct GestureTracker {
+    presses: Vec<Press>,
+    last_click: Option<Click>,
+}
+
+pub fn interpret_gestures(
+    time: Res<Time>,
+    settings: Res<GestureSettings>,
+    mouse_button_input: Res<Input<MouseButton>>,
+    window_q: Query<&Window, With<PrimaryWindow>>,
+    mut tracker: ResMut<GestureTracker>,
+    mut gesture_events: EventWriter<Gesture>,
+) {
+    let cursor = window_q
+        .get_single()
+        .ok()
+        .and_then(|window| window.cursor_position());
+    let now = time.elapsed_seconds();
+
+    for button in BUTTONS {
+        if mouse_button_input.just_pressed(button) {
+            // presses outside the window can't be placed anywhere
+            if let Some(at) = cursor {
+                tracker.presses.retain(|press| press.button != button);
+                tracker.presses.push(Press {
+                    button,
+                    from: at,
+                    last: at,
+                    dragging: false,
+                });
+            }
+        }
+
+        let Some(index) = tracker
+            .presses
+            .iter()
+            .position(|press| press.button == button)
+        else {
+            continue;
+        };
+        let press = &mut tracker.presses[index];
+        // the cursor stays where it was last seen while it's out of the window
+        let at = cursor.unwrap_or(press.last);
+        if !press.dragging && at.distance(press.from) > settings.drag_distance {
+            press.dragging = true;
+            press.last = press.from;
+        }
+        if press.dragging && at != press.last {
+            gesture_events.send(Gesture::Drag {
+                button,
+                delta: at - press.last,
+            });
+        }
+        press.last = at;
+
+        if mouse_button_input.pressed(button) {
+            continue;
+        }
+        let press = tracker.presses.swap_remove(index);
+        if press.dragging {
+            gesture_events.send(Gesture::DragEnded(button));
+            continue;
+        }
+        let double = tracker.last_click.is_some_and(|click| {
+            click.button == button
+                && click.at.distance(press.from) <= settings.drag_distance
+                && now - click.time <= settings.double_click_time
+        });
+        if double {
+            tracker.last_click = None;
+            gesture_events.send(Gesture::DoubleClick(button));
+        } else {
+            tracker.last_click = Some(Click {
+                button,
+                at: press.from,
+                time: now,
+            });
+            gesture_events.send(Gesture::Click(button));
+        }
+    }
+}
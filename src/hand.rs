@@ -0,0 +1,299 @@
+use bevy::{prelude::*, ui::RelativeCursorPosition};
+use bevy_ecs_tilemap::prelude::*;
+
+use crate::board::{tile_rotation, HexRotation, HighlightedLabel, NextTileKind, Rotation};
+use crate::floor::Floor;
+use crate::locale::Strings;
+use crate::pack::TilePack;
+use crate::palette::Palette;
+use crate::pawn::{pawn_floor, Pawn};
+use crate::rules::{Bot, HeldTiles, PlaceTile, Player, SwapHand, TileBag, Turn};
+use crate::snapshot::render_tile;
+use crate::tiles::TileKind;
+
+// Size of the tile shown in the tray, in pixels.
+const TRAY_TILE_SIZE: f32 = 96.0;
+// How much the tray's tile fades while it's being dragged away.
+const LIFTED_ALPHA: f32 = 0.35;
+const PILE_SIZE: f32 = 72.0;
+const BAG_COLOR: Color = Color::rgb(0.15, 0.08, 0.02);
+const DISCARD_COLOR: Color = Color::rgb(0.3, 0.2, 0.1);
+
+// The tray along the bottom of the screen showing the tile in hand, turned
+// the way it would go down on the hovered cell. The tile can be dragged out of
+// it onto the board as well as clicked into place.
+#[derive(Component)]
+pub struct HandTray;
+
+// Whether the tile in hand is being dragged out of the tray. Pressing on the
+// tray picks it up, and letting go puts it down on the hovered cell, or back
+// in the tray if there is none.
+#[derive(Resource, Default)]
+pub struct HandDrag {
+    pub dragging: bool,
+}
+
+// Text beside the tray: what the tile in hand is and how it's turned, what
+// else is held, how many tiles are left face down in the bag, or what's on
+// the discard pile.
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+pub enum HandText {
+    Tile,
+    Held,
+    Bag,
+    Discard,
+}
+
+pub fn spawn_hand_tray(mut commands: Commands) {
+    let text = |label: HandText| {
+        (
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font_size: 16.0,
+                    color: Color::ORANGE,
+                    ..default()
+                },
+            ),
+            label,
+        )
+    };
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(8.0),
+                width: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::FlexEnd,
+                column_gap: Val::Px(12.0),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|row| {
+            row.spawn(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(4.0),
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|hand| {
+                hand.spawn(text(HandText::Held));
+                hand.spawn(text(HandText::Tile));
+                hand.spawn((
+                    ImageBundle {
+                        style: Style {
+                            width: Val::Px(TRAY_TILE_SIZE),
+                            height: Val::Px(TRAY_TILE_SIZE),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    // so the tray can be pressed on, and the board underneath isn't
+                    Interaction::default(),
+                    RelativeCursorPosition::default(),
+                    HandTray,
+                ));
+            });
+            // the rest of the bag face down, and the discard pile face up
+            for (label, color) in [
+                (HandText::Bag, BAG_COLOR),
+                (HandText::Discard, DISCARD_COLOR),
+            ] {
+                row.spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(PILE_SIZE),
+                        height: Val::Px(PILE_SIZE),
+                        align_items: AlignItems::Center,
+                        justify_content: JustifyContent::Center,
+                        ..default()
+                    },
+                    background_color: color.into(),
+                    ..default()
+                })
+                .with_children(|pile| {
+                    pile.spawn(text(label));
+                });
+            }
+        });
+}
+
+// How the tile in hand would go down on the hovered cell, in clockwise quarter
+// turns of the artwork on square boards and sixth turns on hex boards. With
+// nothing hovered it's shown unturned.
+fn hand_rotation(highlighted: Option<(&Rotation, Option<&HexRotation>)>) -> (u8, Option<u8>) {
+    match highlighted {
+        Some((_, Some(hex_rotation))) => (0, Some(hex_rotation.0)),
+        Some((rotation, None)) => (rotation.0.turns(), None),
+        None => (0, None),
+    }
+}
+
+// The tray is drawn again whenever the tile in hand, its turn or the artwork
+// changes. The artwork may not have loaded yet, in which case it's tried again
+// later. Hex tiles are turned by the passages drawn over them, so their
+// artwork is never turned.
+pub fn update_hand_tray(
+    mut images: ResMut<Assets<Image>>,
+    asset_server: Res<AssetServer>,
+    (pack, palette): (Res<TilePack>, Res<Palette>),
+    next_tile_kind: Res<NextTileKind>,
+    highlighted_tiles_q: Query<(&Rotation, Option<&HexRotation>), With<HighlightedLabel>>,
+    mut shown: Local<Option<(TileKind, u8, String)>>,
+    mut tray_q: Query<&mut UiImage, With<HandTray>>,
+) {
+    let (turns, _) = hand_rotation(highlighted_tiles_q.iter().next());
+    let wanted = (next_tile_kind.0, turns, pack.name.clone());
+    if shown.as_ref() == Some(&wanted) && !palette.is_changed() {
+        return;
+    }
+    let Some(atlas) = images.get(&asset_server.load::<Image>(pack.tiles.clone())) else {
+        return;
+    };
+    let tile = match render_tile(atlas, &pack, next_tile_kind.0, turns, palette.background()) {
+        Ok(tile) => images.add(tile),
+        Err(err) => {
+            warn!("can't show the tile in hand: {}", err);
+            return;
+        }
+    };
+    for mut image in tray_q.iter_mut() {
+        image.texture = tile.clone();
+    }
+    *shown = Some(wanted);
+}
+
+pub fn update_hand_text(
+    strings: Res<Strings>,
+    next_tile_kind: Res<NextTileKind>,
+    (bag, held): (Res<TileBag>, Res<HeldTiles>),
+    highlighted_tiles_q: Query<(&Rotation, Option<&HexRotation>), With<HighlightedLabel>>,
+    mut text_q: Query<(&mut Text, &HandText)>,
+) {
+    let degrees = match hand_rotation(highlighted_tiles_q.iter().next()) {
+        (_, Some(sixths)) => sixths as u32 * 60,
+        (quarters, None) => quarters as u32 * 90,
+    };
+    for (mut text, label) in text_q.iter_mut() {
+        let value = match label {
+            HandText::Tile => strings.format(
+                "hand-tile",
+                &[
+                    ("tile", strings.tile(next_tile_kind.0)),
+                    ("degrees", degrees.to_string()),
+                ],
+            ),
+            HandText::Held if held.0.is_empty() => String::new(),
+            HandText::Held => {
+                let tiles: Vec<String> = held.0.iter().map(|kind| strings.tile(*kind)).collect();
+                strings.format("hand-held", &[("tiles", tiles.join(", "))])
+            }
+            HandText::Bag => strings.format("hand-bag", &[("count", bag.tiles.len().to_string())]),
+            HandText::Discard => match bag.discard.last() {
+                Some(top) => strings.format(
+                    "hand-discard",
+                    &[
+                        ("tile", strings.tile(*top)),
+                        ("count", bag.discard.len().to_string()),
+                    ],
+                ),
+                None => strings.get("hand-discard-empty"),
+            },
+        };
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+    }
+}
+
+// swap the tile in hand for another one held on pressing y
+pub fn swap_hand_input(
+    keyboard_input: Res<Input<KeyCode>>,
+    turn: Res<Turn>,
+    bots_q: Query<&Player, With<Bot>>,
+    mut swap_events: EventWriter<SwapHand>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Y) && !turn.is_bot(&bots_q) {
+        swap_events.send(SwapHand);
+    }
+}
+
+pub fn pick_up_hand_tile(
+    turn: Res<Turn>,
+    bots_q: Query<&Player, With<Bot>>,
+    tray_q: Query<&Interaction, (Changed<Interaction>, With<HandTray>)>,
+    mut drag: ResMut<HandDrag>,
+) {
+    // the bots' tiles aren't there for the taking
+    if turn.is_bot(&bots_q) {
+        return;
+    }
+    if tray_q
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed)
+    {
+        drag.dragging = true;
+    }
+}
+
+type DroppedTileQuery = (
+    &'static TilePos,
+    &'static Floor,
+    &'static Rotation,
+    Option<&'static HexRotation>,
+);
+
+// Letting go over the board places the tile in hand on the hovered cell, the
+// same as clicking there would, so the placement rules decide whether it
+// goes down. Letting go anywhere else, the tray included, puts it back, as
+// nothing on the board is hovered from there.
+pub fn drop_hand_tile(
+    mouse_button_input: Res<Input<MouseButton>>,
+    (turn, next_tile_kind): (Res<Turn>, Res<NextTileKind>),
+    pawns_q: Query<(&Player, &Pawn)>,
+    highlighted_tiles_q: Query<DroppedTileQuery, With<HighlightedLabel>>,
+    mut drag: ResMut<HandDrag>,
+    mut place_events: EventWriter<PlaceTile>,
+) {
+    if !drag.dragging || mouse_button_input.pressed(MouseButton::Left) {
+        return;
+    }
+    drag.dragging = false;
+    // a release missed while the game was paused drops nothing
+    if !mouse_button_input.just_released(MouseButton::Left) {
+        return;
+    }
+    let floor = pawn_floor(&pawns_q, turn.player);
+    for (tile_pos, tile_floor, rotation, hex_rotation) in highlighted_tiles_q.iter() {
+        if tile_floor.0 != floor {
+            continue;
+        }
+        place_events.send(PlaceTile {
+            pos: *tile_pos,
+            floor,
+            kind: next_tile_kind.0,
+            rotation: tile_rotation(rotation, hex_rotation),
+        });
+    }
+}
+
+// The tile fades from the tray while it's away being dragged.
+pub fn fade_lifted_tile(
+    drag: Res<HandDrag>,
+    mut tray_q: Query<&mut BackgroundColor, With<HandTray>>,
+) {
+    if !drag.is_changed() {
+        return;
+    }
+    for mut tint in tray_q.iter_mut() {
+        tint.0 = if drag.dragging {
+            Color::WHITE.with_a(LIFTED_ALPHA)
+        } else {
+            Color::WHITE
+        };
+    }
+}
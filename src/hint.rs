@@ -0,0 +1,106 @@
+use bevy::prelude::*;
+use bevy_ecs_tilemap::prelude::*;
+
+use crate::ai::{placements, BoardView, Placement};
+use crate::board::{passage_vectors, rotated_passages, tile_center, NextTileKind};
+use crate::floor::ShownFloor;
+use crate::inspect::BoardInspector;
+use crate::locale::Strings;
+use crate::pawn::{pawn_floor, Pawn};
+use crate::rules::{Player, Turn};
+
+const GHOST_COLOR: Color = Color::rgba(0.6, 0.9, 1.0, 0.6);
+
+// The suggested placement for the tile about to be placed, once asked for.
+#[derive(Resource, Default)]
+pub struct Hint(pub Option<Placement>);
+
+#[derive(Component)]
+pub struct HintText;
+
+pub fn spawn_hint_text(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 20.0,
+                color: GHOST_COLOR,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(8.0),
+            left: Val::Px(8.0),
+            ..default()
+        }),
+        HintText,
+    ));
+}
+
+// ask for a hint on pressing h, for the floor the current player's pawn is on
+pub fn request_hint(
+    keyboard_input: Res<Input<KeyCode>>,
+    (turn, next_tile_kind): (Res<Turn>, Res<NextTileKind>),
+    inspector: BoardInspector,
+    pawns_q: Query<(&Player, &Pawn)>,
+    mut hint: ResMut<Hint>,
+) {
+    if keyboard_input.just_pressed(KeyCode::H) {
+        let floor = pawn_floor(&pawns_q, turn.player);
+        let view = BoardView::on_floor(&inspector, floor);
+        hint.0 = placements(&view, next_tile_kind.0).first().copied();
+    }
+}
+
+// A hint is only good for the tile it was asked for.
+pub fn clear_hint(next_tile_kind: Res<NextTileKind>, mut hint: ResMut<Hint>) {
+    if next_tile_kind.is_changed() && hint.0.is_some() {
+        hint.0 = None;
+    }
+}
+
+// Draw the suggested tile as a ghost over its cell.
+pub fn draw_hint(
+    mut gizmos: Gizmos,
+    hint: Res<Hint>,
+    next_tile_kind: Res<NextTileKind>,
+    tilemap_q: Query<(&TilemapGridSize, &TilemapType, &Transform), With<ShownFloor>>,
+) {
+    let Some(placement) = hint.0 else {
+        return;
+    };
+    for (grid_size, map_type, map_transform) in tilemap_q.iter() {
+        let center = tile_center(&placement.pos, grid_size, map_type, map_transform);
+        let reach = grid_size.x * map_transform.scale.x / 2.0;
+        gizmos.circle_2d(center, reach * 0.9, GHOST_COLOR);
+        let passages = rotated_passages(next_tile_kind.0, map_type, placement.rotation);
+        for direction in passage_vectors(passages, map_type) {
+            gizmos.line_2d(center, center + direction * reach, GHOST_COLOR);
+        }
+    }
+}
+
+pub fn update_hint_text(
+    hint: Res<Hint>,
+    strings: Res<Strings>,
+    mut text_q: Query<&mut Text, With<HintText>>,
+) {
+    if !hint.is_changed() {
+        return;
+    }
+    let value = match hint.0 {
+        Some(placement) => strings.format(
+            "hint",
+            &[
+                ("x", placement.pos.x.to_string()),
+                ("y", placement.pos.y.to_string()),
+                ("reason", placement.reason.describe(&strings)),
+            ],
+        ),
+        None => String::new(),
+    };
+    for mut text in text_q.iter_mut() {
+        text.sections[0].value = value.clone();
+    }
+}
@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+use std::time::SystemTime;
+
+use bevy::prelude::*;
+
+use crate::pack::TilePack;
+use crate::palette::{Palette, PALETTE_FILE};
+
+// How often watched files are checked for changes, in seconds.
+const POLL_SECONDS: f32 = 1.0;
+
+// Where the asset server finds assets on disk.
+const ASSET_DIR: &str = "assets";
+
+// Files read at startup are picked up again when they change on disk, so
+// colours and artwork can be tweaked without restarting the game.
+#[derive(Resource)]
+pub struct HotReload {
+    timer: Timer,
+    modified: HashMap<String, SystemTime>,
+}
+
+impl Default for HotReload {
+    fn default() -> Self {
+        Self {
+            timer: Timer::from_seconds(POLL_SECONDS, TimerMode::Repeating),
+            modified: HashMap::new(),
+        }
+    }
+}
+
+impl HotReload {
+    // Whether `path` changed since it was last checked. The first check only
+    // notes when it was last written.
+    fn changed(&mut self, path: &str) -> bool {
+        let Ok(modified) = std::fs::metadata(path).and_then(|metadata| metadata.modified()) else {
+            return false;
+        };
+        self.modified
+            .insert(path.to_string(), modified)
+            .is_some_and(|previous| previous != modified)
+    }
+}
+
+// Only development builds watch for changes.
+pub fn enabled() -> bool {
+    cfg!(debug_assertions)
+}
+
+pub fn watch_files(
+    time: Res<Time>,
+    asset_server: Res<AssetServer>,
+    mut hot_reload: ResMut<HotReload>,
+    pack: Res<TilePack>,
+    mut palette: ResMut<Palette>,
+) {
+    if !hot_reload.timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    if hot_reload.changed(PALETTE_FILE) {
+        match palette.reload() {
+            Ok(()) => info!("reloaded {}", PALETTE_FILE),
+            Err(err) => warn!("{}", err),
+        }
+    }
+
+    let tiles_path = format!("{}/{}", ASSET_DIR, pack.tiles);
+    if hot_reload.changed(&tiles_path) {
+        info!("reloading {}", tiles_path);
+        asset_server.reload(pack.tiles.clone());
+    }
+}
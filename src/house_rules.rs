@@ -0,0 +1,144 @@
+use bevy::prelude::*;
+
+use crate::locale::parse;
+use crate::save::Format;
+
+// Tiles held in all, the one in hand included, under the larger hands rule.
+const LARGER_HAND_SIZE: usize = 3;
+
+// The house rules are shared by every profile at the table, kept one per
+// line as whether they're on:
+//
+//     # nightcage rules 1
+//     friendly-monsters = on
+//     no-collapse = off
+const RULES_PATH: &str = "nightcage-rules.txt";
+
+const RULES_FORMAT: Format = Format {
+    name: "rules",
+    migrations: &[],
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum HouseRule {
+    // Wax eaters never wake from the dark.
+    FriendlyMonsters,
+    // Tiles can't be placed over placed tiles, so none ever collapse.
+    NoCollapse,
+    // Turning the tile in hand is free.
+    UnlimitedRotation,
+    // Players hold a few tiles and choose which to play.
+    LargerHands,
+    // Corridors, light, pawns and wax eaters cross the board's edges to the
+    // opposite ones.
+    WrapAround,
+}
+
+impl HouseRule {
+    pub const ALL: [HouseRule; 5] = [
+        HouseRule::FriendlyMonsters,
+        HouseRule::NoCollapse,
+        HouseRule::UnlimitedRotation,
+        HouseRule::LargerHands,
+        HouseRule::WrapAround,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            HouseRule::FriendlyMonsters => "friendly-monsters",
+            HouseRule::NoCollapse => "no-collapse",
+            HouseRule::UnlimitedRotation => "unlimited-rotation",
+            HouseRule::LargerHands => "larger-hands",
+            HouseRule::WrapAround => "wrap-around",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<HouseRule> {
+        HouseRule::ALL.into_iter().find(|rule| rule.name() == name)
+    }
+}
+
+// Which house rules are in play. Without any, the game plays by the rules in
+// the box.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RuleSet {
+    pub friendly_monsters: bool,
+    pub no_collapse: bool,
+    pub unlimited_rotation: bool,
+    pub larger_hands: bool,
+    pub wrap_around: bool,
+}
+
+impl RuleSet {
+    pub fn get(&self, rule: HouseRule) -> bool {
+        match rule {
+            HouseRule::FriendlyMonsters => self.friendly_monsters,
+            HouseRule::NoCollapse => self.no_collapse,
+            HouseRule::UnlimitedRotation => self.unlimited_rotation,
+            HouseRule::LargerHands => self.larger_hands,
+            HouseRule::WrapAround => self.wrap_around,
+        }
+    }
+
+    pub fn set(&mut self, rule: HouseRule, on: bool) {
+        match rule {
+            HouseRule::FriendlyMonsters => self.friendly_monsters = on,
+            HouseRule::NoCollapse => self.no_collapse = on,
+            HouseRule::UnlimitedRotation => self.unlimited_rotation = on,
+            HouseRule::LargerHands => self.larger_hands = on,
+            HouseRule::WrapAround => self.wrap_around = on,
+        }
+    }
+
+    // Tiles a player holds, the one in hand included.
+    pub fn hand_size(&self) -> usize {
+        if self.larger_hands {
+            LARGER_HAND_SIZE
+        } else {
+            1
+        }
+    }
+
+    // Loads the house rules, all off when none have been saved yet or they
+    // can't be read, as on the web.
+    pub fn load() -> RuleSet {
+        let loaded = match std::fs::read_to_string(RULES_PATH) {
+            Ok(source) => RuleSet::parse(&source).map_err(|err| format!("{}: {}", RULES_PATH, err)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(RuleSet::default()),
+            Err(err) => Err(format!("failed to read {}: {}", RULES_PATH, err)),
+        };
+        loaded.unwrap_or_else(|err| {
+            warn!("{}", err);
+            RuleSet::default()
+        })
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        std::fs::write(RULES_PATH, self.to_source())
+            .map_err(|err| format!("failed to write {}: {}", RULES_PATH, err))
+    }
+
+    pub fn parse(source: &str) -> Result<RuleSet, String> {
+        let mut rules = RuleSet::default();
+        for (name, value) in parse(&RULES_FORMAT.upgrade(source)?) {
+            let rule =
+                HouseRule::from_name(&name).ok_or_else(|| format!("unknown rule {}", name))?;
+            let on = match value.as_str() {
+                "on" => true,
+                "off" => false,
+                _ => return Err(format!("invalid {} {}", name, value)),
+            };
+            rules.set(rule, on);
+        }
+        Ok(rules)
+    }
+
+    pub fn to_source(&self) -> String {
+        let mut source = RULES_FORMAT.header();
+        for rule in HouseRule::ALL {
+            let on = if self.get(rule) { "on" } else { "off" };
+            source.push_str(&format!("{} = {}\n", rule.name(), on));
+        }
+        source
+    }
+}
@@ -0,0 +1,217 @@
+use bevy::prelude::*;
+
+use crate::board::BoardConfig;
+use crate::floor::CurrentFloor;
+use crate::locale::Strings;
+use crate::nerve::Nerve;
+use crate::pawn::Keys;
+use crate::rng::Seed;
+use crate::rules::{Bot, Player, Turn, TurnBudget};
+
+const LINE_HEIGHT: f32 = 24.0;
+
+#[derive(Component)]
+pub struct TurnText;
+
+#[derive(Component)]
+pub struct NerveText;
+
+#[derive(Component)]
+pub struct ActionsText;
+
+#[derive(Component)]
+pub struct KeysText;
+
+#[derive(Component)]
+pub struct SeedText;
+
+#[derive(Component)]
+pub struct FloorText;
+
+// A line of text in the top right corner.
+fn hud_text(line: usize) -> TextBundle {
+    TextBundle::from_section(
+        "",
+        TextStyle {
+            font_size: 20.0,
+            color: Color::ORANGE,
+            ..default()
+        },
+    )
+    .with_style(Style {
+        position_type: PositionType::Absolute,
+        top: Val::Px(8.0 + line as f32 * LINE_HEIGHT),
+        right: Val::Px(8.0),
+        ..default()
+    })
+}
+
+// A panel in the middle of the screen, hidden until there's something to show
+// on it. `screen` marks the whole of it and `text` its text.
+pub fn spawn_panel(commands: &mut Commands, screen: impl Bundle, text: impl Bundle) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            screen,
+        ))
+        .with_children(|screen| {
+            screen
+                .spawn(NodeBundle {
+                    style: Style {
+                        padding: UiRect::all(Val::Px(16.0)),
+                        ..default()
+                    },
+                    background_color: Color::BLACK.with_a(0.8).into(),
+                    ..default()
+                })
+                .with_children(|panel| {
+                    panel.spawn((
+                        TextBundle::from_section(
+                            "",
+                            TextStyle {
+                                font_size: 24.0,
+                                color: Color::ORANGE,
+                                ..default()
+                            },
+                        ),
+                        text,
+                    ));
+                });
+        });
+}
+
+// For example 4:07.
+pub fn format_duration(seconds: f32) -> String {
+    let seconds = seconds.round() as u32;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+pub fn spawn_hud(mut commands: Commands) {
+    commands.spawn((hud_text(0), TurnText));
+    commands.spawn((hud_text(1), NerveText));
+    commands.spawn((hud_text(2), KeysText));
+    commands.spawn((hud_text(3), ActionsText));
+    commands.spawn((hud_text(4), SeedText));
+    commands.spawn((hud_text(5), FloorText));
+}
+
+pub fn update_turn_text(
+    turn: Res<Turn>,
+    strings: Res<Strings>,
+    players_q: Query<(&Player, Has<Bot>)>,
+    mut text_q: Query<&mut Text, With<TurnText>>,
+) {
+    // Nothing to show when playing alone.
+    let players = players_q.iter().count();
+    let current = players_q
+        .iter()
+        .find(|(player, _)| player.number == turn.player);
+    let value = match current {
+        _ if players < 2 => String::new(),
+        Some((player, true)) => strings.format(
+            "hud-turn-bot",
+            &[("number", (player.number + 1).to_string())],
+        ),
+        Some((player, false)) => strings.format(
+            "hud-turn-player",
+            &[("number", (player.number + 1).to_string())],
+        ),
+        None => String::new(),
+    };
+    for mut text in text_q.iter_mut() {
+        text.sections[0].value = value.clone();
+    }
+}
+
+pub fn update_nerve_text(
+    nerve: Res<Nerve>,
+    strings: Res<Strings>,
+    mut text_q: Query<&mut Text, With<NerveText>>,
+) {
+    for mut text in text_q.iter_mut() {
+        text.sections[0].value = strings.format("hud-nerve", &[("count", nerve.0.to_string())]);
+    }
+}
+
+// Keys carried by each player, in seat order.
+pub fn update_keys_text(
+    strings: Res<Strings>,
+    players_q: Query<(&Player, &Keys)>,
+    mut text_q: Query<&mut Text, With<KeysText>>,
+) {
+    let mut carried: Vec<(usize, u32)> = players_q
+        .iter()
+        .map(|(player, keys)| (player.number, keys.0))
+        .collect();
+    carried.sort();
+    let counts: Vec<String> = carried.iter().map(|(_, keys)| keys.to_string()).collect();
+    for mut text in text_q.iter_mut() {
+        text.sections[0].value = strings.format("hud-keys", &[("counts", counts.join(" "))]);
+    }
+}
+
+// Actions left to the player whose turn it is.
+pub fn update_actions_text(
+    turn: Res<Turn>,
+    strings: Res<Strings>,
+    players_q: Query<(&Player, &TurnBudget)>,
+    mut text_q: Query<&mut Text, With<ActionsText>>,
+) {
+    let Some((_, budget)) = players_q
+        .iter()
+        .find(|(player, _)| player.number == turn.player)
+    else {
+        return;
+    };
+    for mut text in text_q.iter_mut() {
+        text.sections[0].value =
+            strings.format("hud-actions", &[("count", budget.remaining.to_string())]);
+    }
+}
+
+// The seed only changes when a new game is dealt, so the text only needs
+// filling in then.
+pub fn update_seed_text(
+    seed: Res<Seed>,
+    strings: Res<Strings>,
+    mut text_q: Query<(&mut Text, Ref<SeedText>)>,
+) {
+    for (mut text, seed_text) in text_q.iter_mut() {
+        if seed.is_changed() || seed_text.is_added() {
+            text.sections[0].value = strings.format("hud-seed", &[("seed", seed.0.to_string())]);
+        }
+    }
+}
+
+pub fn update_floor_text(
+    board_config: Res<BoardConfig>,
+    current_floor: Res<CurrentFloor>,
+    strings: Res<Strings>,
+    mut text_q: Query<&mut Text, With<FloorText>>,
+) {
+    // Nothing to show on a board of a single floor.
+    let value = match board_config.floors {
+        1 => String::new(),
+        floors => strings.format(
+            "hud-floor",
+            &[
+                ("number", (current_floor.0 + 1).to_string()),
+                ("count", floors.to_string()),
+            ],
+        ),
+    };
+    for mut text in text_q.iter_mut() {
+        text.sections[0].value = value.clone();
+    }
+}
@@ -0,0 +1,161 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+use bevy_ecs_tilemap::prelude::*;
+
+use crate::board::{
+    tile_passages, tile_rotation, BoardConfig, HexRotation, HighlightedLabel, IlluminatedLabel,
+    MonsterSpawn, Occupants, Rotation, TileFog, TileHistory, TileType,
+};
+use crate::floor::{Floor, Floors, Stairs};
+use crate::house_rules::RuleSet;
+use crate::tiles::{Passages, TileKind};
+
+// Everything known about a single cell of the board.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TileInfo {
+    pub pos: TilePos,
+    pub floor: u32,
+    pub entity: Entity,
+    pub kind: TileKind,
+    // Clockwise quarter turns from the tile's unrotated artwork, or sixth turns
+    // on a hex board.
+    pub rotation: u8,
+    pub exits: Passages,
+    // How brightly the tile currently shows through the dark, from 0 to 1.
+    pub light: f32,
+    pub illuminated: bool,
+    pub explored: bool,
+    pub hovered: bool,
+    // A monster appears here when the scenario begins.
+    pub monster: bool,
+    // Numbers of the players whose pawns stand here, lowest first.
+    pub pawns: Vec<usize>,
+    // The floor the stairs here lead to, if there are any.
+    pub stairs: Option<u32>,
+    pub history: Vec<TileKind>,
+}
+
+type TileInfoQuery = (
+    Entity,
+    &'static TilePos,
+    &'static Floor,
+    Option<&'static TileType>,
+    &'static Rotation,
+    Option<&'static HexRotation>,
+    &'static TileFog,
+    &'static TileHistory,
+    &'static Occupants,
+    Option<&'static Stairs>,
+    Has<MonsterSpawn>,
+    Has<HighlightedLabel>,
+    Has<IlluminatedLabel>,
+);
+
+type TileInfoItem<'a> = (
+    Entity,
+    &'a TilePos,
+    &'a Floor,
+    Option<&'a TileType>,
+    &'a Rotation,
+    Option<&'a HexRotation>,
+    &'a TileFog,
+    &'a TileHistory,
+    &'a Occupants,
+    Option<&'a Stairs>,
+    bool,
+    bool,
+    bool,
+);
+
+// Read-only access to cell metadata for anything that needs to describe the
+// board, so each of them doesn't have to query tile components itself. Cells
+// are looked up on the floor on show unless another is asked for.
+#[derive(SystemParam)]
+pub struct BoardInspector<'w, 's> {
+    board_config: Res<'w, BoardConfig>,
+    rules: Res<'w, RuleSet>,
+    floors: Floors<'w, 's>,
+    tiles_q: Query<'w, 's, TileInfoQuery>,
+}
+
+impl<'w, 's> BoardInspector<'w, 's> {
+    pub fn board_config(&self) -> &BoardConfig {
+        &self.board_config
+    }
+
+    // Whether the board's edges wrap round to the opposite ones.
+    pub fn wraps(&self) -> bool {
+        self.rules.wrap_around
+    }
+
+    pub fn floor(&self) -> u32 {
+        self.floors.current()
+    }
+
+    pub fn tile_info(&self, pos: &TilePos) -> Option<TileInfo> {
+        self.tile_info_on(self.floors.current(), pos)
+    }
+
+    pub fn tile_info_on(&self, floor: u32, pos: &TilePos) -> Option<TileInfo> {
+        let entity = self.floors.tile_on(floor, pos)?;
+        self.tile_info_for_entity(entity)
+    }
+
+    pub fn tile_info_for_entity(&self, entity: Entity) -> Option<TileInfo> {
+        self.tiles_q.get(entity).ok().map(tile_info)
+    }
+
+    pub fn tiles(&self) -> impl Iterator<Item = TileInfo> + '_ {
+        self.tiles_on(self.floors.current())
+    }
+
+    pub fn tiles_on(&self, floor: u32) -> impl Iterator<Item = TileInfo> + '_ {
+        self.tiles_q
+            .iter()
+            .filter(move |(_, _, tile_floor, ..)| tile_floor.0 == floor)
+            .map(tile_info)
+    }
+
+    // The cell currently under the cursor, if any.
+    pub fn hovered(&self) -> Option<TileInfo> {
+        self.tiles_q
+            .iter()
+            .find(|(.., hovered, _)| *hovered)
+            .map(tile_info)
+    }
+}
+
+fn tile_info(
+    (
+        entity,
+        pos,
+        floor,
+        tile_type,
+        rotation,
+        hex_rotation,
+        fog,
+        history,
+        occupants,
+        stairs,
+        monster,
+        hovered,
+        illuminated,
+    ): TileInfoItem,
+) -> TileInfo {
+    let kind = tile_type.map_or(TileKind::Empty, |tile_type| tile_type.kind);
+    TileInfo {
+        pos: *pos,
+        floor: floor.0,
+        entity,
+        kind,
+        rotation: tile_rotation(rotation, hex_rotation),
+        exits: tile_passages(kind, rotation, hex_rotation),
+        light: fog.brightness,
+        illuminated,
+        explored: fog.explored,
+        hovered,
+        monster,
+        pawns: occupants.0.clone(),
+        stairs: stairs.map(|stairs| stairs.to),
+        history: history.0.clone(),
+    }
+}
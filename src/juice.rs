@@ -0,0 +1,150 @@
+use bevy::prelude::*;
+
+use crate::falling::PawnFell;
+use crate::pawn::KeyPickedUp;
+use crate::rules::TileCollapsed;
+
+// Most the view is thrown off by a shake at full strength, in world units.
+const MAX_SHAKE: f32 = 12.0;
+// How quickly shakes, flashes and pulses die away, in strength per second.
+const SHAKE_DECAY: f32 = 2.5;
+const FLASH_DECAY: f32 = 1.5;
+const PULSE_DECAY: f32 = 3.0;
+// Strength a collapse adds to a shake, which never gets stronger than 1.
+const COLLAPSE_SHAKE: f32 = 0.6;
+// Thickness of the frame glowing round the screen.
+const FRAME_WIDTH: f32 = 48.0;
+// Most the frame is lit for a snuffed candle and for a key.
+const FLASH_ALPHA: f32 = 0.6;
+const PULSE_ALPHA: f32 = 0.25;
+
+// How strongly the screen answers the game, switched in the settings.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum JuiceLevel {
+    Off,
+    Subtle,
+    #[default]
+    Full,
+}
+
+impl JuiceLevel {
+    pub fn name(self) -> &'static str {
+        match self {
+            JuiceLevel::Off => "off",
+            JuiceLevel::Subtle => "subtle",
+            JuiceLevel::Full => "full",
+        }
+    }
+
+    pub fn next(self) -> JuiceLevel {
+        match self {
+            JuiceLevel::Off => JuiceLevel::Subtle,
+            JuiceLevel::Subtle => JuiceLevel::Full,
+            JuiceLevel::Full => JuiceLevel::Off,
+        }
+    }
+
+    fn scale(self) -> f32 {
+        match self {
+            JuiceLevel::Off => 0.0,
+            JuiceLevel::Subtle => 0.4,
+            JuiceLevel::Full => 1.0,
+        }
+    }
+}
+
+// The view shakes when tiles collapse, the screen's edges flash red when a
+// candle is snuffed out and glow gold when a key is picked up. Each fades
+// from 1 to nothing.
+#[derive(Resource, Default)]
+pub struct Juice {
+    pub level: JuiceLevel,
+    shake: f32,
+    // How far the last shake put the view out, taken back before the next.
+    offset: Vec2,
+    flash: f32,
+    pulse: f32,
+}
+
+#[derive(Component)]
+pub struct JuiceFrame;
+
+pub fn spawn_juice_frame(mut commands: Commands) {
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                border: UiRect::all(Val::Px(FRAME_WIDTH)),
+                ..default()
+            },
+            border_color: Color::NONE.into(),
+            ..default()
+        },
+        JuiceFrame,
+    ));
+}
+
+pub fn kick_juice(
+    mut juice: ResMut<Juice>,
+    mut collapse_events: EventReader<TileCollapsed>,
+    mut fell_events: EventReader<PawnFell>,
+    mut picked_up_events: EventReader<KeyPickedUp>,
+) {
+    let collapses = collapse_events.read().count();
+    if collapses > 0 {
+        juice.shake = (juice.shake + COLLAPSE_SHAKE * collapses as f32).min(1.0);
+    }
+    if fell_events.read().count() > 0 {
+        juice.flash = 1.0;
+    }
+    if picked_up_events.read().count() > 0 {
+        juice.pulse = 1.0;
+    }
+}
+
+// Shakes fall off with the square of their strength so they end gently. The
+// view is thrown about along two waves out of step rather than at random, so
+// the game's dealing isn't touched.
+pub fn shake_camera(
+    time: Res<Time>,
+    mut juice: ResMut<Juice>,
+    mut camera_q: Query<&mut Transform, With<Camera>>,
+) {
+    if juice.shake <= 0.0 && juice.offset == Vec2::ZERO {
+        return;
+    }
+    juice.shake = (juice.shake - SHAKE_DECAY * time.delta_seconds()).max(0.0);
+    let t = time.elapsed_seconds();
+    let strength = juice.shake * juice.shake * MAX_SHAKE * juice.level.scale();
+    let offset = Vec2::new((t * 47.0).sin(), (t * 61.0).cos()) * strength;
+    for mut transform in camera_q.iter_mut() {
+        transform.translation += (offset - juice.offset).extend(0.0);
+    }
+    juice.offset = offset;
+}
+
+// A snuffed candle's red outshines a key's gold while both are fading.
+pub fn light_juice_frame(
+    time: Res<Time>,
+    mut juice: ResMut<Juice>,
+    mut frame_q: Query<&mut BorderColor, With<JuiceFrame>>,
+) {
+    if juice.flash > 0.0 || juice.pulse > 0.0 {
+        let delta = time.delta_seconds();
+        juice.flash = (juice.flash - FLASH_DECAY * delta).max(0.0);
+        juice.pulse = (juice.pulse - PULSE_DECAY * delta).max(0.0);
+    }
+    let scale = juice.level.scale();
+    let color = if juice.flash > 0.0 {
+        Color::RED.with_a(juice.flash * FLASH_ALPHA * scale)
+    } else {
+        Color::GOLD.with_a(juice.pulse * PULSE_ALPHA * scale)
+    };
+    for mut border in frame_q.iter_mut() {
+        if border.0 != color {
+            border.0 = color;
+        }
+    }
+}
@@ -1 +1,56 @@
+pub mod achievements;
+pub mod ai;
+pub mod announce;
+pub mod assist;
+pub mod autosave;
+pub mod board;
 pub mod camera;
+pub mod cli;
+pub mod console;
+pub mod content;
+pub mod daily;
+pub mod darkness;
+pub mod debug_overlay;
+pub mod difficulty;
+pub mod editor;
+pub mod event_file;
+pub mod event_log;
+pub mod falling;
+pub mod floor;
+pub mod generator;
+pub mod gesture;
+pub mod hand;
+pub mod hint;
+pub mod hot_reload;
+pub mod house_rules;
+pub mod hud;
+pub mod inspect;
+pub mod juice;
+pub mod locale;
+#[cfg(feature = "miasma")]
+pub mod miasma;
+pub mod minimap;
+pub mod nerve;
+pub mod odds;
+pub mod pack;
+pub mod palette;
+pub mod path;
+pub mod pause;
+pub mod pawn;
+pub mod photo;
+pub mod ping;
+pub mod restart;
+pub mod rng;
+pub mod rules;
+pub mod save;
+pub mod scenario;
+pub mod script;
+pub mod share;
+pub mod simulation;
+pub mod snapshot;
+pub mod state;
+pub mod stats;
+pub mod summary;
+pub mod tiles;
+pub mod timed;
+pub mod tutorial;
@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::tiles::TileKind;
+
+pub const DEFAULT_LANG: &str = "en";
+
+const LOCALE_DIR: &str = "assets/locales";
+
+// English is built in, so the game always has something to show even where
+// the locale files can't be read.
+const FALLBACK: &str = include_str!("../assets/locales/en.ftl");
+
+// The string table for the chosen language. Entries missing from it fall back
+// to English, and entries missing from both show their key.
+#[derive(Resource)]
+pub struct Strings {
+    pub lang: String,
+    table: HashMap<String, String>,
+    fallback: HashMap<String, String>,
+}
+
+impl Default for Strings {
+    fn default() -> Self {
+        Self {
+            lang: DEFAULT_LANG.to_string(),
+            table: HashMap::new(),
+            fallback: parse(FALLBACK),
+        }
+    }
+}
+
+impl Strings {
+    // Loads `assets/locales/<lang>.ftl`.
+    pub fn load(lang: &str) -> Result<Strings, String> {
+        let mut strings = Strings::default();
+        if lang == DEFAULT_LANG {
+            return Ok(strings);
+        }
+        let path = format!("{}/{}.ftl", LOCALE_DIR, lang);
+        let source = std::fs::read_to_string(&path)
+            .map_err(|err| format!("failed to read {}: {}", path, err))?;
+        strings.lang = lang.to_string();
+        strings.table = parse(&source);
+        Ok(strings)
+    }
+
+    // English and every locale in assets/locales, in code order.
+    pub fn available() -> Vec<String> {
+        let mut langs: Vec<String> = std::fs::read_dir(LOCALE_DIR)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|extension| extension == "ftl"))
+            .filter_map(|path| Some(path.file_stem()?.to_str()?.to_string()))
+            .filter(|lang| lang != DEFAULT_LANG)
+            .collect();
+        langs.sort();
+        langs.insert(0, DEFAULT_LANG.to_string());
+        langs
+    }
+
+    // Loads the language after this one, going back to the first after the last.
+    pub fn next(&self) -> Result<Strings, String> {
+        let langs = Strings::available();
+        let next = langs
+            .iter()
+            .position(|lang| *lang == self.lang)
+            .map_or(0, |current| (current + 1) % langs.len());
+        Strings::load(&langs[next])
+    }
+
+    pub fn get(&self, key: &str) -> String {
+        self.format(key, &[])
+    }
+
+    // Looks up `key` and fills in its `{ $name }` placeables from `args`.
+    pub fn format(&self, key: &str, args: &[(&str, String)]) -> String {
+        let Some(pattern) = self.table.get(key).or_else(|| self.fallback.get(key)) else {
+            return key.to_string();
+        };
+        let mut value = pattern.clone();
+        for (name, arg) in args {
+            value = value.replace(&format!("{{ ${} }}", name), arg);
+        }
+        value
+    }
+
+    pub fn tile(&self, kind: TileKind) -> String {
+        self.get(&format!("tile-{}", kind.name()))
+    }
+}
+
+// Reads the simple subset of Fluent the locale files use: `key = value`
+// messages, indented continuation lines and `{ $name }` placeables. Comments
+// and blank lines are skipped.
+pub(crate) fn parse(source: &str) -> HashMap<String, String> {
+    let mut table = HashMap::new();
+    let mut current: Option<String> = None;
+    for line in source.lines() {
+        if line.starts_with([' ', '\t']) && !line.trim().is_empty() {
+            if let Some(value) = current.as_ref().and_then(|key| table.get_mut(key)) {
+                let value: &mut String = value;
+                value.push('\n');
+                value.push_str(line.trim());
+            }
+            continue;
+        }
+        current = None;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some((key, value)) = line.split_once('=') {
+            let key = key.trim().to_string();
+            table.insert(key.clone(), value.trim().to_string());
+            current = Some(key);
+        }
+    }
+    table
+}
@@ -1,13 +1,177 @@
+use std::collections::HashSet;
+
 use bevy::{
-    input::{keyboard::KeyboardInput, mouse::MouseButtonInput},
+    diagnostic::FrameTimeDiagnosticsPlugin,
+    ecs::system::SystemParam,
+    input::{
+        common_conditions::input_just_pressed,
+        keyboard::KeyboardInput,
+        mouse::{MouseButtonInput, MouseWheel},
+        InputSystem,
+    },
     prelude::*,
+    ui::RelativeCursorPosition,
     window::WindowResolution,
 };
-use bevy_ecs_tilemap::helpers::square_grid::neighbors::Neighbors;
 use bevy_ecs_tilemap::prelude::*;
+use nightcage::achievements::{self, Achievements};
+use nightcage::ai;
+use nightcage::announce::{self, Announcer};
+use nightcage::assist;
+use nightcage::autosave::{self, Autosave};
+use nightcage::board::{
+    hex_direction_vector, lit_neighbors, tile_center, tile_passages, tile_rotation, turn_tile,
+    BoardConfig, BoardShape, HexRotation, HighlightedLabel, HoverEvent, HoverState,
+    IlluminatedLabel, MonsterSpawn, NextTileKind, Occupants, PlayerStart, Rotation, TileFog,
+    TileHistory, TileType,
+};
 use nightcage::camera;
+use nightcage::cli::{self, Args};
+use nightcage::console::{self, Console};
+use nightcage::content::{BaseGamePlugin, ExpansionPlugins, RulesSet};
+use nightcage::daily::{self, Daily, DailyChallenge, Date};
+use nightcage::darkness;
+use nightcage::debug_overlay::{self, DebugOverlay};
+use nightcage::editor;
+use nightcage::event_file::{self, EventFile};
+use nightcage::event_log;
+use nightcage::falling;
+use nightcage::floor::{self, random_stairs, CurrentFloor, Floor, Floors, ShownFloor, Stairs};
+use nightcage::generator;
+use nightcage::gesture::{self, Gesture, GestureTracker};
+use nightcage::hand::{self, HandDrag};
+use nightcage::hint;
+use nightcage::hot_reload::{self, HotReload};
+use nightcage::house_rules::RuleSet;
+use nightcage::hud;
+use nightcage::juice::{self, Juice};
+use nightcage::locale::Strings;
+use nightcage::minimap;
+use nightcage::nerve;
+use nightcage::odds;
+use nightcage::pack::{self, TilePack};
+use nightcage::palette::{self, Palette};
+use nightcage::pause::{self, PausePage};
+use nightcage::pawn;
+use nightcage::photo;
+use nightcage::ping;
+use nightcage::restart;
+use nightcage::rng::{GameRng, Seed};
+use nightcage::rules::{self, Bot, CurrentBudget, HandTurned, PlaceTile, Player, Turn};
+use nightcage::scenario::{ActiveScenario, Scenario};
+use nightcage::script::{TileScriptPlugin, TileScripts};
+use nightcage::share::{self, Replay, Watching};
+use nightcage::snapshot;
+use nightcage::state::{self, GameState};
+use nightcage::stats::{self, GameTally, Stats, DEFAULT_PROFILE};
+use nightcage::summary;
+use nightcage::tiles::{Quarter, TileAnimation, TileKind};
+use nightcage::timed::{self, Timed};
+use nightcage::tutorial::{self, Tutorial};
 
 fn main() {
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("nightcage: {}\n{}", err, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    // A scenario brings its own board, keeping only the tile size from the arguments.
+    let mut board = args.board;
+    let mut scenario = match args.scenario.as_deref().map(Scenario::load).transpose() {
+        Ok(scenario) => scenario,
+        Err(err) => {
+            eprintln!("nightcage: {}", err);
+            std::process::exit(2);
+        }
+    };
+    let mut seed = args.seed.unwrap_or_else(Seed::random);
+    let mut difficulty = args.difficulty;
+    let daily_challenge = match args.daily.then(Date::today) {
+        Some(Some(today)) => Some(DailyChallenge::new(today, args.board)),
+        Some(None) => {
+            eprintln!("nightcage: no daily challenge without a clock to tell the date by");
+            None
+        }
+        None => None,
+    };
+    // The day's challenge is the same game for everyone.
+    if let Some(challenge) = daily_challenge {
+        board = challenge.board;
+        seed = challenge.seed;
+        difficulty = challenge.difficulty;
+        scenario = None;
+    }
+    // A shared game is watched on the board, seed and players it was played with.
+    let mut players = args.players;
+    let replay = match args.replay.as_deref().map(Replay::decode).transpose() {
+        Ok(replay) => replay,
+        Err(err) => {
+            eprintln!("nightcage: {}", err);
+            std::process::exit(2);
+        }
+    };
+    if let Some(replay) = &replay {
+        board.size = replay.size;
+        board.shape = replay.shape;
+        board.floors = replay.floors;
+        seed = replay.seed;
+        difficulty = replay.difficulty;
+        players.bots = replay.bots;
+        scenario = None;
+    }
+    // A generated board is dealt from the seed, and checked against it.
+    if args.generate && daily_challenge.is_none() && replay.is_none() {
+        scenario = match generator::generate(seed, difficulty, &board) {
+            Ok(scenario) => Some(scenario),
+            Err(err) => {
+                eprintln!("nightcage: {}", err);
+                std::process::exit(2);
+            }
+        };
+    }
+    if let Some(scenario) = &scenario {
+        board.size = scenario.size;
+        board.shape = scenario.shape;
+        board.floors = scenario.floors;
+    }
+    let palette = Palette::new(args.palette);
+    let pack = match args.pack.as_deref().map(TilePack::load).transpose() {
+        Ok(pack) => pack.unwrap_or_default(),
+        Err(err) => {
+            eprintln!("nightcage: {}", err);
+            std::process::exit(2);
+        }
+    };
+    let stats = Stats::load(args.profile.as_deref().unwrap_or(DEFAULT_PROFILE));
+    let achievements = Achievements::load(&stats.profile);
+    let tile_scripts = TileScripts::load();
+    let house_rules = if daily_challenge.is_some() {
+        RuleSet::default()
+    } else {
+        RuleSet::load()
+    };
+    // a shared game is played back by the rules it was played by
+    let house_rules = replay.as_ref().map_or(house_rules, |replay| replay.rules);
+    let autosave = Autosave::load(&stats.profile);
+    let daily = Daily::load(&stats.profile, daily_challenge);
+    let event_file = match args.log_events.as_deref().map(EventFile::open).transpose() {
+        Ok(event_file) => event_file.unwrap_or_default(),
+        Err(err) => {
+            eprintln!("nightcage: {}", err);
+            std::process::exit(2);
+        }
+    };
+    let strings = match args.lang.as_deref().map(Strings::load).transpose() {
+        Ok(strings) => strings.unwrap_or_default(),
+        Err(err) => {
+            eprintln!("nightcage: {}", err);
+            std::process::exit(2);
+        }
+    };
+
     App::new()
         .add_plugins(DefaultPlugins.set(WindowPlugin {
             primary_window: Some(Window {
@@ -17,63 +181,477 @@ fn main() {
             }),
             ..default()
         }))
-        .add_plugins(TilemapPlugin)
-        .add_systems(Startup, startup)
+        .add_plugins((TilemapPlugin, FrameTimeDiagnosticsPlugin))
+        .add_plugins((BaseGamePlugin, ExpansionPlugins, TileScriptPlugin))
+        .insert_resource(tile_scripts)
+        .add_systems(
+            Startup,
+            (
+                camera::spawn_views,
+                hud::spawn_hud,
+                hint::spawn_hint_text,
+                event_log::spawn_event_log,
+                hand::spawn_hand_tray,
+                odds::spawn_odds_panel,
+                console::spawn_console,
+                debug_overlay::spawn_debug_text,
+                juice::spawn_juice_frame,
+                minimap::spawn_minimap,
+                stats::spawn_stats_screen,
+                achievements::spawn_toast_list,
+                timed::spawn_timer_text.run_if(timed::enabled),
+                tutorial::spawn_tutorial_text.run_if(tutorial::running),
+                autosave::begin_session,
+                autosave::spawn_resume_offer.run_if(autosave::offered),
+                share::spawn_replay_text,
+            ),
+        )
+        .add_systems(
+            OnEnter(GameState::Setup),
+            (
+                restart::tear_down,
+                apply_deferred,
+                assist::ease_game,
+                (rules::deal_first_tile, spawn_board).chain(),
+                rules::spawn_players,
+                nerve::fill_nerve,
+                stats::start_tally,
+                share::start_record,
+                timed::reset_timers,
+                daily::reset_daily,
+                event_file::log_game_start.run_if(event_file::enabled),
+                apply_deferred,
+                (rules::spawn_wax_eaters, pawn::spawn_pawns),
+                apply_deferred,
+                falling::lay_starting_tiles.run_if(not(autosave::resuming)),
+                autosave::restore_pending.run_if(autosave::resuming),
+                restart::start_playing,
+            )
+                .chain(),
+        )
+        // The board keeps being drawn and animated behind the pause menu, but
+        // doesn't take any input until the game is resumed.
         .add_systems(
             Update,
             (
                 camera::movement,
-                update_cursor_pos,
-                highlight_tile_labels,
-                apply_tile_textures,
-                cycle_tile_texture_index,
-                place_highlighted_tile,
-                rotate_highlighted_tile,
-                illuminate_tiles,
+                camera::drag_camera,
+                camera::center_on_double_click,
+                camera::gamepad_movement,
+                camera::toggle_follow,
+                camera::follow_pawns,
+                (
+                    update_cursor_pos,
+                    move_board_cursor,
+                    update_hover,
+                    sync_hover_labels,
+                )
+                    .chain(),
+                rotate_highlighted_tile.run_if(not(share::watching)),
+                palette::cycle_palette,
+                pack::cycle_pack,
+                minimap::minimap_click,
+                floor::view_floor_input,
+            )
+                .run_if(state::taking_input),
+        )
+        .add_systems(
+            Update,
+            (
+                update_tile_textures,
+                flip_rotated_tiles,
+                draw_hex_passages,
+                editor::draw_markers,
+                palette::apply_background,
+                palette::draw_lit_outlines,
+                pack::apply_pack,
+                minimap::update_minimap,
+                floor::show_current_floor,
+                floor::draw_stairs,
+                darkness::draw_stirring,
+                ping::draw_pings,
+                hand::update_hand_tray,
+            )
+                .run_if(not(in_state(GameState::Photo))),
+        )
+        .add_systems(
+            Update,
+            (
+                (
+                    place_highlighted_tile
+                        .after(hand::pick_up_hand_tile)
+                        .run_if(not(share::watching)),
+                    ai::play_bot_turns.run_if(not(share::watching)),
+                    rules::end_turn_input.run_if(not(share::watching)),
+                    nerve::nerve_input.run_if(not(share::watching)),
+                    pawn::pawn_input,
+                    pawn::walk_input
+                        .after(place_highlighted_tile)
+                        .run_if(not(share::watching)),
+                    pawn::walk_pawns,
+                    pawn::pass_key_input.run_if(not(share::watching)),
+                    floor::stairs_input.run_if(not(share::watching)),
+                    floor::follow_turn,
+                ),
+                (
+                    update_tile_fog,
+                    illuminate_tiles,
+                    pawn::draw_pawns,
+                    pawn::draw_reachable,
+                    hud::update_turn_text,
+                    hud::update_nerve_text,
+                    hud::update_keys_text,
+                    hud::update_actions_text,
+                    hud::update_seed_text,
+                    hud::update_floor_text,
+                    hand::update_hand_text,
+                    ping::ping_input,
+                    ping::spawn_pings,
+                    ping::expire_pings,
+                    hand::pick_up_hand_tile,
+                    hand::drop_hand_tile.run_if(not(share::watching)),
+                    hand::fade_lifted_tile,
+                    hand::swap_hand_input.run_if(not(share::watching)),
+                ),
+                (
+                    event_log::record_events,
+                    event_log::event_log_input,
+                    event_log::update_event_log_panel,
+                    hint::request_hint,
+                    hint::clear_hint,
+                    hint::draw_hint,
+                    hint::update_hint_text,
+                    odds::toggle_odds_panel,
+                    odds::update_odds_panel,
+                    console::run_console_commands,
+                    juice::kick_juice,
+                ),
+                (
+                    (
+                        stats::tally_game,
+                        achievements::unlock_achievements,
+                        stats::record_game_end,
+                    )
+                        .chain(),
+                    stats::toggle_stats_screen,
+                    stats::update_stats_screen,
+                    achievements::show_unlock_toasts,
+                    achievements::expire_toasts,
+                    summary::end_game.after(stats::record_game_end),
+                ),
+                (
+                    timed::tick_timers
+                        .run_if(not(stats::stats_screen_open))
+                        .run_if(not(share::watching)),
+                    timed::update_timer_text,
+                    timed::update_timer_bar,
+                    timed::record_time.after(stats::record_game_end),
+                )
+                    .run_if(timed::enabled),
+                daily::record_daily
+                    .after(stats::record_game_end)
+                    .run_if(daily::running),
+                event_file::log_events
+                    .after(stats::record_game_end)
+                    .run_if(event_file::enabled),
+            )
+                .run_if(in_state(GameState::Playing)),
+        )
+        .add_systems(
+            Update,
+            (
+                tutorial::advance_tutorial,
+                tutorial::draw_tutorial_target,
+                tutorial::update_tutorial_text,
+            )
+                .run_if(in_state(GameState::Playing).and_then(tutorial::running)),
+        )
+        .add_systems(
+            Update,
+            (
+                announce::toggle_announcements,
+                announce::announce_events,
+                announce::announce_hovered_tile,
+            )
+                .run_if(in_state(GameState::Playing)),
+        )
+        .add_systems(
+            Update,
+            (
+                editor::toggle_editor,
+                (
+                    editor::select_tool,
+                    editor::select_tiles,
+                    editor::apply_tool,
+                    editor::draw_selection,
+                    editor::reveal_tiles,
+                    editor::update_overlay,
+                    editor::export_scenario,
+                    cycle_next_tile_kind,
+                )
+                    .run_if(in_state(GameState::Editor)),
             ),
         )
+        // the console takes what's typed into it before anything else sees it
+        .add_systems(PreUpdate, console::console_input.after(InputSystem))
+        .add_systems(PreUpdate, gesture::interpret_gestures.after(InputSystem))
+        // shakes are put on top of wherever the view was moved to
+        .add_systems(
+            Update,
+            (
+                juice::shake_camera
+                    .after(camera::movement)
+                    .after(camera::drag_camera)
+                    .after(camera::center_on_double_click)
+                    .after(camera::gamepad_movement)
+                    .after(camera::follow_pawns)
+                    .after(minimap::minimap_click),
+                juice::light_juice_frame,
+            ),
+        )
+        .add_systems(Update, console::update_console_text)
+        .add_systems(
+            Update,
+            (
+                autosave::resume_input.run_if(
+                    autosave::offered.and_then(
+                        in_state(GameState::Playing).or_else(in_state(GameState::Paused)),
+                    ),
+                ),
+                autosave::withdraw_resume_offer.run_if(not(autosave::offered)),
+                autosave::clear_finished_game,
+            ),
+        )
+        .add_systems(
+            PostUpdate,
+            autosave::autosave_turn.run_if(in_state(GameState::Playing)),
+        )
+        .add_systems(
+            Update,
+            (
+                share::play_replay.before(RulesSet).run_if(share::watching),
+                share::record_moves,
+                share::update_replay_text,
+            )
+                .run_if(in_state(GameState::Playing)),
+        )
+        .add_systems(
+            Update,
+            share::share_input
+                .run_if(in_state(GameState::Playing).or_else(in_state(GameState::GameOver))),
+        )
+        .add_systems(Last, autosave::end_session)
+        .add_systems(
+            Update,
+            (
+                debug_overlay::toggle_debug_overlay,
+                (
+                    debug_overlay::update_debug_text,
+                    debug_overlay::draw_board_bounds,
+                )
+                    .run_if(debug_overlay::shown),
+            )
+                .chain(),
+        )
+        .add_systems(
+            Update,
+            (
+                photo::toggle_photo_mode,
+                pause::toggle_pause,
+                snapshot::export_board.run_if(input_just_pressed(KeyCode::B)),
+                hot_reload::watch_files.run_if(hot_reload::enabled),
+                reapply_tile_textures,
+                (
+                    photo::free_camera,
+                    photo::cycle_filter,
+                    photo::toggle_frame,
+                    photo::capture,
+                    photo::hide_interface,
+                )
+                    .run_if(in_state(GameState::Photo)),
+            ),
+        )
+        .add_systems(
+            Update,
+            (
+                pause::show_menu_page,
+                pause::highlight_buttons,
+                pause::press_menu_buttons,
+                pause::press_settings_buttons,
+                pause::press_house_rules_buttons,
+                pause::update_menu_labels,
+            )
+                .run_if(in_state(GameState::Paused).or_else(in_state(GameState::GameOver))),
+        )
+        .add_systems(OnEnter(GameState::Paused), pause::spawn_pause_menu)
+        .add_systems(OnExit(GameState::Paused), pause::despawn_pause_menu)
+        .add_systems(
+            OnEnter(GameState::GameOver),
+            (assist::evaluate_game, summary::spawn_summary).chain(),
+        )
+        .add_systems(OnExit(GameState::GameOver), summary::despawn_summary)
+        .add_systems(
+            PostUpdate,
+            camera::lay_out_split_views.run_if(camera::split_screen),
+        )
+        .add_systems(OnEnter(GameState::Photo), photo::enter_photo_mode)
+        .add_systems(OnExit(GameState::Photo), photo::exit_photo_mode)
+        .add_systems(OnEnter(GameState::Editor), editor::enter_editor)
+        .add_systems(OnExit(GameState::Editor), editor::exit_editor)
+        .add_state::<GameState>()
+        .insert_resource(board)
+        .insert_resource(ActiveScenario(scenario))
+        .insert_resource(players)
+        .insert_resource(difficulty)
+        .insert_resource(Tutorial::new(args.tutorial))
+        .insert_resource(strings)
+        .insert_resource(palette.clone())
+        .insert_resource(pack)
+        .insert_resource(Announcer::new(args.announce))
+        .insert_resource(seed)
+        .insert_resource(GameRng::new(seed))
+        .insert_resource(house_rules)
+        .init_resource::<hint::Hint>()
+        .init_resource::<HandDrag>()
+        .init_resource::<pawn::MoveMode>()
+        .init_resource::<event_log::EventLog>()
+        .insert_resource(event_file)
+        .add_event::<ping::PingTile>()
+        .insert_resource(stats)
+        .insert_resource(achievements)
+        .add_event::<achievements::AchievementUnlocked>()
+        .init_resource::<GameTally>()
+        .add_event::<stats::GameEnded>()
+        .insert_resource(Timed::new(args.timed, args.timeout, difficulty))
+        .insert_resource(daily)
+        .insert_resource(autosave)
+        .init_resource::<share::GameRecord>()
+        .insert_resource(replay.map(Watching::new).unwrap_or_default())
         .init_resource::<CursorPos>()
-        .init_resource::<NextTileTextureIndex>()
-        .insert_resource(ClearColor(Color::hex("1F1E19").unwrap()))
+        .insert_resource(args.gestures)
+        .insert_resource(camera::SplitScreen(args.split_screen))
+        .init_resource::<GestureTracker>()
+        .add_event::<Gesture>()
+        .init_resource::<HotReload>()
+        .init_resource::<HoverState>()
+        .add_event::<HoverEvent>()
+        .init_resource::<photo::PhotoMode>()
+        .init_resource::<editor::Editor>()
+        .init_resource::<PausePage>()
+        .init_resource::<assist::Assistant>()
+        .init_resource::<Console>()
+        .init_resource::<DebugOverlay>()
+        .init_resource::<Juice>()
+        .add_event::<console::ConsoleCommand>()
+        .init_resource::<NextTileKind>()
+        .insert_resource(ClearColor(palette.background()))
         .run();
 }
 
-fn startup(mut commands: Commands, asset_server: Res<AssetServer>) {
-    commands.spawn(Camera2dBundle::default());
-
-    let texture_handle: Handle<Image> = asset_server.load("tiles.png");
-    let map_size = TilemapSize { x: 7, y: 7 };
-    let mut tile_storage = TileStorage::empty(map_size);
-    let tilemap_entity = commands.spawn_empty().id();
-
-    for x in 0..map_size.x {
-        for y in 0..map_size.y {
-            let tile_pos = TilePos { x, y };
-            let tile_entity = commands
-                .spawn(TileBundle {
-                    position: tile_pos,
-                    tilemap_id: TilemapId(tilemap_entity),
-                    ..Default::default()
-                })
-                .id();
-            tile_storage.set(&tile_pos, tile_entity);
+fn spawn_board(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    board_config: Res<BoardConfig>,
+    active_scenario: Res<ActiveScenario>,
+    pack: Res<TilePack>,
+    mut next_tile_kind: ResMut<NextTileKind>,
+    mut rng: ResMut<GameRng>,
+) {
+    let texture_handle: Handle<Image> = asset_server.load(pack.tiles.clone());
+    let map_size = board_config.size;
+    let mut floors: Vec<(Entity, TileStorage)> = Vec::new();
+
+    for floor in 0..board_config.floors {
+        let mut tile_storage = TileStorage::empty(map_size);
+        let tilemap_entity = commands.spawn_empty().id();
+
+        for x in 0..map_size.x {
+            for y in 0..map_size.y {
+                let tile_pos = TilePos { x, y };
+                let tile_entity = commands
+                    .spawn((
+                        TileBundle {
+                            position: tile_pos,
+                            tilemap_id: TilemapId(tilemap_entity),
+                            // Start fully hidden until a candle reaches the tile.
+                            color: TileColor(Color::NONE),
+                            ..Default::default()
+                        },
+                        Floor(floor),
+                        Rotation::default(),
+                        TileFog::default(),
+                        TileHistory::default(),
+                        Occupants::default(),
+                    ))
+                    .id();
+                if board_config.shape == BoardShape::Hex {
+                    commands.entity(tile_entity).insert(HexRotation::default());
+                }
+                tile_storage.set(&tile_pos, tile_entity);
+            }
+        }
+        floors.push((tilemap_entity, tile_storage));
+    }
+    let tile_at = |floor: u32, pos: &TilePos| floors[floor as usize].1.get(pos).unwrap();
+
+    let stairs = match &active_scenario.0 {
+        Some(scenario) => scenario.stairs.clone(),
+        None => random_stairs(&board_config, &mut rng),
+    };
+    for (floor, pos) in stairs {
+        commands
+            .entity(tile_at(floor, &pos))
+            .insert(Stairs { to: floor + 1 });
+        commands
+            .entity(tile_at(floor + 1, &pos))
+            .insert(Stairs { to: floor });
+    }
+
+    if let Some(scenario) = &active_scenario.0 {
+        for tile in &scenario.tiles {
+            let mut tile_entity = commands.entity(tile_at(tile.floor, &tile.pos));
+            tile_entity.insert(TileType { kind: tile.kind });
+            match board_config.shape {
+                BoardShape::Square => {
+                    tile_entity.insert(Rotation(Quarter::from_turns(tile.rotation)))
+                }
+                BoardShape::Hex => tile_entity.insert(HexRotation(tile.rotation % 6)),
+            };
+        }
+        for pos in &scenario.player_starts {
+            commands.entity(tile_at(0, pos)).insert(PlayerStart);
+        }
+        for pos in &scenario.monsters {
+            commands.entity(tile_at(0, pos)).insert(MonsterSpawn);
+        }
+        if let Some(next) = scenario.next {
+            next_tile_kind.0 = next;
         }
     }
 
-    let tile_size = TilemapTileSize { x: 128.0, y: 128.0 };
+    let tile_size = TilemapTileSize {
+        x: pack.tile_size,
+        y: pack.tile_size,
+    };
     let grid_size = tile_size.into();
-    let map_type = TilemapType::default();
-
-    commands.entity(tilemap_entity).insert(TilemapBundle {
-        grid_size,
-        map_type,
-        size: map_size,
-        storage: tile_storage,
-        texture: TilemapTexture::Single(texture_handle),
-        tile_size,
-        transform: get_tilemap_center_transform(&map_size, &grid_size, &map_type, 0.0),
-        ..Default::default()
-    });
+    let map_type = board_config.map_type();
+
+    // every floor is laid out in the same place, and only the one on show is drawn
+    for (floor, (tilemap_entity, tile_storage)) in (0..).zip(floors) {
+        commands.entity(tilemap_entity).insert((
+            TilemapBundle {
+                grid_size,
+                map_type,
+                size: map_size,
+                storage: tile_storage,
+                texture: TilemapTexture::Single(texture_handle.clone()),
+                tile_size,
+                transform: board_config.tilemap_transform(&grid_size, &map_type),
+                ..Default::default()
+            },
+            Floor(floor),
+        ));
+    }
 }
 
 #[derive(Resource)]
@@ -86,63 +664,103 @@ impl Default for CursorPos {
     }
 }
 
-#[derive(Component)]
-struct HighlightedLabel;
-
-#[derive(Component)]
-struct IlluminatedLabel;
-
-#[derive(Component)]
-struct TileType {
-    texture_index: u32,
-}
+const FOG_FADE_SPEED: f32 = 3.0;
+const FOG_EXPLORED_BRIGHTNESS: f32 = 0.35;
 
 // We need to keep the cursor position updated based on any `CursorMoved` events.
 pub fn update_cursor_pos(
-    mut gizmos: Gizmos,
-    camera_q: Query<(&GlobalTransform, &Camera)>,
+    camera_q: Query<(&Camera, &GlobalTransform)>,
     mut cursor_moved_events: EventReader<CursorMoved>,
+    ui_q: Query<(&Interaction, Option<&RelativeCursorPosition>)>,
     mut cursor_pos: ResMut<CursorPos>,
 ) {
-    gizmos.rect_2d(Vec2::ZERO, 0.0, Vec2::splat(7.0 * 128.0), Color::ORANGE_RED);
-
+    // the board is out of reach under the minimap and other clickable ui.
+    // Ui that can be dragged out of, like the tray the tile in hand is picked
+    // up from, stays pressed while dragging, so it goes by where the cursor is.
+    let over_ui = ui_q
+        .iter()
+        .any(|(interaction, relative_cursor)| match relative_cursor {
+            Some(relative_cursor) => relative_cursor.mouse_over(),
+            None => *interaction != Interaction::None,
+        });
     for cursor_moved in cursor_moved_events.read() {
+        if over_ui {
+            *cursor_pos = CursorPos::default();
+            continue;
+        }
         // To get the mouse's world position, we have to transform its window position by
         // any transforms on the camera. This is done by projecting the cursor position into
-        // camera space (world space).
-        for (cam_t, cam) in camera_q.iter() {
-            if let Some(pos) = cam.viewport_to_world_2d(cam_t, cursor_moved.position) {
-                *cursor_pos = CursorPos(pos);
-            }
+        // camera space (world space), through the view of a split screen it's over.
+        if let Some(pos) = camera::window_to_world(camera_q.iter(), cursor_moved.position) {
+            *cursor_pos = CursorPos(pos);
         }
     }
 }
 
-fn highlight_tile_labels(
-    mut commands: Commands,
-    cursor_pos: Res<CursorPos>,
+// The arrow keys step the cursor from tile to tile, so the board can be played
+// without a mouse. The cursor is put on the tile's center in the world, the
+// same as the mouse would, so hovering, placing and moving all follow it.
+fn move_board_cursor(
+    keyboard_input: Res<Input<KeyCode>>,
+    hover: Res<HoverState>,
+    tilemap_q: Query<(&TilemapSize, &TilemapGridSize, &TilemapType, &Transform), With<ShownFloor>>,
+    mut cursor_pos: ResMut<CursorPos>,
+) {
+    let steps = [
+        (KeyCode::Left, -1, 0),
+        (KeyCode::Right, 1, 0),
+        (KeyCode::Up, 0, 1),
+        (KeyCode::Down, 0, -1),
+    ];
+    let (dx, dy) = steps
+        .iter()
+        .filter(|(key, ..)| keyboard_input.just_pressed(*key))
+        .fold((0, 0), |(x, y), (_, dx, dy)| (x + dx, y + dy));
+    if (dx, dy) == (0, 0) {
+        return;
+    }
+
+    for (map_size, grid_size, map_type, map_transform) in tilemap_q.iter() {
+        // start from the middle of the board if the mouse isn't over it
+        let from = hover.hovered.unwrap_or(TilePos {
+            x: map_size.x / 2,
+            y: map_size.y / 2,
+        });
+        let to = TilePos {
+            x: from.x.saturating_add_signed(dx).min(map_size.x - 1),
+            y: from.y.saturating_add_signed(dy).min(map_size.y - 1),
+        };
+        cursor_pos.0 = tile_center(&to, grid_size, map_type, map_transform);
+    }
+}
+
+// Works out which tile is under the cursor and what the candle would light
+// from it, on the floor on show. The light is only worked out again when the
+// cursor reaches another tile, the tile in hand changes, a tile is placed,
+// turned or cleared, or another floor is shown.
+fn update_hover(
+    (cursor_pos, current_floor): (Res<CursorPos>, Res<CurrentFloor>),
+    (next_tile_kind, rules): (Res<NextTileKind>, Res<RuleSet>),
     tilemap_q: Query<(
         &TilemapSize,
         &TilemapGridSize,
         &TilemapType,
         &TileStorage,
         &Transform,
+        &Floor,
     )>,
-    highlighted_tiles_q: Query<Entity, With<HighlightedLabel>>,
-    illuminated_tiles_q: Query<Entity, With<IlluminatedLabel>>,
+    tiles_q: Query<(Option<&TileType>, &Rotation, Option<&HexRotation>)>,
+    board_changes_q: Query<(), BoardChangeFilter>,
+    mut cleared_tiles: RemovedComponents<TileType>,
+    mut hover: ResMut<HoverState>,
 ) {
-    for highlighted_tile_entity in highlighted_tiles_q.iter() {
-        commands
-            .entity(highlighted_tile_entity)
-            .remove::<HighlightedLabel>();
-    }
-    for illuminated_tile_entity in illuminated_tiles_q.iter() {
-        commands
-            .entity(illuminated_tile_entity)
-            .remove::<IlluminatedLabel>();
-    }
-
-    for (map_size, grid_size, map_type, tile_storage, map_transform) in tilemap_q.iter() {
+    // wrapping round the edges changes what the candle lights too
+    let board_changed =
+        !board_changes_q.is_empty() || cleared_tiles.read().count() > 0 || rules.is_changed();
+    for (map_size, grid_size, map_type, tile_storage, map_transform, floor) in tilemap_q.iter() {
+        if !current_floor.shows(floor) {
+            continue;
+        }
         // Grab the cursor position from the `Res<CursorPos>`
         let cursor_pos: Vec2 = cursor_pos.0;
         // We need to make sure that the cursor's world position is correct relative to the map
@@ -154,154 +772,491 @@ fn highlight_tile_labels(
             cursor_in_map_pos.xy()
         };
         // Once we have a world position we can transform it into a possible tile position.
-        if let Some(tile_pos) =
-            TilePos::from_world_pos(&cursor_in_map_pos, map_size, grid_size, map_type)
+        let hovered = TilePos::from_world_pos(&cursor_in_map_pos, map_size, grid_size, map_type)
+            .filter(|tile_pos| tile_storage.get(tile_pos).is_some());
+        if hovered == hover.hovered
+            && floor.0 == hover.floor
+            && !next_tile_kind.is_changed()
+            && !board_changed
         {
-            // Highlight the relevant tile's label
-            if let Some(tile_entity) = tile_storage.get(&tile_pos) {
-                commands.entity(tile_entity).insert(HighlightedLabel);
-            }
+            continue;
+        }
 
-            // Highlight the relevant tile's neighbors
-            let neighbor_positions =
-                Neighbors::get_square_neighboring_positions(&tile_pos, &map_size, false);
-            let neighbor_entities = neighbor_positions.entities(&tile_storage);
-            for neighbor_entity in neighbor_entities.iter() {
-                commands.entity(*neighbor_entity).insert(IlluminatedLabel);
-            }
+        let mut illuminated = HashSet::new();
+        if let Some(tile_pos) = hovered {
+            // The candle shines out of the open sides of the tile about to be placed.
+            let Ok((_, rotation, hex_rotation)) = tiles_q.get(tile_storage.get(&tile_pos).unwrap())
+            else {
+                continue;
+            };
+            let candle_passages = tile_passages(next_tile_kind.0, rotation, hex_rotation);
+
+            // Light spills into empty cells but is stopped by the walls of placed tiles.
+            let passages_at = |pos: &TilePos| {
+                let (tile_type, rotation, hex_rotation) =
+                    tiles_q.get(tile_storage.get(pos)?).ok()?;
+                Some(tile_passages(tile_type?.kind, rotation, hex_rotation))
+            };
+            illuminated.extend(lit_neighbors(
+                &tile_pos,
+                candle_passages,
+                map_size,
+                map_type,
+                rules.wrap_around,
+                passages_at,
+            ));
+        }
+
+        if hovered != hover.hovered || illuminated != hover.illuminated || floor.0 != hover.floor {
+            hover.floor = floor.0;
+            hover.hovered = hovered;
+            hover.illuminated = illuminated;
         }
     }
 }
 
-// place current hilighted tiles when clicked
-fn place_highlighted_tile(
+// Moves the hover and light labels to follow the hover state, touching only
+// the tiles that gained or lost one. When another floor is shown, the labels
+// all come off the floor left and go on afresh.
+fn sync_hover_labels(
     mut commands: Commands,
-    next_tile_texture_index: Res<NextTileTextureIndex>,
-    mut mouse_button_input_events: EventReader<MouseButtonInput>,
-    highlighted_tiles_q: Query<Entity, With<HighlightedLabel>>,
+    hover: Res<HoverState>,
+    mut shown: Local<HoverState>,
+    floors: Floors,
+    mut hover_events: EventWriter<HoverEvent>,
 ) {
-    for mouse_button_input in mouse_button_input_events.read() {
-        if mouse_button_input.button == MouseButton::Left && mouse_button_input.state.is_pressed() {
-            for highlighted_tile_entity in highlighted_tiles_q.iter() {
-                commands.entity(highlighted_tile_entity).insert(TileType {
-                    texture_index: next_tile_texture_index.0,
-                });
+    if !hover.is_changed() || *hover == *shown {
+        return;
+    }
+
+    let same_floor = shown.floor == hover.floor;
+    let none_lit = HashSet::new();
+    let (still_lit, already_lit) = if same_floor {
+        (&hover.illuminated, &shown.illuminated)
+    } else {
+        (&none_lit, &none_lit)
+    };
+    let mut events = Vec::new();
+    if shown.hovered != hover.hovered || !same_floor {
+        events.extend(shown.hovered.map(HoverEvent::Left));
+        events.extend(hover.hovered.map(HoverEvent::Entered));
+    }
+    events.extend(
+        shown
+            .illuminated
+            .difference(still_lit)
+            .map(|pos| HoverEvent::Unlit(*pos)),
+    );
+    events.extend(
+        hover
+            .illuminated
+            .difference(already_lit)
+            .map(|pos| HoverEvent::Lit(*pos)),
+    );
+
+    for event in &events {
+        let (floor, pos) = match event {
+            HoverEvent::Entered(pos) | HoverEvent::Lit(pos) => (hover.floor, pos),
+            HoverEvent::Left(pos) | HoverEvent::Unlit(pos) => (shown.floor, pos),
+        };
+        let Some(tile_entity) = floors.tile_on(floor, pos) else {
+            continue;
+        };
+        let mut tile = commands.entity(tile_entity);
+        match event {
+            HoverEvent::Entered(_) => tile.insert(HighlightedLabel),
+            HoverEvent::Left(_) => tile.remove::<HighlightedLabel>(),
+            HoverEvent::Lit(_) => tile.insert(IlluminatedLabel),
+            HoverEvent::Unlit(_) => tile.remove::<IlluminatedLabel>(),
+        };
+    }
+    *shown = hover.clone();
+    hover_events.send_batch(events);
+}
+
+// place current hilighted tiles when clicked or on pressing enter
+fn place_highlighted_tile(
+    (turn, drag, move_mode): (Res<Turn>, Res<HandDrag>, Res<pawn::MoveMode>),
+    bots_q: Query<&Player, With<Bot>>,
+    pawns_q: Query<(&Player, &pawn::Pawn)>,
+    (next_tile_kind, keyboard_input): (Res<NextTileKind>, Res<Input<KeyCode>>),
+    mut gesture_events: EventReader<Gesture>,
+    highlighted_tiles_q: Query<PlacedTileQuery, With<HighlightedLabel>>,
+    mut place_events: EventWriter<PlaceTile>,
+) {
+    // alt+click pings instead, a click picking the tile in hand up out of the
+    // tray puts it down on letting go, and clicks walk the pawn while the move
+    // overlay is on. A drag moves the view and the second click of a double
+    // click centers it, so neither places anything.
+    let alt = keyboard_input.any_pressed([KeyCode::AltLeft, KeyCode::AltRight])
+        || drag.dragging
+        || move_mode.on;
+    let clicks = gesture_events
+        .read()
+        .filter(|gesture| **gesture == Gesture::Click(MouseButton::Left) && !alt)
+        .count()
+        + (keyboard_input.just_pressed(KeyCode::Return) && !move_mode.on) as usize;
+    // wait for the bots to finish their turns
+    if turn.is_bot(&bots_q) {
+        return;
+    }
+    // tiles only go down on the player's own floor, not one being looked at
+    let floor = pawn::pawn_floor(&pawns_q, turn.player);
+    for _ in 0..clicks {
+        for (tile_pos, tile_floor, rotation, hex_rotation) in highlighted_tiles_q.iter() {
+            if tile_floor.0 != floor {
+                continue;
             }
+            place_events.send(PlaceTile {
+                pos: *tile_pos,
+                floor,
+                kind: next_tile_kind.0,
+                rotation: tile_rotation(rotation, hex_rotation),
+            });
         }
     }
 }
 
-// rotate current hilighted tiles when right mouse clicked
+// rotate current hilighted tiles when right mouse clicked, on pressing q or
+// scrolling while dragging the tile in hand
 fn rotate_highlighted_tile(
+    keyboard_input: Res<Input<KeyCode>>,
     mut mouse_button_input_events: EventReader<MouseButtonInput>,
-    highlighted_tiles_q: Query<Entity, With<HighlightedLabel>>,
-    mut tile_flips: Query<&mut TileFlip>,
-    mut flips: Local<u32>,
+    mut mouse_wheel_events: EventReader<MouseWheel>,
+    mut highlighted_tiles_q: Query<TurnedTileQuery, With<HighlightedLabel>>,
+    (state, drag, rules): (Res<State<GameState>>, Res<HandDrag>, Res<RuleSet>),
+    (turn, bots_q, mut budget): (Res<Turn>, Query<&Player, With<Bot>>, CurrentBudget),
+    mut turned_events: EventWriter<HandTurned>,
 ) {
-    for mouse_button_input in mouse_button_input_events.read() {
-        if mouse_button_input.button == MouseButton::Right && mouse_button_input.state.is_pressed()
-        {
-            for highlighted_tile_entity in highlighted_tiles_q.iter() {
-                // get the tile type if it exists on the tile entity
-                if let Ok(mut flip) =
-                    tile_flips.get_component_mut::<TileFlip>(highlighted_tile_entity)
-                {
-                    // rotate the tile
-                    *flips = (*flips + 1) % 4;
-                    match *flips {
-                        0 => {
-                            flip.x = false;
-                            flip.y = false;
-                            flip.d = false;
-                        }
-                        1 => {
-                            flip.x = true;
-                            flip.y = false;
-                            flip.d = true;
-                        }
-                        2 => {
-                            flip.x = true;
-                            flip.y = true;
-                            flip.d = false;
-                        }
-                        _ => {
-                            flip.x = false;
-                            flip.y = true;
-                            flip.d = true;
-                        }
-                    }
-                }
+    // shift+right click erases instead
+    let shift = keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let clicks = mouse_button_input_events
+        .read()
+        .filter(|mouse_button_input| {
+            mouse_button_input.button == MouseButton::Right
+                && mouse_button_input.state.is_pressed()
+                && !shift
+        })
+        .count()
+        + keyboard_input.just_pressed(KeyCode::Q) as usize
+        + mouse_wheel_events
+            .read()
+            .filter(|mouse_wheel| drag.dragging && mouse_wheel.y != 0.0)
+            .count();
+
+    let playing = *state.get() == GameState::Playing;
+    // wait for the bots to finish their turns
+    if playing && turn.is_bot(&bots_q) {
+        return;
+    }
+    // during play only the tile in hand is turned, shown over an empty cell,
+    // never one already on the board
+    let placed = highlighted_tiles_q.iter().any(|(.., tile_type)| {
+        tile_type.is_some_and(|tile_type| tile_type.kind != TileKind::Empty)
+    });
+    if playing && (placed || highlighted_tiles_q.is_empty()) {
+        return;
+    }
+    // during play, turning the tile in hand costs an action unless the house
+    // rules say otherwise
+    let paid = playing && !rules.unlimited_rotation;
+    for _ in 0..clicks {
+        if paid && !budget.spend() {
+            continue;
+        }
+        for (.., mut rotation, mut hex_rotation, _) in highlighted_tiles_q.iter_mut() {
+            turn_tile(&mut rotation, hex_rotation.as_deref_mut());
+        }
+        if let Some((tile_pos, floor, ..)) = highlighted_tiles_q.iter().next().filter(|_| paid) {
+            turned_events.send(HandTurned {
+                pos: *tile_pos,
+                floor: floor.0,
+            });
+        }
+    }
+}
+
+type IlluminatedTileQuery = (
+    Entity,
+    &'static TileFog,
+    Has<IlluminatedLabel>,
+    &'static mut TileColor,
+);
+
+type IlluminationChangeFilter = Or<(Changed<TileFog>, Added<IlluminatedLabel>)>;
+
+type BoardChangeFilter = Or<(Changed<TileType>, Changed<Rotation>, Changed<HexRotation>)>;
+
+type ChangedTextureFilter = Or<(
+    Added<TileTextureIndex>,
+    Changed<TileType>,
+    Added<HighlightedLabel>,
+)>;
+
+type PlacedTileQuery = (
+    &'static TilePos,
+    &'static Floor,
+    &'static Rotation,
+    Option<&'static HexRotation>,
+);
+
+type TurnedTileQuery = (
+    &'static TilePos,
+    &'static Floor,
+    &'static mut Rotation,
+    Option<&'static mut HexRotation>,
+    Option<&'static TileType>,
+);
+
+type HexTileQuery = (
+    &'static TilePos,
+    &'static Floor,
+    Option<&'static TileType>,
+    &'static HexRotation,
+    &'static TileFog,
+    Has<HighlightedLabel>,
+);
+
+// The square artwork can't be turned a sixth, so hex boards draw each tile's
+// passages over it.
+fn draw_hex_passages(
+    mut gizmos: Gizmos,
+    next_tile_kind: Res<NextTileKind>,
+    current_floor: Res<CurrentFloor>,
+    tilemap_q: Query<(&TilemapGridSize, &TilemapType, &Transform), With<ShownFloor>>,
+    tiles_q: Query<HexTileQuery>,
+) {
+    for (grid_size, map_type, map_transform) in tilemap_q.iter() {
+        let TilemapType::Hexagon(_) = map_type else {
+            continue;
+        };
+
+        for (tile_pos, floor, tile_type, hex_rotation, fog, highlighted) in tiles_q.iter() {
+            // tiles still lost in the dark can't be seen
+            if !current_floor.shows(floor) || fog.brightness <= 0.0 {
+                continue;
+            }
+            let kind = match (highlighted, tile_type) {
+                (true, _) => next_tile_kind.0,
+                (false, Some(tile_type)) => tile_type.kind,
+                (false, None) => continue,
+            };
+
+            let center = tile_center(tile_pos, grid_size, map_type, map_transform);
+            let reach = grid_size.x * map_transform.scale.x / 2.0;
+            let color = Color::ORANGE.with_a(fog.brightness);
+            for direction in kind
+                .hex_passages()
+                .rotated_hex(hex_rotation.0)
+                .hex_directions()
+            {
+                gizmos.line_2d(
+                    center,
+                    center + hex_direction_vector(direction) * reach,
+                    color,
+                );
             }
         }
     }
 }
 
+type FogChangeFilter = Or<(
+    Changed<TileFog>,
+    Added<HighlightedLabel>,
+    Added<IlluminatedLabel>,
+)>;
+
+// fade tiles in and out of the dark depending on whether a candle reaches them.
+// Only tiles whose light just changed, or whose fog was changed from outside,
+// are looked at, and then each frame until they've faded to where they're
+// headed, so a settled board costs nothing however large it is.
+fn update_tile_fog(
+    time: Res<Time>,
+    mut fading: Local<HashSet<Entity>>,
+    (mut unhovered_tiles, mut unlit_tiles): (
+        RemovedComponents<HighlightedLabel>,
+        RemovedComponents<IlluminatedLabel>,
+    ),
+    changed_tiles_q: Query<Entity, FogChangeFilter>,
+    mut tiles_q: Query<(&mut TileFog, Has<HighlightedLabel>, Has<IlluminatedLabel>)>,
+) {
+    fading.extend(unhovered_tiles.read());
+    fading.extend(unlit_tiles.read());
+    fading.extend(changed_tiles_q.iter());
+    fading.retain(|tile_entity| {
+        // removals can name tiles that have since been despawned
+        let Ok((mut fog, highlighted, illuminated)) = tiles_q.get_mut(*tile_entity) else {
+            return false;
+        };
+        let lit = highlighted || illuminated;
+        // only write when something moves, so settled tiles don't show as changed
+        if lit && !fog.explored {
+            fog.explored = true;
+        }
+
+        let target = if lit {
+            1.0
+        } else if fog.explored {
+            FOG_EXPLORED_BRIGHTNESS
+        } else {
+            0.0
+        };
+
+        let step = FOG_FADE_SPEED * time.delta_seconds();
+        if fog.brightness < target {
+            fog.brightness = (fog.brightness + step).min(target);
+        } else if fog.brightness > target {
+            fog.brightness = (fog.brightness - step).max(target);
+        }
+        fog.brightness != target
+    });
+}
+
+// Only tiles whose fog or light changed are recoloured, unless the palette
+// changed or the editor has just handed the board back.
 fn illuminate_tiles(
-    mut commands: Commands,
-    illuminated_tiles_q: Query<Entity, With<IlluminatedLabel>>,
-    non_illuminated_tiles_q: Query<Entity, Without<IlluminatedLabel>>,
+    palette: Res<Palette>,
+    state: Res<State<GameState>>,
+    mut unlit_tiles: RemovedComponents<IlluminatedLabel>,
+    changed_tiles_q: Query<Entity, IlluminationChangeFilter>,
+    mut tiles_q: Query<IlluminatedTileQuery>,
 ) {
-    for illuminated_tile_entity in illuminated_tiles_q.iter() {
-        commands
-            .entity(illuminated_tile_entity)
-            .insert(TileColor(Color::ORANGE_RED));
+    let mut dirty: HashSet<Entity> = unlit_tiles.read().collect();
+    dirty.extend(changed_tiles_q.iter());
+    if palette.is_changed() || state.is_changed() {
+        dirty.extend(tiles_q.iter().map(|(tile_entity, ..)| tile_entity));
     }
+    for tile_entity in dirty {
+        // removals can name tiles that have since been despawned
+        let Ok((_, fog, illuminated, mut color)) = tiles_q.get_mut(tile_entity) else {
+            continue;
+        };
+        let tint = if illuminated {
+            palette.lit()
+        } else {
+            palette.unlit()
+        };
+        let tint = tint.with_a(fog.brightness);
+        if color.0 != tint {
+            color.0 = tint;
+        }
+    }
+}
 
-    for non_illuminated_tile_entity in non_illuminated_tiles_q.iter() {
-        commands
-            .entity(non_illuminated_tile_entity)
-            .insert(TileColor(Color::WHITE));
+// Which frame of the pack's artwork each tile shows.
+#[derive(SystemParam)]
+struct TileFrames<'w> {
+    time: Res<'w, Time>,
+    pack: Res<'w, TilePack>,
+    next_tile_kind: Res<'w, NextTileKind>,
+}
+
+impl<'w> TileFrames<'w> {
+    fn animation(&self, tile_type: Option<&TileType>, highlighted: bool) -> &TileAnimation {
+        // the hovered tile shows the tile in hand in place of whatever is there
+        match tile_type {
+            _ if highlighted => self.pack.held_animation(self.next_tile_kind.0),
+            Some(tile_type) => self.pack.animation(tile_type.kind),
+            None => self.pack.animation(TileKind::Empty),
+        }
+    }
+
+    fn frame(&self, tile_type: Option<&TileType>, highlighted: bool) -> u32 {
+        self.animation(tile_type, highlighted)
+            .frame_at(self.time.elapsed_seconds())
     }
 }
 
-fn apply_tile_textures(
-    mut commands: Commands,
-    next_tile_texture_index: Res<NextTileTextureIndex>,
+type TileTextureQuery = (
+    Option<&'static TileType>,
+    Has<HighlightedLabel>,
+    &'static mut TileTextureIndex,
+);
+
+// Textures are only rewritten for tiles that were just spawned, placed on,
+// cleared, or hovered onto or off of, for the hovered tile when the tile in
+// hand changes, and for every tile when the pack does. Tiles showing an
+// animated kind are kept track of and moved on to their next frame when it's
+// due, so still tiles aren't looked at again until they change.
+fn update_tile_textures(
+    frames: TileFrames,
+    mut animated: Local<HashSet<Entity>>,
+    (mut cleared_tiles, mut unhovered_tiles): (
+        RemovedComponents<TileType>,
+        RemovedComponents<HighlightedLabel>,
+    ),
+    changed_tiles_q: Query<Entity, ChangedTextureFilter>,
     highlighted_tiles_q: Query<Entity, With<HighlightedLabel>>,
-    non_highlighted_tiles_q: Query<Entity, Without<HighlightedLabel>>,
-    tile_types: Query<&TileType>,
+    all_tiles_q: Query<Entity, With<TileTextureIndex>>,
+    mut tiles_q: Query<TileTextureQuery>,
 ) {
-    for highlighted_tile_entity in highlighted_tiles_q.iter() {
-        commands
-            .entity(highlighted_tile_entity)
-            .insert(TileTextureIndex(next_tile_texture_index.0));
+    let refresh_all = frames.pack.is_changed();
+    let mut dirty: HashSet<Entity> = cleared_tiles.read().collect();
+    dirty.extend(unhovered_tiles.read());
+    dirty.extend(changed_tiles_q.iter());
+    dirty.extend(animated.drain());
+    if frames.next_tile_kind.is_changed() {
+        dirty.extend(highlighted_tiles_q.iter());
+    }
+    if refresh_all {
+        dirty.extend(all_tiles_q.iter());
     }
 
-    for non_highlighted_tile_entity in non_highlighted_tiles_q.iter() {
-        if let Ok(tile_type) = tile_types.get_component::<TileType>(non_highlighted_tile_entity) {
-            commands
-                .entity(non_highlighted_tile_entity)
-                .insert(TileTextureIndex(tile_type.texture_index));
-        } else {
-            commands
-                .entity(non_highlighted_tile_entity)
-                .insert(TileTextureIndex(0));
+    for tile_entity in dirty {
+        // removals can name tiles that have since been despawned
+        let Ok((tile_type, highlighted, mut texture)) = tiles_q.get_mut(tile_entity) else {
+            continue;
+        };
+        if frames.animation(tile_type, highlighted).is_animated() {
+            animated.insert(tile_entity);
+        }
+        let frame = frames.frame(tile_type, highlighted);
+        if refresh_all || texture.0 != frame {
+            texture.0 = frame;
         }
     }
 }
 
-#[derive(Resource)]
-struct NextTileTextureIndex(u32);
-impl Default for NextTileTextureIndex {
-    fn default() -> Self {
-        Self(1)
+// Tiles are drawn turned by flipping their artwork to match their rotation.
+fn flip_rotated_tiles(mut tiles_q: Query<(&Rotation, &mut TileFlip), Changed<Rotation>>) {
+    for (rotation, mut flip) in tiles_q.iter_mut() {
+        *flip = rotation.0.flip();
     }
 }
 
-impl NextTileTextureIndex {
-    fn next(&mut self) {
-        self.0 = (self.0 % 4) + 1;
+// Once the tile artwork has been reloaded every tile's texture is written
+// again, so the tilemap lays its tiles out afresh against the new artwork.
+fn reapply_tile_textures(
+    frames: TileFrames,
+    asset_server: Res<AssetServer>,
+    mut image_events: EventReader<AssetEvent<Image>>,
+    mut tiles_q: Query<TileTextureQuery>,
+) {
+    if image_events.is_empty() {
+        return;
+    }
+    let tiles_texture: Handle<Image> = asset_server.load(frames.pack.tiles.clone());
+    if !image_events
+        .read()
+        .any(|event| event.is_modified(&tiles_texture))
+    {
+        return;
+    }
+
+    for (tile_type, highlighted, mut texture) in tiles_q.iter_mut() {
+        texture.0 = frames.frame(tile_type, highlighted);
+        texture.set_changed();
     }
 }
 
-// cycle next tile texture index on pressing space
-fn cycle_tile_texture_index(
-    mut next_tile_texture_index: ResMut<NextTileTextureIndex>,
+// cycle the tile kind the editor paints on pressing space
+fn cycle_next_tile_kind(
+    mut next_tile_kind: ResMut<NextTileKind>,
     mut keyboard_input_events: EventReader<KeyboardInput>,
 ) {
     for keyboard_input in keyboard_input_events.read() {
         if keyboard_input.state.is_pressed() && keyboard_input.key_code == Some(KeyCode::Space) {
-            next_tile_texture_index.next();
+            next_tile_kind.next();
         }
     }
 }
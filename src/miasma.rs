@@ -0,0 +1,29 @@
+use bevy::prelude::*;
+
+use crate::content::{Content, RulesSet};
+use crate::rules::{TileBag, TileCollapsed};
+use crate::tiles::TileKind;
+
+// The Miasma expansion, with foul air rising from the deep. The bag is heavier
+// with dead ends and pillars, another wax eater lurks from the start, and the
+// rubble of every collapse chokes a passage, putting a dead end on the
+// discard pile.
+pub struct MiasmaExpansionPlugin;
+
+impl Plugin for MiasmaExpansionPlugin {
+    fn build(&self, app: &mut App) {
+        let mut content = Content::of(app);
+        content.plugins.push("miasma");
+        content
+            .extra_tiles
+            .extend([(TileKind::DeadEnd, 2), (TileKind::Pillar, 1)]);
+        content.extra_wax_eaters += 1;
+        app.add_systems(Update, choke_passages.in_set(RulesSet));
+    }
+}
+
+pub fn choke_passages(mut collapse_events: EventReader<TileCollapsed>, mut bag: ResMut<TileBag>) {
+    for _ in collapse_events.read() {
+        bag.discard(TileKind::DeadEnd);
+    }
+}
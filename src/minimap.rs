@@ -0,0 +1,197 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::prelude::*;
+use bevy_ecs_tilemap::prelude::*;
+
+use crate::board::{tile_center, BoardConfig, BoardShape, IlluminatedLabel, TileFog, TileType};
+use crate::camera::{FollowPawn, MainView};
+use crate::floor::{CurrentFloor, Floor, Floors, ShownFloor};
+use crate::palette::Palette;
+use crate::pawn::{pawn_color, Pawn};
+use crate::rules::Player;
+
+// Largest the minimap grows to on either side, in pixels.
+const MINIMAP_SIZE: f32 = 160.0;
+const MAX_CELL_SIZE: f32 = 12.0;
+// Just below the HUD text in the top right corner.
+const MINIMAP_TOP: f32 = 160.0;
+
+#[derive(Component)]
+pub struct Minimap;
+
+// One cell of the board on the minimap. Clicking it centers the view there.
+#[derive(Component)]
+pub struct MinimapCell {
+    pos: TilePos,
+}
+
+pub fn spawn_minimap(mut commands: Commands, board_config: Res<BoardConfig>) {
+    let size = board_config.size;
+    let cell = (MINIMAP_SIZE / size.x.max(size.y) as f32).min(MAX_CELL_SIZE);
+    // offset rows on hex boards are pushed half a cell across
+    let hex = board_config.shape == BoardShape::Hex;
+    let width = (size.x as f32 + if hex { 0.5 } else { 0.0 }) * cell;
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(MINIMAP_TOP),
+                    right: Val::Px(8.0),
+                    width: Val::Px(width),
+                    height: Val::Px(size.y as f32 * cell),
+                    ..default()
+                },
+                background_color: Color::BLACK.with_a(0.6).into(),
+                ..default()
+            },
+            // so the gaps between cells count as being over the minimap
+            Interaction::default(),
+            Minimap,
+        ))
+        .with_children(|minimap| {
+            for y in 0..size.y {
+                for x in 0..size.x {
+                    let shift = if hex && y % 2 == 1 { cell / 2.0 } else { 0.0 };
+                    minimap.spawn((
+                        ButtonBundle {
+                            style: Style {
+                                position_type: PositionType::Absolute,
+                                // the board's first row is at the bottom
+                                top: Val::Px((size.y - 1 - y) as f32 * cell),
+                                left: Val::Px(x as f32 * cell + shift),
+                                width: Val::Px(cell - 1.0),
+                                height: Val::Px(cell - 1.0),
+                                ..default()
+                            },
+                            background_color: Color::NONE.into(),
+                            ..default()
+                        },
+                        MinimapCell {
+                            pos: TilePos { x, y },
+                        },
+                    ));
+                }
+            }
+        });
+}
+
+type MinimapTileQuery = (
+    &'static TilePos,
+    &'static Floor,
+    Has<TileType>,
+    Has<IlluminatedLabel>,
+    &'static TileFog,
+);
+
+type MinimapChangeFilter = Or<(Changed<TileFog>, Changed<TileType>, Added<IlluminatedLabel>)>;
+
+type ShownTilemapFilter = (With<ShownFloor>, Without<MainView>);
+
+// The minimap's cells by the board cell each maps, and where pawns were last
+// shown on it.
+#[derive(Default)]
+pub struct MinimapCells {
+    cells: HashMap<TilePos, Entity>,
+    pawns: Vec<TilePos>,
+}
+
+// Pawns show in their player's colour over lit tiles, then placed ones. Only
+// explored tiles show at all, so the minimap gives nothing away. It maps the
+// floor on show. Only cells whose tile changed are coloured again, along with
+// those pawns are on or have just left, unless the palette or the floor on
+// show changed.
+pub fn update_minimap(
+    (palette, current_floor): (Res<Palette>, Res<CurrentFloor>),
+    (floors, mut shown): (Floors, Local<MinimapCells>),
+    (tiles_q, changed_tiles_q): (Query<MinimapTileQuery>, Query<Entity, MinimapChangeFilter>),
+    (mut unlit_tiles, mut cleared_tiles): (
+        RemovedComponents<IlluminatedLabel>,
+        RemovedComponents<TileType>,
+    ),
+    pawns_q: Query<(&Player, &Pawn)>,
+    new_cells_q: Query<(), Added<MinimapCell>>,
+    mut cells_q: Query<(Entity, &MinimapCell, &mut BackgroundColor)>,
+) {
+    let mut changed: HashSet<Entity> = unlit_tiles.read().collect();
+    changed.extend(cleared_tiles.read());
+    changed.extend(changed_tiles_q.iter());
+
+    let mut dirty: HashSet<TilePos> = HashSet::new();
+    if palette.is_changed() || current_floor.is_changed() || !new_cells_q.is_empty() {
+        shown.cells = cells_q
+            .iter()
+            .map(|(cell_entity, cell, _)| (cell.pos, cell_entity))
+            .collect();
+        dirty.extend(shown.cells.keys());
+    }
+    for tile_entity in changed {
+        // removals can name tiles that have since been despawned
+        if let Ok((pos, floor, ..)) = tiles_q.get(tile_entity) {
+            if current_floor.shows(floor) {
+                dirty.insert(*pos);
+            }
+        }
+    }
+    let pawns: Vec<(TilePos, Color)> = pawns_q
+        .iter()
+        .filter(|(_, pawn)| pawn.floor == current_floor.0)
+        .map(|(player, pawn)| (pawn.pos, pawn_color(player)))
+        .collect();
+    dirty.extend(shown.pawns.drain(..));
+    dirty.extend(pawns.iter().map(|(pos, _)| *pos));
+    shown.pawns = pawns.iter().map(|(pos, _)| *pos).collect();
+
+    let tile_color = |pos: &TilePos| {
+        let (_, _, placed, illuminated, fog) = tiles_q.get(floors.tile(pos)?).ok()?;
+        if illuminated {
+            Some(palette.lit())
+        } else if placed && fog.explored {
+            Some(palette.unlit())
+        } else {
+            None
+        }
+    };
+    for pos in dirty {
+        let Some(Ok((_, _, mut background))) = shown
+            .cells
+            .get(&pos)
+            .map(|cell_entity| cells_q.get_mut(*cell_entity))
+        else {
+            continue;
+        };
+        let color = pawns
+            .iter()
+            .find(|(pawn_pos, _)| *pawn_pos == pos)
+            .map(|(_, color)| *color)
+            .or_else(|| tile_color(&pos))
+            .unwrap_or(Color::NONE);
+        if background.0 != color {
+            background.0 = color;
+        }
+    }
+}
+
+// center the view on a cell when it's clicked on the minimap
+pub fn minimap_click(
+    cells_q: Query<(&MinimapCell, &Interaction), Changed<Interaction>>,
+    tilemap_q: Query<(&TilemapGridSize, &TilemapType, &Transform), ShownTilemapFilter>,
+    mut camera_q: Query<(&mut Transform, Option<&mut FollowPawn>), With<MainView>>,
+) {
+    for (cell, interaction) in cells_q.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        for (grid_size, map_type, map_transform) in tilemap_q.iter() {
+            let center = tile_center(&cell.pos, grid_size, map_type, map_transform);
+            for (mut transform, follow) in camera_q.iter_mut() {
+                if let Some(mut follow) = follow {
+                    follow.on = false;
+                }
+                transform.translation.x = center.x;
+                transform.translation.y = center.y;
+            }
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use bevy::prelude::*;
+
+use crate::difficulty::Difficulty;
+use crate::rules::{Bot, CurrentBudget, Dealer, Player, Turn};
+
+// The shared pool of nerve tokens.
+#[derive(Resource, Default)]
+pub struct Nerve(pub u32);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NerveAction {
+    // Take another action before the turn passes on.
+    ExtraAction,
+    // Swap the tile in hand for another from the bag.
+    Redraw,
+    // Shrug off a monster hit.
+    SurviveHit,
+}
+
+// A request to spend a token from the pool. It is ignored once the pool is empty.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct SpendNerve(pub NerveAction);
+
+pub fn fill_nerve(difficulty: Res<Difficulty>, mut nerve: ResMut<Nerve>) {
+    nerve.0 = difficulty.nerve();
+}
+
+// spend nerve on pressing n for an extra action or r to redraw
+pub fn nerve_input(
+    keyboard_input: Res<Input<KeyCode>>,
+    turn: Res<Turn>,
+    bots_q: Query<&Player, With<Bot>>,
+    mut spend_events: EventWriter<SpendNerve>,
+) {
+    if turn.is_bot(&bots_q) {
+        return;
+    }
+    if keyboard_input.just_pressed(KeyCode::N) {
+        spend_events.send(SpendNerve(NerveAction::ExtraAction));
+    }
+    if keyboard_input.just_pressed(KeyCode::R) {
+        spend_events.send(SpendNerve(NerveAction::Redraw));
+    }
+}
+
+pub fn spend_nerve(
+    mut spend_events: EventReader<SpendNerve>,
+    mut nerve: ResMut<Nerve>,
+    mut dealer: Dealer,
+    mut budget: CurrentBudget,
+) {
+    for SpendNerve(action) in spend_events.read() {
+        if nerve.0 == 0 {
+            continue;
+        }
+        nerve.0 -= 1;
+        match action {
+            NerveAction::ExtraAction => budget.grant(1),
+            NerveAction::Redraw => dealer.redraw(),
+            // The hit itself was shrugged off where it landed.
+            NerveAction::SurviveHit => {}
+        }
+    }
+}
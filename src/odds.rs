@@ -0,0 +1,90 @@
+use bevy::prelude::*;
+
+use crate::locale::Strings;
+use crate::rules::TileBag;
+use crate::tiles::TileKind;
+
+// The panel down the left of the screen listing what's left in the bag and
+// the chance of drawing each kind next. What's in the bag is no secret, only
+// the order it comes out in.
+#[derive(Component)]
+pub struct OddsPanel;
+
+pub fn spawn_odds_panel(mut commands: Commands) {
+    commands
+        .spawn((
+            TextBundle::from_section(
+                "",
+                TextStyle {
+                    font_size: 18.0,
+                    color: Color::ORANGE,
+                    ..default()
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(120.0),
+                left: Val::Px(8.0),
+                padding: UiRect::all(Val::Px(8.0)),
+                ..default()
+            })
+            .with_background_color(Color::BLACK.with_a(0.6)),
+            OddsPanel,
+        ))
+        .insert(Visibility::Hidden);
+}
+
+// toggle the odds on pressing tab
+pub fn toggle_odds_panel(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut panel_q: Query<&mut Visibility, With<OddsPanel>>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::Tab) {
+        return;
+    }
+    for mut visibility in panel_q.iter_mut() {
+        *visibility = match *visibility {
+            Visibility::Hidden => Visibility::Inherited,
+            _ => Visibility::Hidden,
+        };
+    }
+}
+
+// How many of each kind are left in the bag, in the order kinds are cycled
+// through, leaving out the ones there are none of.
+pub fn remaining(bag: &TileBag) -> Vec<(TileKind, usize)> {
+    TileKind::PLACEABLE
+        .into_iter()
+        .map(|kind| (kind, bag.tiles.iter().filter(|tile| **tile == kind).count()))
+        .filter(|(_, count)| *count > 0)
+        .collect()
+}
+
+pub fn update_odds_panel(
+    bag: Res<TileBag>,
+    strings: Res<Strings>,
+    mut text_q: Query<(&mut Text, Ref<OddsPanel>)>,
+) {
+    for (mut text, panel) in text_q.iter_mut() {
+        if !bag.is_changed() && !strings.is_changed() && !panel.is_added() {
+            continue;
+        }
+        let total = bag.tiles.len();
+        let mut lines = vec![strings.format("odds-title", &[("count", total.to_string())])];
+        if total == 0 {
+            lines.push(strings.get("odds-fresh-bag"));
+        }
+        for (kind, count) in remaining(&bag) {
+            let percent = count as f32 * 100.0 / total as f32;
+            lines.push(strings.format(
+                "odds-line",
+                &[
+                    ("tile", strings.tile(kind)),
+                    ("count", count.to_string()),
+                    ("percent", format!("{:.0}", percent)),
+                ],
+            ));
+        }
+        text.sections[0].value = lines.join("\n");
+    }
+}
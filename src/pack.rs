@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use bevy_ecs_tilemap::prelude::*;
+
+use crate::board::{BoardConfig, TEXTURE_TILE_SIZE, TILES_TEXTURE};
+use crate::locale::parse;
+use crate::tiles::{TileAnimation, TileKind};
+
+// Packs live in assets/packs/<name>, each with a pack.txt manifest:
+//
+//     # the artwork, relative to the pack, and the size of its squares
+//     tiles = tiles.png
+//     tile-size = 64
+//     # seconds each frame of an animated tile shows
+//     frame-seconds = 0.2
+//     # texture indices of each kind's frames, in the names scenarios use
+//     straight = 2
+//     pillar = 1 9 10 11
+//     # and as the tile in hand, when it should look different there
+//     held-straight = 2 12 13
+//
+// Kinds a pack leaves out keep the built-in artwork's indices, and kinds it
+// gives no held frames show their own frames in hand.
+const PACK_DIR: &str = "packs";
+const MANIFEST: &str = "pack.txt";
+
+// The pack built into the game, drawn from assets/tiles.png.
+pub const DEFAULT_PACK: &str = "default";
+
+const DEFAULT_FRAME_SECONDS: f32 = 0.15;
+
+// The tile artwork the board is drawn with and how it is laid out.
+#[derive(Resource, Clone, Debug, PartialEq)]
+pub struct TilePack {
+    pub name: String,
+    // Path of the artwork in the assets directory.
+    pub tiles: String,
+    // Size of a square of the artwork, in pixels.
+    pub tile_size: f32,
+    animations: HashMap<TileKind, TileAnimation>,
+    // How each kind looks as the tile in hand.
+    held: HashMap<TileKind, TileAnimation>,
+}
+
+impl Default for TilePack {
+    fn default() -> Self {
+        let mut animations: HashMap<TileKind, TileAnimation> = TileKind::PLACEABLE
+            .into_iter()
+            .map(|kind| (kind, kind.animation()))
+            .collect();
+        animations.insert(TileKind::Empty, TileKind::Empty.animation());
+        let held = animations
+            .keys()
+            .map(|kind| (*kind, kind.held_animation()))
+            .collect();
+        Self {
+            name: DEFAULT_PACK.to_string(),
+            tiles: TILES_TEXTURE.to_string(),
+            tile_size: TEXTURE_TILE_SIZE,
+            animations,
+            held,
+        }
+    }
+}
+
+impl TilePack {
+    // Loads `assets/packs/<name>/pack.txt`.
+    pub fn load(name: &str) -> Result<TilePack, String> {
+        let mut pack = TilePack::default();
+        if name == DEFAULT_PACK {
+            return Ok(pack);
+        }
+        let dir = format!("{}/{}", PACK_DIR, name);
+        let path = format!("assets/{}/{}", dir, MANIFEST);
+        let source = std::fs::read_to_string(&path)
+            .map_err(|err| format!("failed to read {}: {}", path, err))?;
+        let manifest = parse(&source);
+        let number = |key: &str, default: f32| match manifest.get(key) {
+            Some(value) => value
+                .parse::<f32>()
+                .ok()
+                .filter(|number| *number > 0.0)
+                .ok_or_else(|| format!("invalid {} {} in {}", key, value, path)),
+            None => Ok(default),
+        };
+
+        pack.name = name.to_string();
+        pack.tiles = format!(
+            "{}/{}",
+            dir,
+            manifest.get("tiles").map_or(TILES_TEXTURE, String::as_str)
+        );
+        pack.tile_size = number("tile-size", TEXTURE_TILE_SIZE)?;
+        let frame_seconds = number("frame-seconds", DEFAULT_FRAME_SECONDS)?;
+        let mut held_animations = Vec::new();
+        for (key, value) in &manifest {
+            let (held, name) = match key.strip_prefix("held-") {
+                Some(name) => (true, name),
+                None => (false, key.as_str()),
+            };
+            let Some(kind) = TileKind::from_name(name) else {
+                continue;
+            };
+            let frames = value
+                .split_whitespace()
+                .map(|frame| frame.parse::<u32>())
+                .collect::<Result<Vec<u32>, _>>()
+                .ok()
+                .filter(|frames| !frames.is_empty())
+                .ok_or_else(|| format!("invalid frames {} for {} in {}", value, key, path))?;
+            let animation = TileAnimation {
+                frames,
+                frame_seconds,
+            };
+            if held {
+                held_animations.push((kind, animation));
+            } else {
+                pack.animations.insert(kind, animation);
+            }
+        }
+        // the built-in flicker frames are only on the built-in artwork
+        pack.held = pack.animations.clone();
+        pack.held.extend(held_animations);
+        Ok(pack)
+    }
+
+    // The built-in pack and every pack in assets/packs, in name order.
+    pub fn available() -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(format!("assets/{}", PACK_DIR))
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter(|entry| entry.path().join(MANIFEST).is_file())
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .collect();
+        names.sort();
+        names.insert(0, DEFAULT_PACK.to_string());
+        names
+    }
+
+    pub fn animation(&self, kind: TileKind) -> &TileAnimation {
+        &self.animations[&kind]
+    }
+
+    pub fn held_animation(&self, kind: TileKind) -> &TileAnimation {
+        &self.held[&kind]
+    }
+
+    // Loads the pack after this one, going back to the first after the last.
+    pub fn next(&self) -> Result<TilePack, String> {
+        let names = TilePack::available();
+        let next = names
+            .iter()
+            .position(|name| *name == self.name)
+            .map_or(0, |current| (current + 1) % names.len());
+        TilePack::load(&names[next])
+    }
+}
+
+// switch to the next tile pack on pressing o
+pub fn cycle_pack(keyboard_input: Res<Input<KeyCode>>, mut pack: ResMut<TilePack>) {
+    if !keyboard_input.just_pressed(KeyCode::O) {
+        return;
+    }
+    match pack.next() {
+        Ok(next) => *pack = next,
+        Err(err) => warn!("{}", err),
+    }
+}
+
+// Points the tilemap at the new pack's artwork when the pack changes. The
+// board keeps its size on screen whatever size the pack's squares are.
+pub fn apply_pack(
+    asset_server: Res<AssetServer>,
+    board_config: Res<BoardConfig>,
+    pack: Res<TilePack>,
+    mut tilemap_q: Query<(
+        &mut TilemapTexture,
+        &mut TilemapTileSize,
+        &mut TilemapGridSize,
+        &TilemapType,
+        &mut Transform,
+    )>,
+) {
+    if !pack.is_changed() || pack.is_added() {
+        return;
+    }
+    for (mut texture, mut tile_size, mut grid_size, map_type, mut transform) in tilemap_q.iter_mut()
+    {
+        *texture = TilemapTexture::Single(asset_server.load(pack.tiles.clone()));
+        *tile_size = TilemapTileSize {
+            x: pack.tile_size,
+            y: pack.tile_size,
+        };
+        *grid_size = (*tile_size).into();
+        *transform = board_config.tilemap_transform(&grid_size, map_type);
+    }
+}
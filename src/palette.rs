@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use bevy_ecs_tilemap::prelude::*;
+
+use crate::board::{tile_center, IlluminatedLabel};
+use crate::floor::ShownFloor;
+use crate::locale::parse;
+
+pub const PALETTE_FILE: &str = "assets/palettes.txt";
+
+// The shipped colours are built in, so the game still has them where the file
+// can't be read.
+const FALLBACK: &str = include_str!("../assets/palettes.txt");
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PaletteKind {
+    #[default]
+    Classic,
+    Colorblind,
+    HighContrast,
+}
+
+impl PaletteKind {
+    pub fn from_name(name: &str) -> Option<PaletteKind> {
+        [
+            PaletteKind::Classic,
+            PaletteKind::Colorblind,
+            PaletteKind::HighContrast,
+        ]
+        .into_iter()
+        .find(|kind| kind.name() == name)
+    }
+
+    // Name used on the command line and in the palette file.
+    pub fn name(self) -> &'static str {
+        match self {
+            PaletteKind::Classic => "classic",
+            PaletteKind::Colorblind => "colorblind",
+            PaletteKind::HighContrast => "high-contrast",
+        }
+    }
+
+    pub fn next(self) -> PaletteKind {
+        match self {
+            PaletteKind::Classic => PaletteKind::Colorblind,
+            PaletteKind::Colorblind => PaletteKind::HighContrast,
+            PaletteKind::HighContrast => PaletteKind::Classic,
+        }
+    }
+}
+
+// The chosen palette and the colours of every palette from the palette file.
+// Colours missing from the file fall back to the built-in ones.
+#[derive(Resource, Clone, Debug, PartialEq)]
+pub struct Palette {
+    pub kind: PaletteKind,
+    colors: HashMap<String, Color>,
+    fallback: HashMap<String, Color>,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self {
+            kind: PaletteKind::default(),
+            colors: HashMap::new(),
+            fallback: parse_colors(FALLBACK),
+        }
+    }
+}
+
+impl Palette {
+    pub fn new(kind: PaletteKind) -> Palette {
+        let mut palette = Palette { kind, ..default() };
+        // the built-in colours do where the file can't be read, as on the web
+        palette.reload().ok();
+        palette
+    }
+
+    // Reads the palette file again, keeping the colours already loaded if it
+    // can't be read.
+    pub fn reload(&mut self) -> Result<(), String> {
+        let source = std::fs::read_to_string(PALETTE_FILE)
+            .map_err(|err| format!("failed to read {}: {}", PALETTE_FILE, err))?;
+        self.colors = parse_colors(&source);
+        Ok(())
+    }
+
+    fn color(&self, role: &str) -> Option<Color> {
+        let key = format!("{}-{}", self.kind.name(), role);
+        self.colors
+            .get(&key)
+            .or_else(|| self.fallback.get(&key))
+            .copied()
+    }
+
+    // Tint of tiles the candle's light reaches.
+    pub fn lit(&self) -> Color {
+        self.color("lit").unwrap_or(Color::ORANGE_RED)
+    }
+
+    // Tint of every other tile.
+    pub fn unlit(&self) -> Color {
+        self.color("unlit").unwrap_or(Color::WHITE)
+    }
+
+    // Tint of the hovered cell in the editor.
+    pub fn highlight(&self) -> Color {
+        self.color("highlight").unwrap_or(Color::ORANGE_RED)
+    }
+
+    pub fn background(&self) -> Color {
+        self.color("background").unwrap_or(Color::BLACK)
+    }
+
+    // Colour of the outline drawn around lit tiles, if the palette uses one.
+    pub fn lit_outline(&self) -> Option<Color> {
+        self.color("lit-outline")
+    }
+}
+
+// Palette files are `key = hex colour` lines, with comments and blank lines
+// as in the locale files. Colours that don't parse are skipped.
+fn parse_colors(source: &str) -> HashMap<String, Color> {
+    parse(source)
+        .into_iter()
+        .filter_map(|(key, value)| match Color::hex(&value) {
+            Ok(color) => Some((key, color)),
+            Err(_) => {
+                warn!("invalid colour {} for {} in the palette file", value, key);
+                None
+            }
+        })
+        .collect()
+}
+
+// switch palette on pressing v
+pub fn cycle_palette(keyboard_input: Res<Input<KeyCode>>, mut palette: ResMut<Palette>) {
+    if keyboard_input.just_pressed(KeyCode::V) {
+        palette.kind = palette.kind.next();
+    }
+}
+
+pub fn apply_background(palette: Res<Palette>, mut clear_color: ResMut<ClearColor>) {
+    if palette.is_changed() {
+        clear_color.0 = palette.background();
+    }
+}
+
+pub fn draw_lit_outlines(
+    mut gizmos: Gizmos,
+    palette: Res<Palette>,
+    tilemap_q: Query<(&TilemapGridSize, &TilemapType, &Transform), With<ShownFloor>>,
+    lit_tiles_q: Query<&TilePos, With<IlluminatedLabel>>,
+) {
+    let Some(color) = palette.lit_outline() else {
+        return;
+    };
+    for (grid_size, map_type, map_transform) in tilemap_q.iter() {
+        let size = Vec2::new(grid_size.x, grid_size.y) * map_transform.scale.xy() * 0.9;
+        for tile_pos in lit_tiles_q.iter() {
+            let center = tile_center(tile_pos, grid_size, map_type, map_transform);
+            if let TilemapType::Hexagon(_) = map_type {
+                gizmos.circle_2d(center, size.x / 2.0, color);
+            } else {
+                gizmos.rect_2d(center, 0.0, size, color);
+            }
+        }
+    }
+}
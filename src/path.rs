@@ -0,0 +1,81 @@
+use std::collections::{hash_map::Entry, HashMap, VecDeque};
+
+use bevy_ecs_tilemap::prelude::*;
+
+use crate::board::neighbors;
+
+// The shortest ways over the board from one or more cells, found breadth
+// first, taking only the steps `steps_from` allows. Pawns walk the corridors
+// between placed tiles, while the bots and the wax eaters go by how far apart
+// cells are across the board as a whole.
+#[derive(Clone, Debug, Default)]
+pub struct Paths {
+    // Steps to each cell reached, and the cell it was reached from.
+    reached: HashMap<TilePos, (u32, Option<TilePos>)>,
+}
+
+impl Paths {
+    // Searches out from `starts`, at most `max_steps` steps from the nearest.
+    pub fn search(
+        starts: impl IntoIterator<Item = TilePos>,
+        max_steps: u32,
+        steps_from: impl Fn(&TilePos) -> Vec<TilePos>,
+    ) -> Paths {
+        let mut reached = HashMap::new();
+        let mut queue = VecDeque::new();
+        for start in starts {
+            if reached.insert(start, (0, None)).is_none() {
+                queue.push_back(start);
+            }
+        }
+        while let Some(pos) = queue.pop_front() {
+            let steps = reached[&pos].0;
+            if steps >= max_steps {
+                continue;
+            }
+            for next in steps_from(&pos) {
+                if let Entry::Vacant(entry) = reached.entry(next) {
+                    entry.insert((steps + 1, Some(pos)));
+                    queue.push_back(next);
+                }
+            }
+        }
+        Paths { reached }
+    }
+
+    // Steps from the nearest start to `pos`, if it was reached.
+    pub fn steps(&self, pos: &TilePos) -> Option<u32> {
+        self.reached.get(pos).map(|(steps, _)| *steps)
+    }
+
+    // Every cell reached, the starts included, in no particular order.
+    pub fn reached(&self) -> impl Iterator<Item = &TilePos> {
+        self.reached.keys()
+    }
+
+    // The cells stepped through on the way to `to`, ending with it and leaving
+    // out the start.
+    pub fn path_to(&self, to: &TilePos) -> Option<Vec<TilePos>> {
+        let mut path = Vec::new();
+        let mut pos = *to;
+        while let (_, Some(from)) = self.reached.get(&pos)? {
+            path.push(pos);
+            pos = *from;
+        }
+        path.reverse();
+        Some(path)
+    }
+}
+
+// How many steps every cell is from the nearest of `starts`, going from cell
+// to neighboring cell whatever is on them.
+pub fn board_distances(
+    starts: impl IntoIterator<Item = TilePos>,
+    map_size: &TilemapSize,
+    map_type: &TilemapType,
+    wrap: bool,
+) -> Paths {
+    Paths::search(starts, u32::MAX, |pos| {
+        neighbors(pos, map_size, map_type, wrap)
+    })
+}
@@ -0,0 +1,365 @@
+use bevy::{app::AppExit, prelude::*};
+
+use crate::announce::Announcer;
+use crate::assist::Assistant;
+use crate::daily::Daily;
+use crate::house_rules::{HouseRule, RuleSet};
+use crate::juice::Juice;
+use crate::locale::Strings;
+use crate::pack::TilePack;
+use crate::palette::Palette;
+use crate::rng::Seed;
+use crate::share::Watching;
+use crate::state::GameState;
+
+const BUTTON_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.8);
+const HOVERED_BUTTON_COLOR: Color = Color::rgba(0.3, 0.15, 0.0, 0.9);
+
+#[derive(Component)]
+pub struct PauseMenu;
+
+// Which page of the menu is showing.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PausePage {
+    #[default]
+    Main,
+    Settings,
+    HouseRules,
+}
+
+// One of the menu's pages, shown while `PausePage` is on it.
+#[derive(Component)]
+pub struct MenuPage(PausePage);
+
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MenuButton {
+    Resume,
+    Settings,
+    HouseRules,
+    // Start over, dealt the same way again.
+    Restart,
+    // Start over with a fresh seed.
+    NewGame,
+    Quit,
+    // Game over summary
+    Rematch,
+    // Takes up or turns down the assistant's offer to ease the next game.
+    Ease,
+    // Settings page
+    Palette,
+    Pack,
+    Language,
+    Announcements,
+    ScreenEffects,
+    Assistant,
+    Back,
+    // House rules page
+    Rule(HouseRule),
+}
+
+const MAIN_PAGE: [MenuButton; 6] = [
+    MenuButton::Resume,
+    MenuButton::Settings,
+    MenuButton::HouseRules,
+    MenuButton::Restart,
+    MenuButton::NewGame,
+    MenuButton::Quit,
+];
+
+const SETTINGS_PAGE: [MenuButton; 7] = [
+    MenuButton::Palette,
+    MenuButton::Pack,
+    MenuButton::Language,
+    MenuButton::Announcements,
+    MenuButton::ScreenEffects,
+    MenuButton::Assistant,
+    MenuButton::Back,
+];
+
+const HOUSE_RULES_PAGE: [MenuButton; 6] = [
+    MenuButton::Rule(HouseRule::FriendlyMonsters),
+    MenuButton::Rule(HouseRule::NoCollapse),
+    MenuButton::Rule(HouseRule::UnlimitedRotation),
+    MenuButton::Rule(HouseRule::LargerHands),
+    MenuButton::Rule(HouseRule::WrapAround),
+    MenuButton::Back,
+];
+
+// pause and resume on pressing escape, which also backs out of the settings
+// and house rules
+pub fn toggle_pause(
+    keyboard_input: Res<Input<KeyCode>>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut page: ResMut<PausePage>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::Escape) {
+        return;
+    }
+    match state.get() {
+        GameState::Playing => next_state.set(GameState::Paused),
+        GameState::Paused if *page != PausePage::Main => *page = PausePage::Main,
+        GameState::Paused => next_state.set(GameState::Playing),
+        GameState::Setup | GameState::Photo | GameState::Editor | GameState::GameOver => {}
+    }
+}
+
+pub fn spawn_pause_menu(mut commands: Commands) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                background_color: Color::BLACK.with_a(0.5).into(),
+                ..default()
+            },
+            // so the board underneath can't be clicked through the menu
+            Interaction::default(),
+            PauseMenu,
+        ))
+        .with_children(|menu| {
+            for (menu_page, buttons) in [
+                (PausePage::Main, &MAIN_PAGE[..]),
+                (PausePage::Settings, &SETTINGS_PAGE[..]),
+                (PausePage::HouseRules, &HOUSE_RULES_PAGE[..]),
+            ] {
+                menu.spawn((
+                    NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Column,
+                            row_gap: Val::Px(8.0),
+                            ..default()
+                        },
+                        ..default()
+                    },
+                    MenuPage(menu_page),
+                ))
+                .with_children(|page| {
+                    for button in buttons {
+                        spawn_button(page, *button);
+                    }
+                });
+            }
+        });
+}
+
+// A button labelled by update_menu_labels and handled by press_menu_buttons,
+// press_settings_buttons or press_house_rules_buttons.
+pub fn spawn_button(parent: &mut ChildBuilder, button: MenuButton) {
+    parent
+        .spawn((
+            ButtonBundle {
+                style: Style {
+                    min_width: Val::Px(240.0),
+                    padding: UiRect::all(Val::Px(8.0)),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                background_color: BUTTON_COLOR.into(),
+                ..default()
+            },
+            button,
+        ))
+        .with_children(|button| {
+            button.spawn(TextBundle::from_section(
+                "",
+                TextStyle {
+                    font_size: 24.0,
+                    color: Color::ORANGE,
+                    ..default()
+                },
+            ));
+        });
+}
+
+pub fn despawn_pause_menu(
+    mut commands: Commands,
+    mut page: ResMut<PausePage>,
+    menu_q: Query<Entity, With<PauseMenu>>,
+) {
+    for menu in menu_q.iter() {
+        commands.entity(menu).despawn_recursive();
+    }
+    *page = PausePage::Main;
+}
+
+pub fn show_menu_page(page: Res<PausePage>, mut pages_q: Query<(&MenuPage, &mut Style)>) {
+    for (menu_page, mut style) in pages_q.iter_mut() {
+        style.display = if menu_page.0 == *page {
+            Display::Flex
+        } else {
+            Display::None
+        };
+    }
+}
+
+type MenuButtonFilter = (Changed<Interaction>, With<MenuButton>);
+
+pub fn highlight_buttons(
+    mut buttons_q: Query<(&Interaction, &mut BackgroundColor), MenuButtonFilter>,
+) {
+    for (interaction, mut background) in buttons_q.iter_mut() {
+        background.0 = match interaction {
+            Interaction::None => BUTTON_COLOR,
+            _ => HOVERED_BUTTON_COLOR,
+        };
+    }
+}
+
+pub fn press_menu_buttons(
+    buttons_q: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut page: ResMut<PausePage>,
+    (mut seed, mut assistant): (ResMut<Seed>, ResMut<Assistant>),
+    mut exit_events: EventWriter<AppExit>,
+) {
+    for (interaction, button) in buttons_q.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match button {
+            MenuButton::Resume => next_state.set(GameState::Playing),
+            MenuButton::Settings => *page = PausePage::Settings,
+            MenuButton::HouseRules => *page = PausePage::HouseRules,
+            MenuButton::Back => *page = PausePage::Main,
+            MenuButton::Restart | MenuButton::Rematch => next_state.set(GameState::Setup),
+            MenuButton::NewGame => {
+                *seed = Seed::random();
+                next_state.set(GameState::Setup);
+            }
+            MenuButton::Quit => exit_events.send(AppExit),
+            MenuButton::Ease => assistant.accepted = !assistant.accepted,
+            _ => {}
+        }
+    }
+}
+
+pub fn press_settings_buttons(
+    buttons_q: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+    mut palette: ResMut<Palette>,
+    mut pack: ResMut<TilePack>,
+    mut strings: ResMut<Strings>,
+    (mut announcer, mut juice): (ResMut<Announcer>, ResMut<Juice>),
+    mut assistant: ResMut<Assistant>,
+) {
+    for (interaction, button) in buttons_q.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let switched = match button {
+            MenuButton::Palette => {
+                palette.kind = palette.kind.next();
+                Ok(())
+            }
+            MenuButton::Pack => pack.next().map(|next| *pack = next),
+            MenuButton::Language => strings.next().map(|next| *strings = next),
+            MenuButton::Announcements => {
+                announcer.enabled = !announcer.enabled;
+                Ok(())
+            }
+            MenuButton::ScreenEffects => {
+                juice.level = juice.level.next();
+                Ok(())
+            }
+            MenuButton::Assistant => {
+                assistant.enabled = !assistant.enabled;
+                Ok(())
+            }
+            _ => Ok(()),
+        };
+        if let Err(err) = switched {
+            warn!("{}", err);
+        }
+    }
+}
+
+// House rules take hold as soon as they're switched, and are kept for the
+// games after unless a daily challenge or a replay has set the rules.
+pub fn press_house_rules_buttons(
+    buttons_q: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+    mut rules: ResMut<RuleSet>,
+    (daily, watching): (Res<Daily>, Res<Watching>),
+) {
+    let forced = daily.challenge.is_some() || watching.is_replay();
+    for (interaction, button) in buttons_q.iter() {
+        let MenuButton::Rule(rule) = button else {
+            continue;
+        };
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let on = !rules.get(*rule);
+        rules.set(*rule, on);
+        if forced {
+            continue;
+        }
+        if let Err(err) = rules.save() {
+            warn!("{}", err);
+        }
+    }
+}
+
+pub fn update_menu_labels(
+    strings: Res<Strings>,
+    (palette, pack, rules): (Res<Palette>, Res<TilePack>, Res<RuleSet>),
+    (announcer, juice, assistant): (Res<Announcer>, Res<Juice>, Res<Assistant>),
+    buttons_q: Query<(&MenuButton, &Children)>,
+    mut text_q: Query<&mut Text>,
+) {
+    for (button, children) in buttons_q.iter() {
+        let label = match button {
+            MenuButton::Resume => strings.get("menu-resume"),
+            MenuButton::Settings => strings.get("menu-settings"),
+            MenuButton::HouseRules => strings.get("menu-house-rules"),
+            MenuButton::Restart => strings.get("menu-restart"),
+            MenuButton::NewGame => strings.get("menu-new-game"),
+            MenuButton::Quit => strings.get("menu-quit"),
+            MenuButton::Rematch => strings.get("menu-rematch"),
+            MenuButton::Ease if assistant.accepted => strings.get("menu-ease-on"),
+            MenuButton::Ease => strings.get("menu-ease-off"),
+            MenuButton::Palette => strings.format(
+                "menu-palette",
+                &[("palette", palette.kind.name().to_string())],
+            ),
+            MenuButton::Pack => strings.format("menu-pack", &[("pack", pack.name.clone())]),
+            MenuButton::Language => {
+                strings.format("menu-language", &[("lang", strings.lang.clone())])
+            }
+            MenuButton::Announcements if announcer.enabled => strings.get("menu-announcements-on"),
+            MenuButton::Announcements => strings.get("menu-announcements-off"),
+            MenuButton::ScreenEffects => strings.format(
+                "menu-screen-effects",
+                &[(
+                    "level",
+                    strings.get(&format!("juice-{}", juice.level.name())),
+                )],
+            ),
+            MenuButton::Assistant if assistant.enabled => strings.get("menu-assistant-on"),
+            MenuButton::Assistant => strings.get("menu-assistant-off"),
+            MenuButton::Back => strings.get("menu-back"),
+            MenuButton::Rule(rule) => {
+                let name = strings.get(&format!("rule-{}", rule.name()));
+                let key = if rules.get(*rule) {
+                    "menu-rule-on"
+                } else {
+                    "menu-rule-off"
+                };
+                strings.format(key, &[("rule", name)])
+            }
+        };
+        for child in children.iter() {
+            let Ok(mut text) = text_q.get_mut(*child) else {
+                continue;
+            };
+            if text.sections[0].value != label {
+                text.sections[0].value = label.clone();
+            }
+        }
+    }
+}
@@ -0,0 +1,569 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use bevy_ecs_tilemap::prelude::*;
+
+use crate::board::{
+    lit_neighbors, neighbors, tile_center, HighlightedLabel, HoverState, Occupants, PlayerStart,
+    TileType,
+};
+use crate::floor::{Floor, ShownFloor};
+use crate::gesture::Gesture;
+use crate::inspect::BoardInspector;
+use crate::path::Paths;
+use crate::rules::{Bot, Player, Turn, TurnBudget};
+use crate::tiles::{Passages, TileKind};
+
+const PAWN_COLORS: [Color; 4] = [
+    Color::TOMATO,
+    Color::TURQUOISE,
+    Color::VIOLET,
+    Color::YELLOW_GREEN,
+];
+const PAWN_RADIUS: f32 = 0.15;
+// Size of the ring around a pawn a key can be handed to, against the pawn's.
+const RECIPIENT_RING: f32 = 1.4;
+// Size of the marks on the tiles a pawn can walk to, against a tile's.
+const REACHABLE_MARK: f32 = 0.3;
+// Seconds a walking pawn takes over each step.
+const WALK_STEP_SECONDS: f32 = 0.2;
+
+// Where a player's pawn stands on the board.
+#[derive(Component)]
+pub struct Pawn {
+    pub pos: TilePos,
+    pub floor: u32,
+}
+
+// Keys a player is carrying.
+#[derive(Component, Default)]
+pub struct Keys(pub u32);
+
+// While the move overlay is on, the tiles the current player's pawn can walk
+// to with the actions left this turn are marked, and clicking one or pressing
+// enter on it walks the pawn there a step at a time.
+#[derive(Resource, Default)]
+pub struct MoveMode {
+    pub on: bool,
+    // Who's walking and the tiles still to step into, next first.
+    walk: Option<(usize, Vec<TilePos>)>,
+    // Seconds until the next step.
+    step_timer: f32,
+}
+
+// A request to walk a player's pawn into a neighboring tile.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct MovePawn {
+    pub player: usize,
+    pub to: TilePos,
+}
+
+// A player's pawn picked up the key from a key tile.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct KeyPickedUp {
+    pub player: usize,
+}
+
+// A request to hand one of a player's keys to the pawn at `to`.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct PassKey {
+    pub player: usize,
+    pub to: TilePos,
+}
+
+// A key went from one player's pawn to another's.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct KeyPassed {
+    pub from: usize,
+    pub to: usize,
+}
+
+// A player's pawn carried a key out through a gate, ending the game.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct Escaped {
+    pub player: usize,
+}
+
+// Pawns start on the player starts in turn, or in the middle of the board
+// when there are none, always on the ground floor.
+pub fn spawn_pawns(
+    mut commands: Commands,
+    inspector: BoardInspector,
+    players_q: Query<(Entity, &Player)>,
+    starts_q: Query<(&TilePos, &Floor), With<PlayerStart>>,
+) {
+    let mut starts: Vec<TilePos> = starts_q
+        .iter()
+        .filter(|(_, floor)| floor.0 == 0)
+        .map(|(pos, _)| *pos)
+        .collect();
+    starts.sort_by_key(|pos| (pos.y, pos.x));
+    let size = inspector.board_config().size;
+
+    for (player_entity, player) in players_q.iter() {
+        let pos = start_pos(&starts, &size, player.number);
+        commands
+            .entity(player_entity)
+            .insert((Pawn { pos, floor: 0 }, Keys::default()));
+    }
+}
+
+// The floor `player`'s pawn is on, where the tiles they place go down and
+// where anything their placements set off happens.
+pub fn pawn_floor<'a>(
+    pawns: impl IntoIterator<Item = (&'a Player, &'a Pawn)>,
+    player: usize,
+) -> u32 {
+    pawns
+        .into_iter()
+        .find(|(seat, _)| seat.number == player)
+        .map_or(0, |(_, pawn)| pawn.floor)
+}
+
+// Pawns can only walk along corridors joining two placed tiles on their floor.
+pub fn can_move(inspector: &BoardInspector, floor: u32, from: &TilePos, to: &TilePos) -> bool {
+    let board_config = inspector.board_config();
+    can_move_between(
+        from,
+        to,
+        &board_config.size,
+        &board_config.map_type(),
+        inspector.wraps(),
+        |pos| {
+            inspector
+                .tile_info_on(floor, pos)
+                .filter(|info| info.kind != TileKind::Empty)
+                .map(|info| info.exits)
+        },
+    )
+}
+
+// Pawns walk between neighboring placed tiles whose passages meet.
+// `passages_at` gives the passages of placed tiles only.
+pub fn can_move_between(
+    from: &TilePos,
+    to: &TilePos,
+    map_size: &TilemapSize,
+    map_type: &TilemapType,
+    wrap: bool,
+    passages_at: impl Fn(&TilePos) -> Option<Passages>,
+) -> bool {
+    let (Some(from_passages), Some(_)) = (passages_at(from), passages_at(to)) else {
+        return false;
+    };
+    lit_neighbors(from, from_passages, map_size, map_type, wrap, passages_at).contains(to)
+}
+
+// Where a pawn on `floor` can walk from `from` in at most `steps` steps, and
+// the way there.
+pub fn walkable(inspector: &BoardInspector, floor: u32, from: &TilePos, steps: u32) -> Paths {
+    let board_config = inspector.board_config();
+    let map_type = board_config.map_type();
+    let passages_at = |pos: &TilePos| {
+        inspector
+            .tile_info_on(floor, pos)
+            .filter(|info| info.kind != TileKind::Empty)
+            .map(|info| info.exits)
+    };
+    Paths::search([*from], steps, |pos| {
+        let Some(passages) = passages_at(pos) else {
+            return Vec::new();
+        };
+        let lit = lit_neighbors(
+            pos,
+            passages,
+            &board_config.size,
+            &map_type,
+            inspector.wraps(),
+            passages_at,
+        );
+        lit.into_iter()
+            .filter(|to| passages_at(to).is_some())
+            .collect()
+    })
+}
+
+// The players standing on each cell, by floor and position, lowest first.
+pub fn occupants<'a>(
+    pawns: impl IntoIterator<Item = (&'a Player, &'a Pawn)>,
+) -> HashMap<(u32, TilePos), Vec<usize>> {
+    let mut standing: HashMap<(u32, TilePos), Vec<usize>> = HashMap::new();
+    for (player, pawn) in pawns {
+        standing
+            .entry((pawn.floor, pawn.pos))
+            .or_default()
+            .push(player.number);
+    }
+    for players in standing.values_mut() {
+        players.sort_unstable();
+    }
+    standing
+}
+
+// Keeps every cell's occupants up to date with where the pawns stand, so the
+// board can be inspected without reading the pawns.
+pub fn mark_occupants(
+    pawns_q: Query<(&Player, &Pawn)>,
+    mut tiles_q: Query<(&TilePos, &Floor, &mut Occupants)>,
+) {
+    let standing = occupants(pawns_q.iter());
+    for (pos, floor, mut occupants) in tiles_q.iter_mut() {
+        let here = standing
+            .get(&(floor.0, *pos))
+            .map_or(&[][..], Vec::as_slice);
+        if occupants.0 != here {
+            occupants.0 = here.to_vec();
+        }
+    }
+}
+
+// Where a player's pawn starts, given the board's player starts in order.
+pub fn start_pos(starts: &[TilePos], map_size: &TilemapSize, player: usize) -> TilePos {
+    match starts.len() {
+        0 => TilePos {
+            x: map_size.x / 2,
+            y: map_size.y / 2,
+        },
+        count => starts[player % count],
+    }
+}
+
+// A pawn escapes by carrying a key through a gate.
+pub fn escapes(tile: TileKind, keys: u32) -> bool {
+    tile == TileKind::Gate && keys > 0
+}
+
+type MovedPawnFilter = Or<(Changed<Pawn>, Changed<Keys>)>;
+
+pub fn detect_escapes(
+    inspector: BoardInspector,
+    pawns_q: Query<(&Player, &Pawn, &Keys), MovedPawnFilter>,
+    mut escaped_events: EventWriter<Escaped>,
+) {
+    for (player, pawn, keys) in pawns_q.iter() {
+        if inspector
+            .tile_info_on(pawn.floor, &pawn.pos)
+            .is_some_and(|info| escapes(info.kind, keys.0))
+        {
+            escaped_events.send(Escaped {
+                player: player.number,
+            });
+        }
+    }
+}
+
+// switch the move overlay on and off on pressing m
+pub fn pawn_input(
+    keyboard_input: Res<Input<KeyCode>>,
+    turn: Res<Turn>,
+    bots_q: Query<&Player, With<Bot>>,
+    mut move_mode: ResMut<MoveMode>,
+) {
+    if keyboard_input.just_pressed(KeyCode::M) && !turn.is_bot(&bots_q) {
+        move_mode.on = !move_mode.on;
+    }
+}
+
+// walk to the hovered tile when it's clicked or enter is pressed on it while
+// the move overlay is on, which goes off again as the pawn sets out
+pub fn walk_input(
+    (keyboard_input, hover): (Res<Input<KeyCode>>, Res<HoverState>),
+    mut gesture_events: EventReader<Gesture>,
+    turn: Res<Turn>,
+    inspector: BoardInspector,
+    pawns_q: Query<(&Player, &Pawn, &TurnBudget)>,
+    mut move_mode: ResMut<MoveMode>,
+) {
+    let clicked = gesture_events
+        .read()
+        .any(|gesture| *gesture == Gesture::Click(MouseButton::Left));
+    if !move_mode.on || !(clicked || keyboard_input.just_pressed(KeyCode::Return)) {
+        return;
+    }
+    let Some(to) = hover.hovered else {
+        return;
+    };
+    for (player, pawn, budget) in pawns_q.iter() {
+        if player.number != turn.player || pawn.floor != hover.floor {
+            continue;
+        }
+        let paths = walkable(&inspector, pawn.floor, &pawn.pos, budget.remaining);
+        if let Some(path) = paths.path_to(&to).filter(|path| !path.is_empty()) {
+            move_mode.on = false;
+            move_mode.walk = Some((player.number, path));
+            move_mode.step_timer = 0.0;
+        }
+    }
+}
+
+// Walks take a step every so often, each one an ordinary move paid for with
+// an action. A walk ends early if the turn passes, and the overlay goes off.
+pub fn walk_pawns(
+    time: Res<Time>,
+    turn: Res<Turn>,
+    mut move_mode: ResMut<MoveMode>,
+    mut move_events: EventWriter<MovePawn>,
+) {
+    if turn.is_changed() {
+        move_mode.on = false;
+        move_mode.walk = None;
+    }
+    let move_mode = &mut *move_mode;
+    let Some((player, path)) = &mut move_mode.walk else {
+        return;
+    };
+    move_mode.step_timer -= time.delta_seconds();
+    if move_mode.step_timer > 0.0 {
+        return;
+    }
+    move_mode.step_timer = WALK_STEP_SECONDS;
+    move_events.send(MovePawn {
+        player: *player,
+        to: path.remove(0),
+    });
+    if path.is_empty() {
+        move_mode.walk = None;
+    }
+}
+
+pub fn move_pawns(
+    mut move_events: EventReader<MovePawn>,
+    inspector: BoardInspector,
+    mut pawns_q: Query<(&Player, &mut Pawn, &mut TurnBudget)>,
+) {
+    for move_pawn in move_events.read() {
+        let Some((_, mut pawn, mut budget)) = pawns_q
+            .iter_mut()
+            .find(|(player, ..)| player.number == move_pawn.player)
+        else {
+            continue;
+        };
+        if can_move(&inspector, pawn.floor, &pawn.pos, &move_pawn.to) && budget.spend() {
+            pawn.pos = move_pawn.to;
+        }
+    }
+}
+
+// hand a key to the pawn on the hovered tile on pressing j
+pub fn pass_key_input(
+    keyboard_input: Res<Input<KeyCode>>,
+    turn: Res<Turn>,
+    bots_q: Query<&Player, With<Bot>>,
+    highlighted_tiles_q: Query<&TilePos, With<HighlightedLabel>>,
+    mut pass_events: EventWriter<PassKey>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::J) || turn.is_bot(&bots_q) {
+        return;
+    }
+    for tile_pos in highlighted_tiles_q.iter() {
+        pass_events.send(PassKey {
+            player: turn.player,
+            to: *tile_pos,
+        });
+    }
+}
+
+// Who a player's key would go to when handed to the pawn at `to`, if anyone:
+// a pawn on a neighboring tile joined to the giver's by a corridor, as if it
+// were to be walked. With several pawns there, the first player's takes it.
+pub fn key_recipient<'a>(
+    inspector: &BoardInspector,
+    (giver, giver_pawn, keys): (usize, &Pawn, u32),
+    pawns: impl IntoIterator<Item = (usize, &'a Pawn)>,
+    to: &TilePos,
+) -> Option<usize> {
+    if keys == 0 || !can_move(inspector, giver_pawn.floor, &giver_pawn.pos, to) {
+        return None;
+    }
+    pawns
+        .into_iter()
+        .filter(|(player, pawn)| {
+            *player != giver && pawn.floor == giver_pawn.floor && pawn.pos == *to
+        })
+        .map(|(player, _)| player)
+        .min()
+}
+
+// Handing a key over costs the giver an action.
+pub fn pass_keys(
+    mut pass_events: EventReader<PassKey>,
+    inspector: BoardInspector,
+    mut pawns_q: Query<(&Player, &Pawn, &mut Keys, &mut TurnBudget)>,
+    mut passed_events: EventWriter<KeyPassed>,
+) {
+    for pass in pass_events.read() {
+        let pawns = pawns_q
+            .iter()
+            .map(|(player, pawn, ..)| (player.number, pawn));
+        let Some(recipient) = pawns_q
+            .iter()
+            .find(|(player, ..)| player.number == pass.player)
+            .and_then(|(_, pawn, keys, _)| {
+                key_recipient(&inspector, (pass.player, pawn, keys.0), pawns, &pass.to)
+            })
+        else {
+            continue;
+        };
+        let mut paid = false;
+        for (player, _, mut keys, mut budget) in pawns_q.iter_mut() {
+            if player.number == pass.player && budget.spend() {
+                keys.0 -= 1;
+                paid = true;
+            }
+        }
+        if !paid {
+            continue;
+        }
+        for (player, _, mut keys, _) in pawns_q.iter_mut() {
+            if player.number == recipient {
+                keys.0 += 1;
+            }
+        }
+        passed_events.send(KeyPassed {
+            from: pass.player,
+            to: recipient,
+        });
+    }
+}
+
+// Pawns pick up the key of a lit key tile they stand on, including one whose
+// corridor is only joined up after they got there.
+pub fn pick_up_keys(
+    mut commands: Commands,
+    inspector: BoardInspector,
+    mut pawns_q: Query<(&Player, &Pawn, &mut Keys)>,
+    mut picked_up_events: EventWriter<KeyPickedUp>,
+) {
+    let board_config = inspector.board_config();
+    let map_type = board_config.map_type();
+    for (player, pawn, mut keys) in pawns_q.iter_mut() {
+        let Some(info) = inspector.tile_info_on(pawn.floor, &pawn.pos) else {
+            continue;
+        };
+        let passages_at = |pos: &TilePos| {
+            inspector
+                .tile_info_on(pawn.floor, pos)
+                .filter(|info| info.kind != TileKind::Empty)
+                .map(|info| info.exits)
+        };
+        if info.kind != TileKind::Key
+            || !key_lit(
+                &pawn.pos,
+                &board_config.size,
+                &map_type,
+                inspector.wraps(),
+                passages_at,
+            )
+        {
+            continue;
+        }
+        keys.0 += 1;
+        picked_up_events.send(KeyPickedUp {
+            player: player.number,
+        });
+        // without its key the tile is an ordinary dead end
+        commands.entity(info.entity).insert(TileType {
+            kind: TileKind::DeadEnd,
+        });
+    }
+}
+
+// A key tile is lit once its corridor joins up with another placed tile's,
+// letting the light along it. Until then its key can't be found in the dark.
+// `passages_at` gives the passages of placed tiles only.
+pub fn key_lit(
+    pos: &TilePos,
+    map_size: &TilemapSize,
+    map_type: &TilemapType,
+    wrap: bool,
+    passages_at: impl Fn(&TilePos) -> Option<Passages>,
+) -> bool {
+    neighbors(pos, map_size, map_type, wrap)
+        .iter()
+        .any(|neighbor| can_move_between(pos, neighbor, map_size, map_type, wrap, &passages_at))
+}
+
+pub fn pawn_color(player: &Player) -> Color {
+    PAWN_COLORS[player.number % PAWN_COLORS.len()]
+}
+
+// Pawns are drawn as rings on their tile, with the keys they carry beside them.
+// Only the pawns on the floor on show are drawn. While the player whose turn
+// it is carries a key, the pawns it could be handed to get a gold ring too.
+pub fn draw_pawns(
+    mut gizmos: Gizmos,
+    turn: Res<Turn>,
+    inspector: BoardInspector,
+    tilemap_q: Query<(&TilemapGridSize, &TilemapType, &Transform), With<ShownFloor>>,
+    pawns_q: Query<(&Player, &Pawn, &Keys)>,
+) {
+    let mut recipients = Vec::new();
+    if let Some((_, giver, keys)) = pawns_q
+        .iter()
+        .find(|(player, ..)| player.number == turn.player)
+    {
+        for (_, pawn, _) in pawns_q.iter() {
+            let pawns = pawns_q
+                .iter()
+                .map(|(player, pawn, _)| (player.number, pawn));
+            recipients.extend(key_recipient(
+                &inspector,
+                (turn.player, giver, keys.0),
+                pawns,
+                &pawn.pos,
+            ));
+        }
+    }
+
+    for (grid_size, map_type, map_transform) in tilemap_q.iter() {
+        let radius = grid_size.x * map_transform.scale.x * PAWN_RADIUS;
+        for (player, pawn, keys) in pawns_q.iter() {
+            if pawn.floor != inspector.floor() {
+                continue;
+            }
+            // spread pawns sharing a tile around its center
+            let angle = player.number as f32 * std::f32::consts::FRAC_PI_2;
+            let center = tile_center(&pawn.pos, grid_size, map_type, map_transform)
+                + Vec2::from_angle(angle) * radius;
+            gizmos.circle_2d(center, radius, pawn_color(player));
+            if recipients.contains(&player.number) {
+                gizmos.circle_2d(center, radius * RECIPIENT_RING, Color::GOLD);
+            }
+            for key in 0..keys.0 {
+                let offset = Vec2::new(radius * (1.5 + key as f32 * 0.6), radius);
+                gizmos.circle_2d(center + offset, radius / 4.0, Color::GOLD);
+            }
+        }
+    }
+}
+
+// The move overlay marks each tile the current player's pawn can reach in its
+// color, while it's on and the pawn's floor is on show.
+pub fn draw_reachable(
+    mut gizmos: Gizmos,
+    (turn, move_mode): (Res<Turn>, Res<MoveMode>),
+    inspector: BoardInspector,
+    tilemap_q: Query<(&TilemapGridSize, &TilemapType, &Transform), With<ShownFloor>>,
+    pawns_q: Query<(&Player, &Pawn, &TurnBudget)>,
+) {
+    if !move_mode.on {
+        return;
+    }
+    let Some((player, pawn, budget)) = pawns_q
+        .iter()
+        .find(|(player, ..)| player.number == turn.player)
+    else {
+        return;
+    };
+    if pawn.floor != inspector.floor() {
+        return;
+    }
+    let paths = walkable(&inspector, pawn.floor, &pawn.pos, budget.remaining);
+    for (grid_size, map_type, map_transform) in tilemap_q.iter() {
+        let radius = grid_size.x * map_transform.scale.x * REACHABLE_MARK;
+        for pos in paths.reached().filter(|pos| **pos != pawn.pos) {
+            let center = tile_center(pos, grid_size, map_type, map_transform);
+            gizmos.circle_2d(center, radius, pawn_color(player).with_a(0.6));
+        }
+    }
+}
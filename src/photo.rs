@@ -0,0 +1,285 @@
+use std::path::Path;
+
+use bevy::{
+    input::mouse::MouseWheel, prelude::*, render::view::screenshot::ScreenshotManager,
+    window::PrimaryWindow,
+};
+
+use crate::camera::MainView;
+use crate::state::GameState;
+
+const PAN_SPEED: f32 = 250.0;
+const ZOOM_STEP: f32 = 0.02;
+const MIN_ZOOM: f32 = 0.1;
+const MAX_ZOOM: f32 = 4.0;
+const FRAME_WIDTH: f32 = 32.0;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PhotoFilter {
+    #[default]
+    None,
+    Sepia,
+    Noir,
+    Moonlight,
+}
+
+impl PhotoFilter {
+    fn next(self) -> PhotoFilter {
+        match self {
+            PhotoFilter::None => PhotoFilter::Sepia,
+            PhotoFilter::Sepia => PhotoFilter::Noir,
+            PhotoFilter::Noir => PhotoFilter::Moonlight,
+            PhotoFilter::Moonlight => PhotoFilter::None,
+        }
+    }
+
+    // Filters are a translucent wash laid over the whole window.
+    fn overlay_color(self) -> Color {
+        match self {
+            PhotoFilter::None => Color::NONE,
+            PhotoFilter::Sepia => Color::rgba(0.44, 0.26, 0.08, 0.3),
+            PhotoFilter::Noir => Color::rgba(0.05, 0.05, 0.05, 0.45),
+            PhotoFilter::Moonlight => Color::rgba(0.1, 0.2, 0.45, 0.3),
+        }
+    }
+}
+
+// Photo mode settings are kept between visits so the same look can be reused.
+#[derive(Resource, Default)]
+pub struct PhotoMode {
+    pub filter: PhotoFilter,
+    pub frame: bool,
+    shots: u32,
+    // The gameplay view to return to when leaving photo mode.
+    saved_view: Option<(Vec3, f32)>,
+    // The state photo mode was entered from, and is left back to.
+    return_to: GameState,
+    // Interface hidden for the shot, with how it was shown before.
+    hidden: Vec<(Entity, Visibility)>,
+}
+
+#[derive(Component)]
+pub struct PhotoOverlay;
+
+#[derive(Component)]
+pub struct PhotoFilterOverlay;
+
+#[derive(Component)]
+pub struct PhotoFrameOverlay;
+
+// toggle photo mode on pressing p
+pub fn toggle_photo_mode(
+    keyboard_input: Res<Input<KeyCode>>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut photo_mode: ResMut<PhotoMode>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::P) {
+        return;
+    }
+    match state.get() {
+        GameState::Photo => next_state.set(photo_mode.return_to),
+        GameState::Playing | GameState::Editor => {
+            photo_mode.return_to = *state.get();
+            next_state.set(GameState::Photo);
+        }
+        GameState::Setup | GameState::Paused | GameState::GameOver => {}
+    }
+}
+
+pub fn enter_photo_mode(
+    mut commands: Commands,
+    mut photo_mode: ResMut<PhotoMode>,
+    camera_q: Query<(&Transform, &OrthographicProjection), With<MainView>>,
+) {
+    if let Ok((transform, ortho)) = camera_q.get_single() {
+        photo_mode.saved_view = Some((transform.translation, ortho.scale));
+    }
+
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                ..default()
+            },
+            background_color: photo_mode.filter.overlay_color().into(),
+            ..default()
+        },
+        PhotoOverlay,
+        PhotoFilterOverlay,
+    ));
+
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                border: UiRect::all(Val::Px(FRAME_WIDTH)),
+                ..default()
+            },
+            border_color: Color::hex("0B0A08").unwrap().into(),
+            visibility: frame_visibility(photo_mode.frame),
+            ..default()
+        },
+        PhotoOverlay,
+        PhotoFrameOverlay,
+    ));
+}
+
+type InterfaceFilter = (With<Node>, Without<Parent>, Without<PhotoOverlay>);
+
+// Keep the HUD, hand, minimap, log, toasts and the rest of the interface out
+// of the shot, including anything that pops up while photo mode is open.
+pub fn hide_interface(
+    mut photo_mode: ResMut<PhotoMode>,
+    mut interface_q: Query<(Entity, &mut Visibility), InterfaceFilter>,
+) {
+    for (entity, mut visibility) in interface_q.iter_mut() {
+        if *visibility == Visibility::Hidden {
+            continue;
+        }
+        if !photo_mode
+            .hidden
+            .iter()
+            .any(|(hidden, _)| *hidden == entity)
+        {
+            photo_mode.hidden.push((entity, *visibility));
+        }
+        *visibility = Visibility::Hidden;
+    }
+}
+
+pub fn exit_photo_mode(
+    mut commands: Commands,
+    mut photo_mode: ResMut<PhotoMode>,
+    overlays_q: Query<Entity, With<PhotoOverlay>>,
+    mut interface_q: Query<&mut Visibility, InterfaceFilter>,
+    mut camera_q: Query<(&mut Transform, &mut OrthographicProjection), With<MainView>>,
+) {
+    for overlay_entity in overlays_q.iter() {
+        commands.entity(overlay_entity).despawn_recursive();
+    }
+
+    for (entity, shown) in photo_mode.hidden.drain(..) {
+        if let Ok(mut visibility) = interface_q.get_mut(entity) {
+            *visibility = shown;
+        }
+    }
+
+    if let Some((translation, scale)) = photo_mode.saved_view.take() {
+        for (mut transform, mut ortho) in camera_q.iter_mut() {
+            transform.translation = translation;
+            ortho.scale = scale;
+        }
+    }
+}
+
+// Pan and zoom with finer control than the gameplay camera, and without its limits.
+pub fn free_camera(
+    time: Res<Time>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut mouse_wheel_events: EventReader<MouseWheel>,
+    mut camera_q: Query<(&mut Transform, &mut OrthographicProjection), With<MainView>>,
+) {
+    let scroll: f32 = mouse_wheel_events.read().map(|wheel| wheel.y).sum();
+
+    for (mut transform, mut ortho) in camera_q.iter_mut() {
+        let mut direction = Vec3::ZERO;
+        if keyboard_input.pressed(KeyCode::A) {
+            direction -= Vec3::X;
+        }
+        if keyboard_input.pressed(KeyCode::D) {
+            direction += Vec3::X;
+        }
+        if keyboard_input.pressed(KeyCode::W) {
+            direction += Vec3::Y;
+        }
+        if keyboard_input.pressed(KeyCode::S) {
+            direction -= Vec3::Y;
+        }
+
+        let mut zoom = -scroll;
+        if keyboard_input.pressed(KeyCode::Z) {
+            zoom += 1.0;
+        }
+        if keyboard_input.pressed(KeyCode::X) {
+            zoom -= 1.0;
+        }
+        ortho.scale = (ortho.scale + zoom * ZOOM_STEP).clamp(MIN_ZOOM, MAX_ZOOM);
+
+        // Pan in screen space so movement feels the same at every zoom level.
+        let z = transform.translation.z;
+        transform.translation += time.delta_seconds() * direction * PAN_SPEED * ortho.scale;
+        transform.translation.z = z;
+    }
+}
+
+// cycle the colour filter on pressing f
+pub fn cycle_filter(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut photo_mode: ResMut<PhotoMode>,
+    mut filter_q: Query<&mut BackgroundColor, With<PhotoFilterOverlay>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::F) {
+        photo_mode.filter = photo_mode.filter.next();
+        for mut background_color in filter_q.iter_mut() {
+            *background_color = photo_mode.filter.overlay_color().into();
+        }
+    }
+}
+
+// toggle the frame overlay on pressing g
+pub fn toggle_frame(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut photo_mode: ResMut<PhotoMode>,
+    mut frame_q: Query<&mut Visibility, With<PhotoFrameOverlay>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::G) {
+        photo_mode.frame = !photo_mode.frame;
+        for mut visibility in frame_q.iter_mut() {
+            *visibility = frame_visibility(photo_mode.frame);
+        }
+    }
+}
+
+// save a screenshot of the window, filter and frame included, on pressing c
+pub fn capture(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut photo_mode: ResMut<PhotoMode>,
+    mut screenshot_manager: ResMut<ScreenshotManager>,
+    window_q: Query<Entity, With<PrimaryWindow>>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::C) {
+        return;
+    }
+    let Ok(window_entity) = window_q.get_single() else {
+        return;
+    };
+
+    // Don't overwrite shots from earlier sessions.
+    let path = loop {
+        photo_mode.shots += 1;
+        let path = format!("nightcage-photo-{}.png", photo_mode.shots);
+        if !Path::new(&path).exists() {
+            break path;
+        }
+    };
+
+    if screenshot_manager
+        .save_screenshot_to_disk(window_entity, &path)
+        .is_err()
+    {
+        warn!("a screenshot is already being taken, skipping {}", path);
+    }
+}
+
+fn frame_visibility(frame: bool) -> Visibility {
+    if frame {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    }
+}
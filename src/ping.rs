@@ -0,0 +1,174 @@
+use bevy::prelude::*;
+use bevy_ecs_tilemap::prelude::*;
+
+use crate::board::{tile_center, HighlightedLabel};
+use crate::floor::{CurrentFloor, Floor, ShownFloor};
+use crate::gesture::Gesture;
+use crate::locale::Strings;
+use crate::pawn::pawn_color;
+use crate::rules::{Bot, Player, Turn};
+
+// How long a ping stays on the board, in seconds.
+const PING_SECONDS: f32 = 4.0;
+const PING_RADIUS: f32 = 0.45;
+// Rings a ping sends out over its life.
+const PING_PULSES: f32 = 4.0;
+// Height of a ping's label, against a tile's.
+const LABEL_SIZE: f32 = 0.22;
+
+// What a ping asks of the others, shown beside it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PingLabel {
+    PlaceHere,
+}
+
+impl PingLabel {
+    fn key(self) -> &'static str {
+        match self {
+            PingLabel::PlaceHere => "ping-place-here",
+        }
+    }
+}
+
+// A player pointing out a cell for the others to see. Pings don't touch the
+// rules, so anyone can send one at any time.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct PingTile {
+    pub player: usize,
+    pub floor: u32,
+    pub pos: TilePos,
+    pub label: Option<PingLabel>,
+}
+
+// A ping on the board. It is a child of its floor's tilemap, so it is shown
+// with the floor and cleared away with the board.
+#[derive(Component)]
+pub struct Ping {
+    pub player: usize,
+    pub floor: u32,
+    pub pos: TilePos,
+    timer: Timer,
+}
+
+// ping the hovered tile on alt + left click, asking for a tile to be placed
+// there with shift held too
+pub fn ping_input(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut gesture_events: EventReader<Gesture>,
+    (turn, current_floor): (Res<Turn>, Res<CurrentFloor>),
+    bots_q: Query<&Player, With<Bot>>,
+    highlighted_tiles_q: Query<&TilePos, With<HighlightedLabel>>,
+    mut ping_events: EventWriter<PingTile>,
+) {
+    let alt = keyboard_input.any_pressed([KeyCode::AltLeft, KeyCode::AltRight]);
+    let clicks = gesture_events
+        .read()
+        .filter(|gesture| **gesture == Gesture::Click(MouseButton::Left))
+        .count();
+    if !alt || clicks == 0 || turn.is_bot(&bots_q) {
+        return;
+    }
+    let shift = keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    for tile_pos in highlighted_tiles_q.iter() {
+        ping_events.send(PingTile {
+            player: turn.player,
+            floor: current_floor.0,
+            pos: *tile_pos,
+            label: shift.then_some(PingLabel::PlaceHere),
+        });
+    }
+}
+
+// Each player has one ping on the board at a time, so a new one replaces
+// their last.
+pub fn spawn_pings(
+    mut commands: Commands,
+    strings: Res<Strings>,
+    mut ping_events: EventReader<PingTile>,
+    tilemap_q: Query<(Entity, &Floor, &TilemapGridSize, &TilemapType), With<TileStorage>>,
+    pings_q: Query<(Entity, &Ping)>,
+) {
+    for ping in ping_events.read() {
+        let Some((tilemap_entity, _, grid_size, map_type)) = tilemap_q
+            .iter()
+            .find(|(_, floor, ..)| floor.0 == ping.floor)
+        else {
+            continue;
+        };
+        for (ping_entity, old) in pings_q.iter() {
+            if old.player == ping.player {
+                commands.entity(ping_entity).despawn_recursive();
+            }
+        }
+
+        let label = ping
+            .label
+            .map_or(String::new(), |label| strings.get(label.key()));
+        let center = ping.pos.center_in_world(grid_size, map_type);
+        let ping_entity = commands
+            .spawn((
+                Text2dBundle {
+                    text: Text::from_section(
+                        label,
+                        TextStyle {
+                            font_size: grid_size.y * LABEL_SIZE,
+                            color: pawn_color(&Player {
+                                number: ping.player,
+                            }),
+                            ..default()
+                        },
+                    ),
+                    // above the tile, clear of the pawns on it
+                    transform: Transform::from_translation(
+                        (center + Vec2::new(0.0, grid_size.y * 0.5)).extend(10.0),
+                    ),
+                    ..default()
+                },
+                Ping {
+                    player: ping.player,
+                    floor: ping.floor,
+                    pos: ping.pos,
+                    timer: Timer::from_seconds(PING_SECONDS, TimerMode::Once),
+                },
+            ))
+            .id();
+        commands.entity(tilemap_entity).add_child(ping_entity);
+    }
+}
+
+pub fn expire_pings(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut pings_q: Query<(Entity, &mut Ping)>,
+) {
+    for (ping_entity, mut ping) in pings_q.iter_mut() {
+        if ping.timer.tick(time.delta()).finished() {
+            commands.entity(ping_entity).despawn_recursive();
+        }
+    }
+}
+
+// A ping is drawn in its player's color, as rings spreading out from the cell
+// and fading as they go.
+pub fn draw_pings(
+    mut gizmos: Gizmos,
+    current_floor: Res<CurrentFloor>,
+    tilemap_q: Query<(&TilemapGridSize, &TilemapType, &Transform), With<ShownFloor>>,
+    pings_q: Query<&Ping>,
+) {
+    for (grid_size, map_type, map_transform) in tilemap_q.iter() {
+        let radius = grid_size.x * map_transform.scale.x * PING_RADIUS;
+        for ping in pings_q.iter() {
+            if ping.floor != current_floor.0 {
+                continue;
+            }
+            let color = pawn_color(&Player {
+                number: ping.player,
+            });
+            let center = tile_center(&ping.pos, grid_size, map_type, map_transform);
+            let spread = (ping.timer.percent() * PING_PULSES).fract();
+            gizmos.circle_2d(center, radius * spread, color.with_a(1.0 - spread));
+            gizmos.circle_2d(center, radius * 0.1, color);
+        }
+    }
+}
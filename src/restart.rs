@@ -0,0 +1,56 @@
+use bevy::prelude::*;
+use bevy_ecs_tilemap::prelude::*;
+
+use crate::board::{HoverState, NextTileKind};
+use crate::content::Content;
+use crate::editor::Editor;
+use crate::event_log::EventLog;
+use crate::floor::CurrentFloor;
+use crate::hint::Hint;
+use crate::rng::{GameRng, Seed};
+use crate::rules::{HeldTiles, Player, TileBag, Turn};
+use crate::scenario::ActiveScenario;
+use crate::state::GameState;
+use crate::tutorial::Tutorial;
+
+type GameEntityFilter = Or<(With<TileStorage>, With<TilePos>, With<Player>)>;
+
+// Every game is set up on entering GameState::Setup, the first one included,
+// so starting over is a matter of clearing away the last game and going back
+// there. This clears the board, the players and everything dealt to them, and
+// reseeds the randomness, so the same seed deals the same game again and a
+// fresh one a new game.
+pub fn tear_down(
+    mut commands: Commands,
+    (seed, content, active_scenario): (Res<Seed>, Res<Content>, Res<ActiveScenario>),
+    mut log: ResMut<EventLog>,
+    mut tutorial: ResMut<Tutorial>,
+    mut editor: ResMut<Editor>,
+    game_q: Query<Entity, GameEntityFilter>,
+) {
+    for entity in game_q.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.insert_resource(GameRng::new(*seed));
+    commands.insert_resource(Turn::default());
+    commands.insert_resource(TileBag {
+        extra: content.extra_tiles.clone(),
+        mix: active_scenario
+            .0
+            .as_ref()
+            .map_or(Vec::new(), |scenario| scenario.bag.clone()),
+        ..default()
+    });
+    commands.insert_resource(HeldTiles::default());
+    commands.insert_resource(NextTileKind::default());
+    commands.insert_resource(HoverState::default());
+    commands.insert_resource(CurrentFloor::default());
+    commands.insert_resource(Hint::default());
+    log.clear();
+    tutorial.restart();
+    editor.selection.clear();
+}
+
+pub fn start_playing(mut next_state: ResMut<NextState<GameState>>) {
+    next_state.set(GameState::Playing);
+}
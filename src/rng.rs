@@ -0,0 +1,22 @@
+use bevy::prelude::*;
+
+// The seed a game's randomness is drawn from. Starting two games from the
+// same seed, with the same settings and moves, plays them out identically.
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Seed(pub u64);
+
+impl Seed {
+    pub fn random() -> Seed {
+        Seed(fastrand::u64(..))
+    }
+}
+
+// The one source of randomness for the game.
+#[derive(Resource)]
+pub struct GameRng(pub fastrand::Rng);
+
+impl GameRng {
+    pub fn new(seed: Seed) -> Self {
+        Self(fastrand::Rng::with_seed(seed.0))
+    }
+}
@@ -0,0 +1,471 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+use bevy_ecs_tilemap::prelude::*;
+
+use crate::board::{
+    HexRotation, MonsterSpawn, NextTileKind, PlayerStart, Rotation, TileHistory, TileType,
+};
+use crate::content::Content;
+use crate::difficulty::Difficulty;
+use crate::floor::{Floor, Floors};
+use crate::house_rules::RuleSet;
+use crate::rng::GameRng;
+use crate::scenario::ActiveScenario;
+use crate::tiles::{Quarter, TileKind};
+
+// A seat at the table. Player 0 is the local human.
+#[derive(Component)]
+pub struct Player {
+    pub number: usize,
+}
+
+// A player whose turns are taken by the computer.
+#[derive(Component)]
+pub struct Bot;
+
+#[derive(Resource, Clone, Copy, Debug, Default)]
+pub struct PlayerConfig {
+    pub bots: usize,
+}
+
+pub const ACTIONS_PER_TURN: u32 = 3;
+
+#[derive(Resource, Default)]
+pub struct Turn {
+    // Number of the player whose turn it is.
+    pub player: usize,
+}
+
+impl Turn {
+    pub fn advance(&mut self, players: usize) {
+        self.player = (self.player + 1) % players.max(1);
+    }
+
+    pub fn is_bot<'a>(&self, bots: impl IntoIterator<Item = &'a Player>) -> bool {
+        bots.into_iter().any(|player| player.number == self.player)
+    }
+}
+
+// Actions a player has left this turn. Moving, placing, rotating the tile in
+// hand and passing a key each cost one, and the turn passes on once they are
+// all spent.
+#[derive(Component)]
+pub struct TurnBudget {
+    pub remaining: u32,
+}
+
+impl Default for TurnBudget {
+    fn default() -> Self {
+        Self {
+            remaining: ACTIONS_PER_TURN,
+        }
+    }
+}
+
+impl TurnBudget {
+    // Whether an action could be paid for, taking its cost if so.
+    pub fn spend(&mut self) -> bool {
+        if self.remaining == 0 {
+            return false;
+        }
+        self.remaining -= 1;
+        true
+    }
+}
+
+// The budget of the player whose turn it is.
+#[derive(SystemParam)]
+pub struct CurrentBudget<'w, 's> {
+    turn: Res<'w, Turn>,
+    players_q: Query<'w, 's, (&'static Player, &'static mut TurnBudget)>,
+}
+
+impl<'w, 's> CurrentBudget<'w, 's> {
+    pub fn player(&self) -> usize {
+        self.turn.player
+    }
+
+    // Whether the current player could pay for an action, taking its cost if so.
+    pub fn spend(&mut self) -> bool {
+        let player = self.turn.player;
+        self.players_q
+            .iter_mut()
+            .find(|(seat, _)| seat.number == player)
+            .is_some_and(|(_, mut budget)| budget.spend())
+    }
+
+    // Gives up the rest of the current player's turn.
+    pub fn pass(&mut self) {
+        let player = self.turn.player;
+        for (seat, mut budget) in self.players_q.iter_mut() {
+            if seat.number == player {
+                budget.remaining = 0;
+            }
+        }
+    }
+
+    pub fn grant(&mut self, actions: u32) {
+        let player = self.turn.player;
+        for (seat, mut budget) in self.players_q.iter_mut() {
+            if seat.number == player {
+                budget.remaining += actions;
+            }
+        }
+    }
+}
+
+// A tile put down by whoever's turn it is, on the floor their pawn is on.
+// Human input and bots both go through this so they play by the same rules.
+// Rotation is in quarter turns on square boards and sixth turns on hex boards.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct PlaceTile {
+    pub pos: TilePos,
+    pub floor: u32,
+    pub kind: TileKind,
+    pub rotation: u8,
+}
+
+// A placement that went through, for anything following the game.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct TilePlaced {
+    pub player: usize,
+    pub pos: TilePos,
+    pub floor: u32,
+    pub kind: TileKind,
+    pub rotation: u8,
+}
+
+// A tile that has left the board, taking anything standing on it along.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct TileCollapsed {
+    pub pos: TilePos,
+    pub floor: u32,
+    pub kind: TileKind,
+}
+
+// A tile drawn from the bag into hand.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct TileDrawn {
+    pub kind: TileKind,
+}
+
+// The tile in hand turned over a cell during play and paid for with an
+// action.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct HandTurned {
+    pub pos: TilePos,
+    pub floor: u32,
+}
+
+// The rest of a turn given up, by choice, by a bot with nothing to do or by
+// running out of time.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct TurnPassed {
+    pub player: usize,
+}
+
+// Tiles still to be drawn, in draw order, and the discard pile of tiles that
+// have collapsed off the board or been swapped out of hand, newest last.
+#[derive(Resource, Default)]
+pub struct TileBag {
+    pub tiles: Vec<TileKind>,
+    pub discard: Vec<TileKind>,
+    // Tiles the expansions in play add to every fresh bag.
+    pub extra: Vec<(TileKind, usize)>,
+    // A scenario's own mix for every fresh bag, or empty for the difficulty's.
+    pub mix: Vec<(TileKind, usize)>,
+}
+
+impl TileBag {
+    // Takes the next tile, starting a fresh bag once this one runs out. The
+    // discard pile is shuffled into the fresh bag unless the difficulty keeps
+    // discarded tiles out of the game.
+    pub fn draw(&mut self, difficulty: Difficulty, rng: &mut GameRng) -> TileKind {
+        if self.tiles.is_empty() {
+            let difficulty_mix = difficulty.bag_mix();
+            let mix = if self.mix.is_empty() {
+                &difficulty_mix[..]
+            } else {
+                &self.mix[..]
+            };
+            for (kind, count) in mix {
+                self.tiles.extend(std::iter::repeat_n(*kind, *count));
+            }
+            for (kind, count) in &self.extra {
+                self.tiles.extend(std::iter::repeat_n(*kind, *count));
+            }
+            if difficulty.discard_reshuffles() {
+                self.tiles.append(&mut self.discard);
+            }
+            rng.0.shuffle(&mut self.tiles);
+        }
+        self.tiles.pop().unwrap_or(TileKind::Straight)
+    }
+
+    pub fn discard(&mut self, kind: TileKind) {
+        if kind != TileKind::Empty {
+            self.discard.push(kind);
+        }
+    }
+}
+
+// Tiles drawn into a player's hand ahead of the one to be placed, next up
+// first. A hand only holds more than the one tile under the larger hands
+// house rule.
+#[derive(Resource, Default)]
+pub struct HeldTiles(pub Vec<TileKind>);
+
+impl HeldTiles {
+    // Takes the next tile into hand, first topping the hand up from the bag so
+    // `hand_size` tiles are held in all. Returns the tile taken and every tile
+    // drawn from the bag to get it.
+    pub fn draw(
+        &mut self,
+        hand_size: usize,
+        bag: &mut TileBag,
+        difficulty: Difficulty,
+        rng: &mut GameRng,
+    ) -> (TileKind, Vec<TileKind>) {
+        let mut drawn = Vec::new();
+        while self.0.len() < hand_size.max(1) {
+            let kind = bag.draw(difficulty, rng);
+            self.0.push(kind);
+            drawn.push(kind);
+        }
+        (self.0.remove(0), drawn)
+    }
+
+    // Puts the tile in hand back among the held ones, taking the next of
+    // them up instead. Returns the new tile in hand, or None when nothing
+    // else is held.
+    pub fn swap(&mut self, in_hand: TileKind) -> Option<TileKind> {
+        if self.0.is_empty() {
+            return None;
+        }
+        self.0.push(in_hand);
+        Some(self.0.remove(0))
+    }
+}
+
+// A request to swap the tile in hand for another one held.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct SwapHand;
+
+// Everything involved in handing out tiles, so systems that draw don't need
+// to take each piece separately.
+#[derive(SystemParam)]
+pub struct Dealer<'w> {
+    bag: ResMut<'w, TileBag>,
+    held: ResMut<'w, HeldTiles>,
+    next_tile_kind: ResMut<'w, NextTileKind>,
+    difficulty: Res<'w, Difficulty>,
+    rules: Res<'w, RuleSet>,
+    rng: ResMut<'w, GameRng>,
+    collapse_events: EventWriter<'w, TileCollapsed>,
+    drawn_events: EventWriter<'w, TileDrawn>,
+}
+
+impl<'w> Dealer<'w> {
+    // Replaces the tile in hand with the next one held, drawing from the bag
+    // to keep the hand full.
+    pub fn draw(&mut self) {
+        let (kind, drawn) = self.held.draw(
+            self.rules.hand_size(),
+            &mut self.bag,
+            *self.difficulty,
+            &mut self.rng,
+        );
+        self.next_tile_kind.0 = kind;
+        self.drawn_events
+            .send_batch(drawn.into_iter().map(|kind| TileDrawn { kind }));
+    }
+
+    // Draws a tile straight from the bag onto the board, past the hand.
+    pub fn draw_from_bag(&mut self) -> TileKind {
+        let kind = self.bag.draw(*self.difficulty, &mut self.rng);
+        self.drawn_events.send(TileDrawn { kind });
+        kind
+    }
+
+    pub fn swap(&mut self) {
+        if let Some(kind) = self.held.swap(self.next_tile_kind.0) {
+            self.next_tile_kind.0 = kind;
+        }
+    }
+
+    // Whether a tile can go down over one of `kind`, collapsing it, which the
+    // no collapse house rule forbids.
+    pub fn can_place_over(&self, kind: TileKind) -> bool {
+        kind == TileKind::Empty || !self.rules.no_collapse
+    }
+
+    // Swaps the tile in hand for the next from the bag, discarding it.
+    pub fn redraw(&mut self) {
+        self.bag.discard(self.next_tile_kind.0);
+        self.draw();
+    }
+
+    // A tile that has left the board goes on the discard pile.
+    pub fn collapse(&mut self, pos: TilePos, floor: u32, kind: TileKind) {
+        self.collapse_events
+            .send(TileCollapsed { pos, floor, kind });
+        self.bag.discard(kind);
+    }
+}
+
+pub fn deal_first_tile(mut dealer: Dealer) {
+    dealer.draw();
+}
+
+pub fn spawn_players(mut commands: Commands, player_config: Res<PlayerConfig>) {
+    commands.spawn((Player { number: 0 }, TurnBudget::default()));
+    for number in 1..=player_config.bots {
+        commands.spawn((Player { number }, TurnBudget::default(), Bot));
+    }
+}
+
+pub fn apply_placements(
+    mut commands: Commands,
+    mut place_events: EventReader<PlaceTile>,
+    mut placed_events: EventWriter<TilePlaced>,
+    mut dealer: Dealer,
+    mut budget: CurrentBudget,
+    floors: Floors,
+    mut tiles_q: Query<(
+        Option<&TileType>,
+        &mut Rotation,
+        Option<&mut HexRotation>,
+        &mut TileHistory,
+    )>,
+) {
+    for place in place_events.read() {
+        let Some(tile_entity) = floors.tile_on(place.floor, &place.pos) else {
+            continue;
+        };
+        let Ok((tile_type, mut rotation, hex_rotation, mut history)) = tiles_q.get_mut(tile_entity)
+        else {
+            continue;
+        };
+        if tile_type.is_some_and(|tile_type| !dealer.can_place_over(tile_type.kind))
+            || !budget.spend()
+        {
+            continue;
+        }
+
+        // remember what is being placed over, which leaves the board
+        if let Some(tile_type) = tile_type {
+            history.0.push(tile_type.kind);
+            dealer.collapse(place.pos, place.floor, tile_type.kind);
+        }
+        match hex_rotation {
+            Some(mut hex_rotation) => hex_rotation.0 = place.rotation % 6,
+            None => rotation.0 = Quarter::from_turns(place.rotation),
+        }
+        commands
+            .entity(tile_entity)
+            .insert(TileType { kind: place.kind });
+        placed_events.send(TilePlaced {
+            player: budget.player(),
+            pos: place.pos,
+            floor: place.floor,
+            kind: place.kind,
+            rotation: place.rotation,
+        });
+
+        // a fresh tile goes into hand
+        dealer.draw();
+    }
+}
+
+// Swapping which tile is in hand is free, as it's only a choice of what to
+// play next.
+pub fn swap_hands(mut swap_events: EventReader<SwapHand>, mut dealer: Dealer) {
+    for _ in swap_events.read() {
+        dealer.swap();
+    }
+}
+
+// end the turn early on pressing t
+pub fn end_turn_input(
+    keyboard_input: Res<Input<KeyCode>>,
+    turn: Res<Turn>,
+    mut players_q: Query<(&Player, &mut TurnBudget), Without<Bot>>,
+    mut passed_events: EventWriter<TurnPassed>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::T) {
+        return;
+    }
+    for (player, mut budget) in players_q.iter_mut() {
+        if player.number == turn.player {
+            budget.remaining = 0;
+            passed_events.send(TurnPassed {
+                player: player.number,
+            });
+        }
+    }
+}
+
+// Once the current player has spent all their actions, the next player
+// gets a fresh budget and the turn.
+pub fn advance_turns(mut turn: ResMut<Turn>, mut players_q: Query<(&Player, &mut TurnBudget)>) {
+    let exhausted = players_q
+        .iter()
+        .any(|(player, budget)| player.number == turn.player && budget.remaining == 0);
+    if !exhausted {
+        return;
+    }
+
+    turn.advance(players_q.iter().count());
+    for (player, mut budget) in players_q.iter_mut() {
+        if player.number == turn.player {
+            *budget = TurnBudget::default();
+        }
+    }
+}
+
+type FreeCellQuery = (
+    Entity,
+    &'static TilePos,
+    &'static Floor,
+    Has<TileType>,
+    Has<PlayerStart>,
+);
+
+// Without a scenario placing them, wax eaters lurk in random empty cells of
+// the ground floor.
+pub fn spawn_wax_eaters(
+    mut commands: Commands,
+    (difficulty, content): (Res<Difficulty>, Res<Content>),
+    active_scenario: Res<ActiveScenario>,
+    mut rng: ResMut<GameRng>,
+    tiles_q: Query<FreeCellQuery>,
+) {
+    let placed_by_scenario = active_scenario
+        .0
+        .as_ref()
+        .is_some_and(|scenario| !scenario.monsters.is_empty());
+    if placed_by_scenario {
+        return;
+    }
+
+    let cells = tiles_q
+        .iter()
+        .filter(|(_, _, floor, placed, player_start)| floor.0 == 0 && !placed && !player_start)
+        .map(|(tile_entity, tile_pos, ..)| (tile_entity, *tile_pos))
+        .collect();
+    let count = difficulty.wax_eaters() + content.extra_wax_eaters;
+    for tile_entity in wax_eater_cells(cells, count, &mut rng) {
+        commands.entity(tile_entity).insert(MonsterSpawn);
+    }
+}
+
+// Picks which of the free `cells` the `count` wax eaters start in.
+pub fn wax_eater_cells<T>(mut cells: Vec<(T, TilePos)>, count: usize, rng: &mut GameRng) -> Vec<T> {
+    // Query order isn't stable, so sort before shuffling to keep games repeatable.
+    cells.sort_by_key(|(_, pos)| (pos.y, pos.x));
+    rng.0.shuffle(&mut cells);
+    cells
+        .into_iter()
+        .take(count)
+        .map(|(cell, _)| cell)
+        .collect()
+}
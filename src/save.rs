@@ -0,0 +1,71 @@
+// Files the game keeps between runs, and the scenarios it loads, start with
+// the format they were written in and its version:
+//
+//     # nightcage stats 1
+//
+// The header is a comment, so every parser skips it as it always has. Files
+// written before there were headers are version 1. When a format changes, its
+// version goes up by adding a migration bringing the version before it up to
+// date, and files from every earlier version keep loading.
+pub struct Format {
+    pub name: &'static str,
+    // Each rewrites a file of the version one below its place in the list,
+    // counting from 1, as the version above.
+    pub migrations: &'static [Migration],
+}
+
+pub type Migration = fn(&str) -> Result<String, String>;
+
+const HEADER_PREFIX: &str = "# nightcage ";
+
+impl Format {
+    // The version files are written in.
+    pub fn version(&self) -> u32 {
+        self.migrations.len() as u32 + 1
+    }
+
+    pub fn header(&self) -> String {
+        format!("{}{} {}\n", HEADER_PREFIX, self.name, self.version())
+    }
+
+    // The version `source` was written in, from its header.
+    pub fn version_of(&self, source: &str) -> Result<u32, String> {
+        let Some(header) = source
+            .lines()
+            .next()
+            .and_then(|line| line.trim().strip_prefix(HEADER_PREFIX))
+        else {
+            return Ok(1);
+        };
+        let invalid = || format!("invalid header {}{}", HEADER_PREFIX, header);
+        let (name, version) = header.split_once(' ').ok_or_else(invalid)?;
+        if name != self.name {
+            return Err(format!(
+                "expected a {} file, got a {} file",
+                self.name, name
+            ));
+        }
+        match version.trim().parse::<u32>() {
+            Ok(version) if version > self.version() => Err(format!(
+                "{} version {} is newer than this build reads, up to {}",
+                self.name,
+                version,
+                self.version()
+            )),
+            Ok(version) if version > 0 => Ok(version),
+            _ => Err(invalid()),
+        }
+    }
+
+    // Brings `source` up to the current version. The header is left as it
+    // was, since nothing reads it after this.
+    pub fn upgrade(&self, source: &str) -> Result<String, String> {
+        let version = self.version_of(source)?;
+        let mut source = source.to_string();
+        for (from, migrate) in (version..).zip(&self.migrations[version as usize - 1..]) {
+            source = migrate(&source)
+                .map_err(|err| format!("migrating {} version {}: {}", self.name, from, err))?;
+        }
+        Ok(source)
+    }
+}
@@ -0,0 +1,240 @@
+use std::fmt;
+
+use bevy::prelude::*;
+use bevy_ecs_tilemap::prelude::*;
+
+use crate::board::BoardShape;
+use crate::floor::stairs_meet;
+use crate::save::Format;
+use crate::tiles::TileKind;
+
+// A tile laid out by a scenario. Rotation is in quarter turns on square boards
+// and sixth turns on hex boards.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScenarioTile {
+    pub pos: TilePos,
+    pub floor: u32,
+    pub kind: TileKind,
+    pub rotation: u8,
+}
+
+// A board set up ahead of play, as authored in the editor. Players start and
+// monsters lurk on the ground floor.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Scenario {
+    pub size: TilemapSize,
+    pub shape: BoardShape,
+    pub floors: u32,
+    pub tiles: Vec<ScenarioTile>,
+    // Flights of stairs up from a floor to the one above, by the floor they
+    // start on.
+    pub stairs: Vec<(u32, TilePos)>,
+    pub player_starts: Vec<TilePos>,
+    pub monsters: Vec<TilePos>,
+    // The first tile to be placed, if the scenario picks one.
+    pub next: Option<TileKind>,
+    // How many of each kind go in every fresh bag, in place of the
+    // difficulty's mix. Empty leaves the bag to the difficulty.
+    pub bag: Vec<(TileKind, usize)>,
+}
+
+// The scenario the board was set up from, if any.
+#[derive(Resource, Default)]
+pub struct ActiveScenario(pub Option<Scenario>);
+
+const SCENARIO_DIR: &str = "assets/scenarios";
+
+pub const SCENARIO_FORMAT: Format = Format {
+    name: "scenario",
+    migrations: &[],
+};
+
+impl Scenario {
+    // Loads `assets/scenarios/<name>.scenario`.
+    pub fn load(name: &str) -> Result<Scenario, String> {
+        let path = format!("{}/{}.scenario", SCENARIO_DIR, name);
+        let source = std::fs::read_to_string(&path)
+            .map_err(|err| format!("failed to read {}: {}", path, err))?;
+        Scenario::parse(&source).map_err(|err| format!("{}: {}", path, err))
+    }
+
+    // Scenario files are plain text with one entry per line, after the
+    // format's header. Blank lines and lines starting with `#` are ignored,
+    // and the board must come first.
+    // Entries are on the ground floor until a `floor` line moves on to
+    // another, and stairs lead up from the floor they're on:
+    //
+    //   # nightcage scenario 1
+    //   board 7x7 square
+    //   tile 3 2 straight 1
+    //   stairs 3 2
+    //   start 0 0
+    //   monster 4 4
+    //   next corner
+    //   bag straight 6
+    //   bag key 1
+    //   floor 1
+    //   tile 3 2 dead-end 0
+    pub fn parse(source: &str) -> Result<Scenario, String> {
+        let mut scenario: Option<Scenario> = None;
+        let mut floor = 0;
+        let source = SCENARIO_FORMAT.upgrade(source)?;
+        for (number, line) in source.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let at_line = |err: String| format!("line {}: {}", number + 1, err);
+
+            let Some(scenario) = scenario.as_mut() else {
+                scenario = Some(parse_board(&words).map_err(at_line)?);
+                continue;
+            };
+            match words.as_slice() {
+                ["tile", x, y, kind, rotation] => {
+                    let pos = parse_pos(x, y, &scenario.size).map_err(at_line)?;
+                    let kind = parse_kind(kind).map_err(at_line)?;
+                    let rotation = rotation
+                        .parse::<u8>()
+                        .map_err(|_| at_line(format!("invalid rotation {}", rotation)))?;
+                    scenario.tiles.push(ScenarioTile {
+                        pos,
+                        floor,
+                        kind,
+                        rotation,
+                    });
+                }
+                ["floor", number] => {
+                    floor = number
+                        .parse::<u32>()
+                        .map_err(|_| at_line(format!("invalid floor {}", number)))?;
+                    scenario.floors = scenario.floors.max(floor + 1);
+                }
+                ["stairs", x, y] => {
+                    let pos = parse_pos(x, y, &scenario.size).map_err(at_line)?;
+                    if scenario
+                        .stairs
+                        .iter()
+                        .any(|other| stairs_meet(other, &(floor, pos)))
+                    {
+                        return Err(at_line(format!("stairs at {} {} meet other stairs", x, y)));
+                    }
+                    scenario.stairs.push((floor, pos));
+                    scenario.floors = scenario.floors.max(floor + 2);
+                }
+                ["start" | "monster", ..] if floor > 0 => {
+                    return Err(at_line(
+                        "only the ground floor has starts and monsters".to_string(),
+                    ))
+                }
+                ["start", x, y] => {
+                    let pos = parse_pos(x, y, &scenario.size).map_err(at_line)?;
+                    scenario.player_starts.push(pos);
+                }
+                ["monster", x, y] => {
+                    let pos = parse_pos(x, y, &scenario.size).map_err(at_line)?;
+                    scenario.monsters.push(pos);
+                }
+                ["next", kind] => scenario.next = Some(parse_kind(kind).map_err(at_line)?),
+                ["bag", kind, count] => {
+                    let kind = parse_kind(kind).map_err(at_line)?;
+                    let count = count
+                        .parse::<usize>()
+                        .map_err(|_| at_line(format!("invalid count {}", count)))?;
+                    scenario.bag.push((kind, count));
+                }
+                _ => return Err(at_line(format!("unrecognised entry {}", line))),
+            }
+        }
+        scenario.ok_or_else(|| "missing board".to_string())
+    }
+
+    // The tiles and stairs of one floor.
+    fn fmt_floor(&self, f: &mut fmt::Formatter, floor: u32) -> fmt::Result {
+        for tile in self.tiles.iter().filter(|tile| tile.floor == floor) {
+            writeln!(
+                f,
+                "tile {} {} {} {}",
+                tile.pos.x,
+                tile.pos.y,
+                tile.kind.name(),
+                tile.rotation
+            )?;
+        }
+        for (_, pos) in self.stairs.iter().filter(|(from, _)| *from == floor) {
+            writeln!(f, "stairs {} {}", pos.x, pos.y)?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_board(words: &[&str]) -> Result<Scenario, String> {
+    let ["board", size, shape] = words else {
+        return Err("expected board <width>x<height> <square|hex>".to_string());
+    };
+    let invalid_size = || format!("invalid board size {}", size);
+    let (x, y) = size.split_once('x').ok_or_else(invalid_size)?;
+    let (Ok(x), Ok(y)) = (x.parse::<u32>(), y.parse::<u32>()) else {
+        return Err(invalid_size());
+    };
+    if x == 0 || y == 0 {
+        return Err(invalid_size());
+    }
+    let shape = match *shape {
+        "square" => BoardShape::Square,
+        "hex" => BoardShape::Hex,
+        _ => return Err(format!("invalid board shape {}", shape)),
+    };
+    Ok(Scenario {
+        size: TilemapSize { x, y },
+        shape,
+        floors: 1,
+        tiles: Vec::new(),
+        stairs: Vec::new(),
+        player_starts: Vec::new(),
+        monsters: Vec::new(),
+        next: None,
+        bag: Vec::new(),
+    })
+}
+
+fn parse_pos(x: &str, y: &str, size: &TilemapSize) -> Result<TilePos, String> {
+    match (x.parse::<u32>(), y.parse::<u32>()) {
+        (Ok(x), Ok(y)) if x < size.x && y < size.y => Ok(TilePos { x, y }),
+        _ => Err(format!("invalid position {} {}", x, y)),
+    }
+}
+
+fn parse_kind(name: &str) -> Result<TileKind, String> {
+    TileKind::from_name(name).ok_or_else(|| format!("unknown tile {}", name))
+}
+
+impl fmt::Display for Scenario {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let shape = match self.shape {
+            BoardShape::Square => "square",
+            BoardShape::Hex => "hex",
+        };
+        write!(f, "{}", SCENARIO_FORMAT.header())?;
+        writeln!(f, "board {}x{} {}", self.size.x, self.size.y, shape)?;
+        self.fmt_floor(f, 0)?;
+        for pos in &self.player_starts {
+            writeln!(f, "start {} {}", pos.x, pos.y)?;
+        }
+        for pos in &self.monsters {
+            writeln!(f, "monster {} {}", pos.x, pos.y)?;
+        }
+        if let Some(next) = self.next {
+            writeln!(f, "next {}", next.name())?;
+        }
+        for (kind, count) in &self.bag {
+            writeln!(f, "bag {} {}", kind.name(), count)?;
+        }
+        for floor in 1..self.floors {
+            writeln!(f, "floor {}", floor)?;
+            self.fmt_floor(f, floor)?;
+        }
+        Ok(())
+    }
+}
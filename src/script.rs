@@ -0,0 +1,270 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use bevy::{ecs::system::SystemParam, prelude::*};
+use bevy_ecs_tilemap::prelude::*;
+
+use crate::board::lit_neighbors;
+use crate::content::RulesSet;
+use crate::inspect::BoardInspector;
+use crate::nerve::Nerve;
+use crate::pawn::{self, Keys, Pawn};
+use crate::rules::{self, CurrentBudget, Dealer, Player, TilePlaced};
+use crate::tiles::TileKind;
+
+// Scripts live in assets/scripts, one per kind of tile named as scenarios
+// name them, and give the tile effects when something happens to it. Each
+// line runs one effect on one hook:
+//
+//     # assets/scripts/pillar.script
+//     on place grant 1
+//     on enter nerve 1
+//     on light key 1
+//     on enter redraw
+//
+// A tile is placed when it goes down, entered when a pawn arrives on it and
+// lit when a tile placed beside it opens a passage onto it. Scripts can only
+// reach the rules through the effects below, a few at a time, so a custom
+// tile can't break a game.
+//
+// This is a fixed list of effects read from config, not a scripting engine:
+// there are no variables, conditions or loops, and nothing is embedded to
+// run them. A tile that needs more than these four effects needs code.
+const SCRIPT_DIR: &str = "assets/scripts";
+const SCRIPT_EXTENSION: &str = "script";
+
+// Most of anything one effect hands out.
+const MAX_AMOUNT: u32 = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Hook {
+    Place,
+    Enter,
+    Light,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Effect {
+    // More actions for whoever's turn it is.
+    Grant(u32),
+    // More tokens in the shared nerve pool.
+    Nerve(u32),
+    // Keys for the player who set the hook off.
+    Key(u32),
+    // Swap the tile in hand for the next one.
+    Redraw,
+}
+
+// A script's hooks and the effects they run, in order.
+pub type Script = Vec<(Hook, Effect)>;
+
+// Every kind's script.
+#[derive(Resource, Clone, Debug, Default, PartialEq)]
+pub struct TileScripts(HashMap<TileKind, Script>);
+
+impl TileScripts {
+    // Loads every script in assets/scripts, which needn't be there at all.
+    // Where the folder can't be read, as on the web, there are no scripts,
+    // and a script that can't be read is left out.
+    pub fn load() -> TileScripts {
+        let mut scripts = TileScripts::default();
+        let entries = match std::fs::read_dir(SCRIPT_DIR) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return scripts,
+            Err(err) => {
+                warn!("failed to read {}: {}", SCRIPT_DIR, err);
+                return scripts;
+            }
+        };
+        for entry in entries {
+            match entry
+                .map_err(|err| format!("failed to read {}: {}", SCRIPT_DIR, err))
+                .and_then(|entry| read_script(&entry.path()))
+            {
+                Ok(Some((kind, script))) => {
+                    scripts.0.insert(kind, script);
+                }
+                Ok(None) => {}
+                Err(err) => warn!("{}", err),
+            }
+        }
+        scripts
+    }
+
+    // The effects a kind's script runs on `hook`, in order.
+    pub fn effects(&self, kind: TileKind, hook: Hook) -> impl Iterator<Item = Effect> + '_ {
+        self.0
+            .get(&kind)
+            .into_iter()
+            .flatten()
+            .filter(move |(on, _)| *on == hook)
+            .map(|(_, effect)| *effect)
+    }
+}
+
+// The kind a script file is for and its script, or nothing for a file that
+// isn't a script.
+fn read_script(path: &Path) -> Result<Option<(TileKind, Script)>, String> {
+    if path.extension().and_then(|ext| ext.to_str()) != Some(SCRIPT_EXTENSION) {
+        return Ok(None);
+    }
+    let name = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("");
+    let kind = TileKind::from_name(name)
+        .filter(|kind| *kind != TileKind::Empty)
+        .ok_or_else(|| format!("{}: no tile is named {}", path.display(), name))?;
+    let source = std::fs::read_to_string(path)
+        .map_err(|err| format!("failed to read {}: {}", path.display(), err))?;
+    let script = parse(&source).map_err(|err| format!("{}: {}", path.display(), err))?;
+    Ok(Some((kind, script)))
+}
+
+// Blank lines and lines starting with `#` are skipped.
+pub fn parse(source: &str) -> Result<Script, String> {
+    let mut script = Vec::new();
+    for (number, line) in source.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let at_line = |err: String| format!("line {}: {}", number + 1, err);
+        let (hook, effect) = match words.as_slice() {
+            ["on", hook, effect @ ..] => (parse_hook(hook), parse_effect(effect)),
+            _ => {
+                return Err(at_line(format!(
+                    "expected on <hook> <effect>, got {}",
+                    line
+                )))
+            }
+        };
+        script.push((hook.map_err(at_line)?, effect.map_err(at_line)?));
+    }
+    Ok(script)
+}
+
+fn parse_hook(word: &str) -> Result<Hook, String> {
+    match word {
+        "place" => Ok(Hook::Place),
+        "enter" => Ok(Hook::Enter),
+        "light" => Ok(Hook::Light),
+        _ => Err(format!("unknown hook {}", word)),
+    }
+}
+
+fn parse_effect(words: &[&str]) -> Result<Effect, String> {
+    let amount = |value: &str| match value.parse::<u32>() {
+        Ok(amount) if (1..=MAX_AMOUNT).contains(&amount) => Ok(amount),
+        _ => Err(format!(
+            "invalid amount {}, expected 1 to {}",
+            value, MAX_AMOUNT
+        )),
+    };
+    match words {
+        ["grant", value] => Ok(Effect::Grant(amount(value)?)),
+        ["nerve", value] => Ok(Effect::Nerve(amount(value)?)),
+        ["key", value] => Ok(Effect::Key(amount(value)?)),
+        ["redraw"] => Ok(Effect::Redraw),
+        _ => Err(format!("unknown effect {}", words.join(" "))),
+    }
+}
+
+// All a script can touch.
+#[derive(SystemParam)]
+pub struct ScriptApi<'w, 's> {
+    scripts: Res<'w, TileScripts>,
+    budget: CurrentBudget<'w, 's>,
+    nerve: ResMut<'w, Nerve>,
+    dealer: Dealer<'w>,
+    keys_q: Query<'w, 's, (&'static Player, &'static mut Keys)>,
+}
+
+impl<'w, 's> ScriptApi<'w, 's> {
+    // Runs a kind's effects on `hook`, set off by `player`.
+    fn run(&mut self, kind: TileKind, hook: Hook, player: usize) {
+        let effects: Vec<Effect> = self.scripts.effects(kind, hook).collect();
+        for effect in effects {
+            match effect {
+                Effect::Grant(actions) => self.budget.grant(actions),
+                Effect::Nerve(tokens) => self.nerve.0 += tokens,
+                Effect::Key(keys) => {
+                    for (seat, mut carried) in self.keys_q.iter_mut() {
+                        if seat.number == player {
+                            carried.0 += keys;
+                        }
+                    }
+                }
+                Effect::Redraw => self.dealer.redraw(),
+            }
+        }
+    }
+}
+
+// Runs the scripts tiles carry. The scripts themselves are loaded by the app
+// before it starts.
+pub struct TileScriptPlugin;
+
+impl Plugin for TileScriptPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TileScripts>().add_systems(
+            Update,
+            (
+                // placed tiles have to be on the board to light anything
+                (apply_deferred, run_place_scripts)
+                    .chain()
+                    .after(rules::apply_placements),
+                run_enter_scripts.after(pawn::move_pawns),
+            )
+                .in_set(RulesSet),
+        );
+    }
+}
+
+// A placed tile runs its place hook, then the light hook of every placed tile
+// its passages open onto.
+pub fn run_place_scripts(
+    mut placed_events: EventReader<TilePlaced>,
+    inspector: BoardInspector,
+    mut api: ScriptApi,
+) {
+    let board_config = inspector.board_config();
+    for placed in placed_events.read() {
+        let passages_at = |pos: &TilePos| {
+            inspector
+                .tile_info_on(placed.floor, pos)
+                .filter(|info| info.kind != TileKind::Empty)
+                .map(|info| info.exits)
+        };
+        api.run(placed.kind, Hook::Place, placed.player);
+        let Some(exits) = passages_at(&placed.pos) else {
+            continue;
+        };
+        let lit = lit_neighbors(
+            &placed.pos,
+            exits,
+            &board_config.size,
+            &board_config.map_type(),
+            inspector.wraps(),
+            passages_at,
+        );
+        for pos in lit {
+            if let Some(info) = inspector.tile_info_on(placed.floor, &pos) {
+                api.run(info.kind, Hook::Light, placed.player);
+            }
+        }
+    }
+}
+
+pub fn run_enter_scripts(
+    inspector: BoardInspector,
+    pawns_q: Query<(&Player, &Pawn), Changed<Pawn>>,
+    mut api: ScriptApi,
+) {
+    for (player, pawn) in pawns_q.iter() {
+        if let Some(info) = inspector.tile_info_on(pawn.floor, &pawn.pos) {
+            api.run(info.kind, Hook::Enter, player.number);
+        }
+    }
+}
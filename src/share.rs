@@ -0,0 +1,563 @@
+use bevy::prelude::*;
+use bevy_ecs_tilemap::prelude::*;
+
+use crate::assist::Easing;
+use crate::autosave::Autosave;
+use crate::board::{turn_tile, BoardConfig, BoardShape, HexRotation, Rotation};
+use crate::difficulty::Difficulty;
+use crate::floor::{Floors, TakeStairs};
+use crate::house_rules::{HouseRule, RuleSet};
+use crate::locale::Strings;
+use crate::nerve::{NerveAction, SpendNerve};
+use crate::pawn::{MovePawn, PassKey};
+use crate::rng::Seed;
+use crate::rules::{CurrentBudget, HandTurned, PlaceTile, PlayerConfig, SwapHand, TurnPassed};
+use crate::scenario::ActiveScenario;
+use crate::tiles::TileKind;
+
+// How long each move of a replay is shown for, in seconds, unless skipping
+// ahead.
+const REPLAY_MOVE_SECONDS: f32 = 0.6;
+
+const CODE_VERSION: u8 = 2;
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+// A step of play by whoever's turn it is, as the game's events carry it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Move {
+    // The tile in hand put down, turned as given.
+    Place {
+        pos: TilePos,
+        floor: u32,
+        kind: TileKind,
+        rotation: u8,
+    },
+    // The tile in hand turned over a cell, paying an action.
+    Turn {
+        pos: TilePos,
+        floor: u32,
+    },
+    Walk(TilePos),
+    PassKey(TilePos),
+    Stairs,
+    Swap,
+    Nerve(NerveAction),
+    // The rest of the turn given up.
+    Pass,
+}
+
+// A game dealt from a seed, and every move made in it. Playing the moves
+// again on the same seed, board, players and rules deals the same tiles and
+// wakes the same wax eaters, so the game plays out as it did. Games set up
+// from a scenario can't be shared, as the scenario isn't in the code.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Replay {
+    pub seed: Seed,
+    pub difficulty: Difficulty,
+    pub size: TilemapSize,
+    pub shape: BoardShape,
+    pub floors: u32,
+    pub bots: usize,
+    pub rules: RuleSet,
+    pub moves: Vec<Move>,
+}
+
+// Replays are shared as a short code to paste in chat, or on the end of a
+// link after a `#`. The code is the replay in bytes, written out in url safe
+// base64 without padding. Numbers are stored seven bits to a byte, low bits
+// first, with the top bit set on every byte but the last:
+//
+//     version      1
+//     seed         number
+//     settings     difficulty (0 easy, 1 normal, 2 nightmare) + 4 if hex
+//     board        width, height, floors, all numbers
+//     bots         number
+//     rules        number, a bit for each house rule on in menu order
+//     moves        one byte each, then a cell for those with one, then
+//                  for placements and turns the floor × 8 + the kind of
+//                  tile placed, as a number
+//
+// A move's byte has its kind in the low three bits and the rotation of a
+// placement or the nerve spent on above them. Cells are numbered row by row
+// from the bottom left, and tiles by their place in `TileKind::PLACEABLE`.
+impl Replay {
+    pub fn encode(&self) -> String {
+        let mut bytes = vec![CODE_VERSION];
+        push_number(&mut bytes, self.seed.0);
+        let difficulty = match self.difficulty {
+            Difficulty::Easy => 0,
+            Difficulty::Normal => 1,
+            Difficulty::Nightmare => 2,
+        };
+        let hex = match self.shape {
+            BoardShape::Square => 0,
+            BoardShape::Hex => 4,
+        };
+        bytes.push(difficulty + hex);
+        for number in [self.size.x, self.size.y, self.floors] {
+            push_number(&mut bytes, number as u64);
+        }
+        push_number(&mut bytes, self.bots as u64);
+        let rules = HouseRule::ALL
+            .into_iter()
+            .enumerate()
+            .filter(|(_, rule)| self.rules.get(*rule))
+            .fold(0, |bits, (bit, _)| bits | 1 << bit);
+        push_number(&mut bytes, rules);
+
+        let cell = |pos: &TilePos| (pos.y * self.size.x + pos.x) as u64;
+        let placeable = |kind: &TileKind| {
+            TileKind::PLACEABLE
+                .iter()
+                .position(|placeable| placeable == kind)
+                .unwrap_or(0) as u64
+        };
+        for play in &self.moves {
+            let (kind, detail, pos) = match play {
+                Move::Place { pos, rotation, .. } => (0, *rotation, Some(pos)),
+                Move::Turn { pos, .. } => (1, 0, Some(pos)),
+                Move::Walk(pos) => (2, 0, Some(pos)),
+                Move::PassKey(pos) => (3, 0, Some(pos)),
+                Move::Stairs => (4, 0, None),
+                Move::Swap => (5, 0, None),
+                Move::Nerve(action) => {
+                    let action = match action {
+                        NerveAction::ExtraAction => 0,
+                        NerveAction::Redraw => 1,
+                        NerveAction::SurviveHit => 2,
+                    };
+                    (6, action, None)
+                }
+                Move::Pass => (7, 0, None),
+            };
+            bytes.push(kind | detail << 3);
+            if let Some(pos) = pos {
+                push_number(&mut bytes, cell(pos));
+            }
+            match play {
+                Move::Place { floor, kind, .. } => {
+                    push_number(&mut bytes, *floor as u64 * 8 + placeable(kind))
+                }
+                Move::Turn { floor, .. } => push_number(&mut bytes, *floor as u64 * 8),
+                _ => {}
+            }
+        }
+        to_base64(&bytes)
+    }
+
+    // Takes a code on its own or the link it ends.
+    pub fn decode(code: &str) -> Result<Replay, String> {
+        let code = code.rsplit('#').next().unwrap_or(code).trim();
+        let bytes = from_base64(code).ok_or_else(|| format!("invalid share code {}", code))?;
+        let mut bytes = bytes.into_iter();
+        let mut byte = || bytes.next().ok_or("the share code is cut short");
+
+        let version = byte()?;
+        if version != CODE_VERSION {
+            return Err(format!(
+                "share code version {} isn't one this build reads",
+                version
+            ));
+        }
+        let seed = Seed(read_number(&mut byte)?);
+        let settings = byte()?;
+        let difficulty = match settings & 3 {
+            0 => Difficulty::Easy,
+            1 => Difficulty::Normal,
+            2 => Difficulty::Nightmare,
+            _ => return Err("invalid difficulty in share code".to_string()),
+        };
+        let shape = if settings & 4 == 0 {
+            BoardShape::Square
+        } else {
+            BoardShape::Hex
+        };
+        let mut small = |what: &str| match read_number(&mut byte)? {
+            number if number > 0 && number <= u32::MAX as u64 => Ok(number as u32),
+            _ => Err(format!("invalid {} in share code", what)),
+        };
+        let size = TilemapSize {
+            x: small("board width")?,
+            y: small("board height")?,
+        };
+        let floors = small("floor count")?;
+        let bots = read_number(&mut byte)? as usize;
+        let rule_bits = read_number(&mut byte)?;
+        let mut rules = RuleSet::default();
+        for (bit, rule) in HouseRule::ALL.into_iter().enumerate() {
+            rules.set(rule, rule_bits & 1 << bit != 0);
+        }
+
+        let mut moves = Vec::new();
+        while let Ok(play) = byte() {
+            let detail = play >> 3;
+            let cell = |cell: u64| {
+                if cell >= size.x as u64 * size.y as u64 {
+                    return Err(format!("invalid cell {} in share code", cell));
+                }
+                Ok(TilePos {
+                    x: (cell % size.x as u64) as u32,
+                    y: (cell / size.x as u64) as u32,
+                })
+            };
+            let floor_and_kind = |number: u64| match number / 8 {
+                floor if floor < floors as u64 => {
+                    Ok((floor as u32, TileKind::PLACEABLE[(number % 8) as usize]))
+                }
+                floor => Err(format!("invalid floor {} in share code", floor)),
+            };
+            moves.push(match play & 7 {
+                0 => {
+                    let pos = cell(read_number(&mut byte)?)?;
+                    let (floor, kind) = floor_and_kind(read_number(&mut byte)?)?;
+                    Move::Place {
+                        pos,
+                        floor,
+                        kind,
+                        rotation: detail,
+                    }
+                }
+                1 => Move::Turn {
+                    pos: cell(read_number(&mut byte)?)?,
+                    floor: floor_and_kind(read_number(&mut byte)?)?.0,
+                },
+                2 => Move::Walk(cell(read_number(&mut byte)?)?),
+                3 => Move::PassKey(cell(read_number(&mut byte)?)?),
+                4 => Move::Stairs,
+                5 => Move::Swap,
+                6 => Move::Nerve(match detail {
+                    0 => NerveAction::ExtraAction,
+                    1 => NerveAction::Redraw,
+                    2 => NerveAction::SurviveHit,
+                    _ => return Err(format!("invalid nerve action {} in share code", detail)),
+                }),
+                _ => Move::Pass,
+            });
+        }
+        Ok(Replay {
+            seed,
+            difficulty,
+            size,
+            shape,
+            floors,
+            bots,
+            rules,
+            moves,
+        })
+    }
+}
+
+fn push_number(bytes: &mut Vec<u8>, mut number: u64) {
+    while number >= 0x80 {
+        bytes.push(number as u8 | 0x80);
+        number >>= 7;
+    }
+    bytes.push(number as u8);
+}
+
+fn read_number(byte: &mut impl FnMut() -> Result<u8, &'static str>) -> Result<u64, String> {
+    let mut number = 0;
+    for shift in (0..64).step_by(7) {
+        let next = byte()?;
+        number |= ((next & 0x7f) as u64) << shift;
+        if next & 0x80 == 0 {
+            return Ok(number);
+        }
+    }
+    Err("invalid number in share code".to_string())
+}
+
+fn to_base64(bytes: &[u8]) -> String {
+    let mut code = String::new();
+    for chunk in bytes.chunks(3) {
+        let bits = chunk.iter().enumerate().fold(0u32, |bits, (i, byte)| {
+            bits | (*byte as u32) << (16 - 8 * i)
+        });
+        for i in 0..=chunk.len() {
+            code.push(BASE64[(bits >> (18 - 6 * i) & 63) as usize] as char);
+        }
+    }
+    code
+}
+
+fn from_base64(code: &str) -> Option<Vec<u8>> {
+    let digits = code
+        .bytes()
+        .map(|c| {
+            BASE64
+                .iter()
+                .position(|digit| *digit == c)
+                .map(|i| i as u32)
+        })
+        .collect::<Option<Vec<u32>>>()?;
+    let mut bytes = Vec::new();
+    for chunk in digits.chunks(4) {
+        if chunk.len() == 1 {
+            return None;
+        }
+        let bits = chunk
+            .iter()
+            .enumerate()
+            .fold(0, |bits, (i, digit)| bits | digit << (18 - 6 * i));
+        for i in 0..chunk.len() - 1 {
+            bytes.push((bits >> (16 - 8 * i)) as u8);
+        }
+    }
+    Some(bytes)
+}
+
+// The moves made in the game in play, kept so it can be shared. A game
+// picked up from an autosave has moves from before it was saved missing.
+#[derive(Resource, Default)]
+pub struct GameRecord {
+    moves: Vec<Move>,
+    resumed: bool,
+}
+
+// A replay being watched and how far it has got. While there are moves left
+// they're played for everyone, bots included, and nobody else can play.
+#[derive(Resource, Default)]
+pub struct Watching {
+    replay: Option<Replay>,
+    next: usize,
+    // Time since the last move was played.
+    waited: f32,
+    // Skipping ahead, playing a move every frame.
+    skipping: bool,
+}
+
+impl Watching {
+    pub fn new(replay: Replay) -> Watching {
+        Watching {
+            replay: Some(replay),
+            ..default()
+        }
+    }
+
+    // Whether the game in play was set up to watch a shared one.
+    pub fn is_replay(&self) -> bool {
+        self.replay.is_some()
+    }
+
+    fn moves_left(&self) -> bool {
+        self.replay
+            .as_ref()
+            .is_some_and(|replay| self.next < replay.moves.len())
+    }
+}
+
+pub fn watching(watching: Res<Watching>) -> bool {
+    watching.moves_left()
+}
+
+// A game started over from the replay's seed watches it again from the
+// start, while a game dealt from any other seed leaves it behind.
+pub fn start_record(
+    (seed, autosave): (Res<Seed>, Res<Autosave>),
+    mut record: ResMut<GameRecord>,
+    mut watching: ResMut<Watching>,
+) {
+    record.moves.clear();
+    record.resumed = autosave.resuming();
+    if watching
+        .replay
+        .as_ref()
+        .is_some_and(|replay| replay.seed != *seed)
+    {
+        watching.replay = None;
+    }
+    watching.next = 0;
+    watching.waited = 0.0;
+    watching.skipping = false;
+}
+
+// Moves made in the same frame are kept in the order they'd be made in.
+pub fn record_moves(
+    mut record: ResMut<GameRecord>,
+    (mut turned_events, mut place_events, mut move_events, mut pass_key_events): (
+        EventReader<HandTurned>,
+        EventReader<PlaceTile>,
+        EventReader<MovePawn>,
+        EventReader<PassKey>,
+    ),
+    (mut stairs_events, mut swap_events, mut nerve_events, mut passed_events): (
+        EventReader<TakeStairs>,
+        EventReader<SwapHand>,
+        EventReader<SpendNerve>,
+        EventReader<TurnPassed>,
+    ),
+) {
+    let moves = &mut record.moves;
+    moves.extend(turned_events.read().map(|turned| Move::Turn {
+        pos: turned.pos,
+        floor: turned.floor,
+    }));
+    moves.extend(place_events.read().map(|place| Move::Place {
+        pos: place.pos,
+        floor: place.floor,
+        kind: place.kind,
+        rotation: place.rotation,
+    }));
+    moves.extend(move_events.read().map(|move_pawn| Move::Walk(move_pawn.to)));
+    moves.extend(pass_key_events.read().map(|pass| Move::PassKey(pass.to)));
+    moves.extend(stairs_events.read().map(|_| Move::Stairs));
+    moves.extend(swap_events.read().map(|_| Move::Swap));
+    // nerve spent on surviving hits follows from the board, so it comes
+    // back by itself as the game is played back
+    moves.extend(
+        nerve_events
+            .read()
+            .filter(|SpendNerve(action)| *action != NerveAction::SurviveHit)
+            .map(|SpendNerve(action)| Move::Nerve(*action)),
+    );
+    moves.extend(passed_events.read().map(|_| Move::Pass));
+}
+
+// Plays the replay's next move through the same events as input does, with
+// tiles put down and turned on the floor and cell they were in the game
+// rather than wherever the view is. F10 skips ahead to where the replay ends,
+// after which the game is there to play on from.
+pub fn play_replay(
+    (time, keyboard_input): (Res<Time>, Res<Input<KeyCode>>),
+    mut watching: ResMut<Watching>,
+    mut budget: CurrentBudget,
+    (floors, mut tiles_q): (Floors, Query<(&mut Rotation, Option<&mut HexRotation>)>),
+    (mut place_events, mut move_events, mut pass_key_events): (
+        EventWriter<PlaceTile>,
+        EventWriter<MovePawn>,
+        EventWriter<PassKey>,
+    ),
+    (mut stairs_events, mut swap_events, mut nerve_events): (
+        EventWriter<TakeStairs>,
+        EventWriter<SwapHand>,
+        EventWriter<SpendNerve>,
+    ),
+) {
+    if keyboard_input.just_pressed(KeyCode::F10) {
+        watching.skipping = true;
+    }
+    watching.waited += time.delta_seconds();
+    if !watching.skipping && watching.waited < REPLAY_MOVE_SECONDS {
+        return;
+    }
+    watching.waited = 0.0;
+    let Some(play) = watching
+        .replay
+        .as_ref()
+        .and_then(|replay| replay.moves.get(watching.next).copied())
+    else {
+        return;
+    };
+    watching.next += 1;
+
+    let player = budget.player();
+    match play {
+        Move::Place {
+            pos,
+            floor,
+            kind,
+            rotation,
+        } => place_events.send(PlaceTile {
+            pos,
+            floor,
+            kind,
+            rotation,
+        }),
+        Move::Turn { pos, floor } => {
+            let tile = floors.tile_on(floor, &pos);
+            if let Some((mut rotation, mut hex_rotation)) =
+                tile.and_then(|tile| tiles_q.get_mut(tile).ok())
+            {
+                if budget.spend() {
+                    turn_tile(&mut rotation, hex_rotation.as_deref_mut());
+                }
+            }
+        }
+        Move::Walk(to) => move_events.send(MovePawn { player, to }),
+        Move::PassKey(to) => pass_key_events.send(PassKey { player, to }),
+        Move::Stairs => stairs_events.send(TakeStairs { player }),
+        Move::Swap => swap_events.send(SwapHand),
+        Move::Nerve(action) => nerve_events.send(SpendNerve(action)),
+        Move::Pass => budget.pass(),
+    }
+}
+
+#[derive(Component)]
+pub struct ReplayText;
+
+pub fn spawn_replay_text(mut commands: Commands, strings: Res<Strings>) {
+    commands.spawn((
+        TextBundle::from_section(
+            strings.get("replay-watching"),
+            TextStyle {
+                font_size: 20.0,
+                color: Color::WHITE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(40.0),
+            left: Val::Px(8.0),
+            ..default()
+        }),
+        ReplayText,
+        Visibility::Hidden,
+    ));
+}
+
+pub fn update_replay_text(
+    watching: Res<Watching>,
+    mut text_q: Query<&mut Visibility, With<ReplayText>>,
+) {
+    let shown = if watching.moves_left() {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+    for mut visibility in text_q.iter_mut() {
+        if *visibility != shown {
+            *visibility = shown;
+        }
+    }
+}
+
+// F7 prints a code for the game so far, to share so others can watch it.
+pub fn share_input(
+    keyboard_input: Res<Input<KeyCode>>,
+    (strings, record, easing): (Res<Strings>, Res<GameRecord>, Res<Easing>),
+    (seed, difficulty, rules): (Res<Seed>, Res<Difficulty>, Res<RuleSet>),
+    (board, players, active_scenario): (Res<BoardConfig>, Res<PlayerConfig>, Res<ActiveScenario>),
+) {
+    if !keyboard_input.just_pressed(KeyCode::F7) {
+        return;
+    }
+    if active_scenario.0.is_some() {
+        println!("{}", strings.get("share-scenario"));
+        return;
+    }
+    if record.resumed {
+        println!("{}", strings.get("share-resumed"));
+        return;
+    }
+    // the easing isn't in the code either, and changes what's dealt
+    if *easing != Easing::default() {
+        println!("{}", strings.get("share-eased"));
+        return;
+    }
+    let replay = Replay {
+        seed: *seed,
+        difficulty: *difficulty,
+        size: board.size,
+        shape: board.shape,
+        floors: board.floors,
+        bots: players.bots,
+        rules: *rules,
+        moves: record.moves.clone(),
+    };
+    println!(
+        "{}",
+        strings.format("share-code", &[("code", replay.encode())])
+    );
+}
@@ -0,0 +1,476 @@
+use std::collections::HashMap;
+
+use bevy_ecs_tilemap::prelude::*;
+
+use crate::ai::BoardView;
+use crate::board::{lit_neighbors, rotated_passages, rotation_steps, BoardConfig};
+use crate::darkness::{dark_frontier, land_hits, waking_cells};
+use crate::difficulty::Difficulty;
+use crate::falling::landing;
+use crate::floor::random_stairs;
+use crate::house_rules::RuleSet;
+use crate::nerve::NerveAction;
+use crate::pawn::{can_move_between, escapes, key_lit, start_pos};
+use crate::rng::{GameRng, Seed};
+use crate::rules::{wax_eater_cells, HeldTiles, TileBag, Turn, TurnBudget};
+use crate::scenario::Scenario;
+use crate::share::Move;
+use crate::tiles::{Passages, TileKind};
+
+// Something a player can do on their turn, as the input systems would send it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    // Put the tile in hand down, turned as it is in hand.
+    Place(TilePos),
+    // Turn the tile in hand one step clockwise.
+    Rotate,
+    // Walk the current player's pawn into a neighboring tile.
+    Move(TilePos),
+    // Hand one of the current player's keys to the pawn on a neighboring tile.
+    PassKey(TilePos),
+    EndTurn,
+    // Spend nerve on another action this turn.
+    ExtraAction,
+    // Spend nerve to swap the tile in hand.
+    Redraw,
+    // Swap the tile in hand for another one held.
+    Swap,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SimPawn {
+    pub pos: TilePos,
+    pub keys: u32,
+}
+
+// The rules of the game without any of the rendering, input or windowing, so
+// whole games can be played out from a list of actions. It follows the same
+// rules as the game's systems, through the same functions where they're kept
+// apart from the ECS, and draws from the same seeded randomness, so a seed and
+// a list of actions play out the same way in both. Left out of it are:
+//
+//     - every floor but the ground floor, and taking the stairs
+//     - tile scripts
+//     - expansions, and any tiles or wax eaters they add
+//     - wax eaters stirring before they crawl out, as they crawl out at once
+//     - the countdowns of timed mode
+//     - the assistant's easing
+pub struct Simulation {
+    pub size: TilemapSize,
+    pub map_type: TilemapType,
+    difficulty: Difficulty,
+    rules: RuleSet,
+    rng: GameRng,
+    bag: TileBag,
+    held: HeldTiles,
+    tiles: HashMap<TilePos, (TileKind, u8)>,
+    monsters: Vec<TilePos>,
+    pub hand: TileKind,
+    pub hand_rotation: u8,
+    pub nerve: u32,
+    pub turn: Turn,
+    budgets: Vec<TurnBudget>,
+    pawns: Vec<SimPawn>,
+}
+
+impl Simulation {
+    // Sets a game up the way the game does at startup, from a scenario if
+    // there is one. Only the ground floor is played out.
+    pub fn new(
+        board: BoardConfig,
+        scenario: Option<&Scenario>,
+        players: usize,
+        difficulty: Difficulty,
+        rules: RuleSet,
+        seed: Seed,
+    ) -> Simulation {
+        let mut board = board;
+        if let Some(scenario) = scenario {
+            board.size = scenario.size;
+            board.shape = scenario.shape;
+        }
+        let mut sim = Simulation {
+            size: board.size,
+            map_type: board.map_type(),
+            difficulty,
+            rules,
+            rng: GameRng::new(seed),
+            bag: TileBag {
+                mix: scenario.map_or(Vec::new(), |scenario| scenario.bag.clone()),
+                ..TileBag::default()
+            },
+            held: HeldTiles::default(),
+            tiles: HashMap::new(),
+            monsters: Vec::new(),
+            hand: TileKind::Empty,
+            hand_rotation: 0,
+            nerve: difficulty.nerve(),
+            turn: Turn::default(),
+            budgets: (0..players.max(1)).map(|_| TurnBudget::default()).collect(),
+            pawns: Vec::new(),
+        };
+        let first_draw = sim.draw();
+
+        let mut starts = Vec::new();
+        match scenario {
+            // stairs are laid the same as the game lays them, to keep the
+            // randomness in step with it
+            None => {
+                random_stairs(&board, &mut sim.rng);
+            }
+            Some(scenario) => {
+                let steps = rotation_steps(&sim.map_type);
+                for tile in scenario.tiles.iter().filter(|tile| tile.floor == 0) {
+                    sim.tiles
+                        .insert(tile.pos, (tile.kind, tile.rotation % steps));
+                }
+                starts = scenario.player_starts.clone();
+                sim.monsters = scenario.monsters.clone();
+                if let Some(next) = scenario.next {
+                    sim.hand = next;
+                }
+            }
+        }
+        starts.sort_by_key(|pos| (pos.y, pos.x));
+
+        if sim.monsters.is_empty() {
+            let mut cells = Vec::new();
+            for y in 0..sim.size.y {
+                for x in 0..sim.size.x {
+                    let pos = TilePos { x, y };
+                    if !sim.tiles.contains_key(&pos) && !starts.contains(&pos) {
+                        cells.push((pos, pos));
+                    }
+                }
+            }
+            sim.monsters = wax_eater_cells(cells, difficulty.wax_eaters(), &mut sim.rng);
+        }
+
+        sim.pawns = (0..sim.budgets.len())
+            .map(|player| SimPawn {
+                pos: start_pos(&starts, &sim.size, player),
+                keys: 0,
+            })
+            .collect();
+        let mut drawn = first_draw;
+        drawn.extend(sim.resolve_falls(&[]));
+        sim.pick_up_keys();
+        sim.wake_wax_eaters(&[], &drawn);
+        sim
+    }
+
+    // Plays out `actions` in order, returning how many of them were allowed.
+    pub fn run(&mut self, actions: impl IntoIterator<Item = Action>) -> usize {
+        actions
+            .into_iter()
+            .filter(|action| self.apply(*action))
+            .count()
+    }
+
+    // Takes an action for the current player. Returns whether the rules
+    // allowed it; refused actions change nothing.
+    pub fn apply(&mut self, action: Action) -> bool {
+        let player = self.turn.player;
+        let mut collapsed = Vec::new();
+        let mut drawn = Vec::new();
+        match action {
+            Action::Place(pos) => {
+                let on_board = pos.x < self.size.x && pos.y < self.size.y;
+                let covered = self.tiles.get(&pos).map(|(kind, _)| *kind);
+                if !on_board
+                    || (covered.is_some() && self.rules.no_collapse)
+                    || !self.budgets[player].spend()
+                {
+                    return false;
+                }
+                if let Some(kind) = covered {
+                    collapsed.push(pos);
+                    self.bag.discard(kind);
+                }
+                self.tiles.insert(pos, (self.hand, self.hand_rotation));
+                drawn = self.draw();
+            }
+            Action::Rotate => {
+                if !self.rules.unlimited_rotation && !self.budgets[player].spend() {
+                    return false;
+                }
+                self.hand_rotation = (self.hand_rotation + 1) % rotation_steps(&self.map_type);
+            }
+            Action::Move(to) => {
+                let from = self.pawns[player].pos;
+                let passages_at = |pos: &TilePos| self.placed_passages(pos);
+                if !can_move_between(
+                    &from,
+                    &to,
+                    &self.size,
+                    &self.map_type,
+                    self.wraps(),
+                    passages_at,
+                ) || !self.budgets[player].spend()
+                {
+                    return false;
+                }
+                self.pawns[player].pos = to;
+            }
+            Action::PassKey(to) => {
+                let from = self.pawns[player].pos;
+                let recipient = (0..self.pawns.len())
+                    .find(|other| *other != player && self.pawns[*other].pos == to);
+                let passages_at = |pos: &TilePos| self.placed_passages(pos);
+                let Some(recipient) = recipient else {
+                    return false;
+                };
+                if self.pawns[player].keys == 0
+                    || !can_move_between(
+                        &from,
+                        &to,
+                        &self.size,
+                        &self.map_type,
+                        self.wraps(),
+                        passages_at,
+                    )
+                    || !self.budgets[player].spend()
+                {
+                    return false;
+                }
+                self.pawns[player].keys -= 1;
+                self.pawns[recipient].keys += 1;
+            }
+            Action::EndTurn => self.budgets[player].remaining = 0,
+            Action::ExtraAction | Action::Redraw if self.nerve == 0 => return false,
+            Action::ExtraAction => {
+                self.nerve -= 1;
+                self.budgets[player].remaining += 1;
+            }
+            Action::Redraw => {
+                self.nerve -= 1;
+                self.bag.discard(self.hand);
+                drawn = self.draw();
+            }
+            Action::Swap => match self.held.swap(self.hand) {
+                Some(kind) => self.hand = kind,
+                None => return false,
+            },
+        }
+        drawn.extend(self.resolve_falls(&collapsed));
+        self.pick_up_keys();
+        self.wake_wax_eaters(&collapsed, &drawn);
+        self.advance_turn();
+        true
+    }
+
+    // Plays a move of a shared game the way watching it plays it, putting
+    // down the tile it names turned as it was. Moves off the ground floor
+    // aren't played out.
+    pub fn play(&mut self, play: Move) -> bool {
+        let action = match play {
+            Move::Place {
+                pos,
+                floor: 0,
+                kind,
+                rotation,
+            } => {
+                self.hand = kind;
+                self.hand_rotation = rotation % rotation_steps(&self.map_type);
+                Action::Place(pos)
+            }
+            Move::Turn { floor: 0, .. } => Action::Rotate,
+            Move::Walk(to) => Action::Move(to),
+            Move::PassKey(to) => Action::PassKey(to),
+            Move::Swap => Action::Swap,
+            Move::Nerve(NerveAction::ExtraAction) => Action::ExtraAction,
+            Move::Nerve(NerveAction::Redraw) => Action::Redraw,
+            Move::Pass => Action::EndTurn,
+            _ => return false,
+        };
+        self.apply(action)
+    }
+
+    pub fn tile(&self, pos: &TilePos) -> TileKind {
+        self.tiles
+            .get(pos)
+            .map_or(TileKind::Empty, |(kind, _)| *kind)
+    }
+
+    // Passages of the tile at `pos`, or none for an empty cell.
+    pub fn passages(&self, pos: &TilePos) -> Passages {
+        self.placed_passages(pos).unwrap_or(Passages::NONE)
+    }
+
+    // Every placed tile with its rotation.
+    pub fn tiles(&self) -> impl Iterator<Item = (TilePos, TileKind, u8)> + '_ {
+        self.tiles
+            .iter()
+            .map(|(pos, (kind, rotation))| (*pos, *kind, *rotation))
+    }
+
+    pub fn pawns(&self) -> &[SimPawn] {
+        &self.pawns
+    }
+
+    pub fn monsters(&self) -> &[TilePos] {
+        &self.monsters
+    }
+
+    pub fn remaining_actions(&self) -> u32 {
+        self.budgets[self.turn.player].remaining
+    }
+
+    // Cells lit by holding `kind`, turned by `rotation`, over `pos`.
+    pub fn illuminated_by(&self, pos: &TilePos, kind: TileKind, rotation: u8) -> Vec<TilePos> {
+        let candle = rotated_passages(kind, &self.map_type, rotation);
+        lit_neighbors(
+            pos,
+            candle,
+            &self.size,
+            &self.map_type,
+            self.wraps(),
+            |neighbor_pos| self.placed_passages(neighbor_pos),
+        )
+    }
+
+    // Cells lit by holding the tile in hand over `pos`.
+    pub fn illuminated(&self, pos: &TilePos) -> Vec<TilePos> {
+        self.illuminated_by(pos, self.hand, self.hand_rotation)
+    }
+
+    // Whether the board's edges wrap round to the opposite ones.
+    pub fn wraps(&self) -> bool {
+        self.rules.wrap_around
+    }
+
+    // Whether any pawn has escaped, ending the game.
+    pub fn escaped(&self) -> bool {
+        self.pawns
+            .iter()
+            .any(|pawn| escapes(self.tile(&pawn.pos), pawn.keys))
+    }
+
+    fn placed_passages(&self, pos: &TilePos) -> Option<Passages> {
+        let (kind, rotation) = self.tiles.get(pos)?;
+        Some(rotated_passages(*kind, &self.map_type, *rotation))
+    }
+
+    fn view(&self) -> BoardView {
+        let mut view = BoardView::empty(self.size, self.map_type);
+        view.wrap = self.wraps();
+        // sorted so ties between placements always break the same way
+        let mut tiles: Vec<(TilePos, TileKind, u8)> = self.tiles().collect();
+        tiles.sort_by_key(|(pos, ..)| (pos.y, pos.x));
+        for (pos, kind, _) in tiles {
+            view.insert_tile(pos, kind, self.passages(&pos));
+        }
+        for pos in &self.monsters {
+            view.insert_monster(*pos);
+        }
+        view
+    }
+
+    // Takes the next tile into hand the way the dealer does, returning every
+    // tile drawn from the bag.
+    fn draw(&mut self) -> Vec<TileKind> {
+        let (kind, drawn) = self.held.draw(
+            self.rules.hand_size(),
+            &mut self.bag,
+            self.difficulty,
+            &mut self.rng,
+        );
+        self.hand = kind;
+        drawn
+    }
+
+    // Pawns standing on a lit key tile pick its key up, as in the game.
+    fn pick_up_keys(&mut self) {
+        for player in 0..self.pawns.len() {
+            let pos = self.pawns[player].pos;
+            let passages_at = |pos: &TilePos| self.placed_passages(pos);
+            if self.tile(&pos) != TileKind::Key
+                || !key_lit(&pos, &self.size, &self.map_type, self.wraps(), passages_at)
+            {
+                continue;
+            }
+            self.tiles
+                .insert(pos, (TileKind::DeadEnd, self.tiles[&pos].1));
+            self.pawns[player].keys += 1;
+        }
+    }
+
+    // Lands the pawns that fell, or started out, in the dark on fresh tiles,
+    // returning the tiles drawn for them.
+    fn resolve_falls(&mut self, collapsed: &[TilePos]) -> Vec<TileKind> {
+        // as in the game, everyone falling at once lands on the board as it
+        // was before any of them landed
+        let mut view: Option<BoardView> = None;
+        let mut landed = Vec::new();
+        let mut drawn = Vec::new();
+        for player in 0..self.pawns.len() {
+            let pos = self.pawns[player].pos;
+            if self.tiles.contains_key(&pos) && !collapsed.contains(&pos) {
+                continue;
+            }
+
+            self.pawns[player].keys = 0;
+            let kind = self.bag.draw(self.difficulty, &mut self.rng);
+            drawn.push(kind);
+            let view = view.get_or_insert_with(|| self.view());
+            let Some(landing) = landing(view, kind, &landed) else {
+                continue;
+            };
+            self.tiles.insert(landing.pos, (kind, landing.rotation));
+            landed.push(landing.pos);
+            self.pawns[player].pos = landing.pos;
+        }
+        drawn
+    }
+
+    // Wax eaters wake as they do in the game, except that they crawl out at
+    // once rather than stirring in the dark for a while first, lunging at the
+    // pawns beside them as they do.
+    fn wake_wax_eaters(&mut self, collapsed: &[TilePos], drawn: &[TileKind]) {
+        if self.rules.friendly_monsters {
+            return;
+        }
+        let pawns: Vec<TilePos> = self.pawns.iter().map(|pawn| pawn.pos).collect();
+        let mut taken = pawns.clone();
+        taken.extend(&self.monsters);
+        let frontier = dark_frontier(
+            &self.size,
+            &self.map_type,
+            self.wraps(),
+            |pos| self.tile(pos) != TileKind::Empty,
+            &taken,
+        );
+        let woken = waking_cells(
+            (&self.size, &self.map_type, self.wraps()),
+            frontier,
+            collapsed,
+            drawn,
+            &pawns,
+            self.monsters.len(),
+            self.difficulty,
+        );
+        let mut cells: Vec<(TilePos, u32)> = self
+            .pawns
+            .iter()
+            .map(|pawn| (pawn.pos, pawn.keys))
+            .collect();
+        land_hits(
+            (&self.size, &self.map_type, self.wraps()),
+            &woken,
+            &mut cells,
+            &mut self.nerve,
+        );
+        for (pawn, (_, keys)) in self.pawns.iter_mut().zip(cells) {
+            pawn.keys = keys;
+        }
+        self.monsters.extend(woken);
+    }
+
+    fn advance_turn(&mut self) {
+        if self.budgets[self.turn.player].remaining > 0 {
+            return;
+        }
+        self.turn.advance(self.budgets.len());
+        self.budgets[self.turn.player] = TurnBudget::default();
+    }
+}
@@ -0,0 +1,189 @@
+use std::path::Path;
+
+use bevy::{
+    ecs::system::SystemParam,
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+};
+use bevy_ecs_tilemap::prelude::*;
+
+use crate::board::{HexRotation, Rotation, TileType};
+use crate::floor::{CurrentFloor, Floor, ShownFloor};
+use crate::pack::TilePack;
+use crate::palette::Palette;
+use crate::tiles::TileKind;
+
+// One cell of the board as it's drawn into the image.
+struct DrawnTile {
+    // In artwork pixels, the same space the tilemap lays its grid out in.
+    center: Vec2,
+    texture_index: u32,
+    // Clockwise quarter turns of the artwork.
+    quarter_turns: u8,
+}
+
+type BoardTileQuery = (
+    &'static TilePos,
+    &'static Floor,
+    Option<&'static TileType>,
+    &'static Rotation,
+    Option<&'static HexRotation>,
+);
+
+// Everything needed to draw the board into an image. The board is put together
+// from the tile artwork on the cpu rather than read back from the screen, so
+// it comes out whole and at full size wherever the camera is and however far
+// it's zoomed. Only the floor on show is drawn.
+#[derive(SystemParam)]
+pub struct BoardRenderer<'w, 's> {
+    asset_server: Res<'w, AssetServer>,
+    pack: Res<'w, TilePack>,
+    palette: Res<'w, Palette>,
+    current_floor: Res<'w, CurrentFloor>,
+    tilemap_q: Query<'w, 's, (&'static TilemapGridSize, &'static TilemapType), With<ShownFloor>>,
+    tiles_q: Query<'w, 's, BoardTileQuery>,
+}
+
+impl<'w, 's> BoardRenderer<'w, 's> {
+    pub fn render(&self, images: &Assets<Image>) -> Result<Image, String> {
+        let atlas = images
+            .get(&self.asset_server.load::<Image>(self.pack.tiles.clone()))
+            .ok_or("the tile artwork hasn't loaded yet")?;
+        let (grid_size, map_type) = self
+            .tilemap_q
+            .get_single()
+            .map_err(|_| "there's no board to draw")?;
+        let tiles: Vec<DrawnTile> = self
+            .tiles_q
+            .iter()
+            .filter(|(_, floor, ..)| self.current_floor.shows(floor))
+            .map(|(pos, _, tile_type, rotation, hex_rotation)| DrawnTile {
+                center: pos.center_in_world(grid_size, map_type),
+                texture_index: self
+                    .pack
+                    .animation(tile_type.map_or(TileKind::Empty, |tile_type| tile_type.kind))
+                    .frames[0],
+                // hex tiles are turned by the passages drawn over them, not
+                // by turning the artwork
+                quarter_turns: if hex_rotation.is_some() {
+                    0
+                } else {
+                    rotation.0.turns()
+                },
+            })
+            .collect();
+        render_board(
+            atlas,
+            self.pack.tile_size as u32,
+            &tiles,
+            self.palette.background(),
+        )
+    }
+}
+
+pub fn export_board(renderer: BoardRenderer, images: Res<Assets<Image>>, mut exports: Local<u32>) {
+    // Don't overwrite exports from earlier sessions.
+    let path = loop {
+        *exports += 1;
+        let path = format!("nightcage-board-{}.png", *exports);
+        if !Path::new(&path).exists() {
+            break path;
+        }
+    };
+
+    match renderer
+        .render(&images)
+        .and_then(|board| save_png(board, &path))
+    {
+        Ok(()) => info!("exported the board to {}", path),
+        Err(err) => warn!("failed to export the board to {}: {}", path, err),
+    }
+}
+
+// Draws a single tile of the artwork over `background`, turned clockwise by
+// `quarter_turns` as the board would turn it.
+pub fn render_tile(
+    atlas: &Image,
+    pack: &TilePack,
+    kind: TileKind,
+    quarter_turns: u8,
+    background: Color,
+) -> Result<Image, String> {
+    let tile = DrawnTile {
+        center: Vec2::ZERO,
+        texture_index: pack.animation(kind).frames[0],
+        quarter_turns,
+    };
+    render_board(atlas, pack.tile_size as u32, &[tile], background)
+}
+
+fn render_board(
+    atlas: &Image,
+    tile_pixels: u32,
+    tiles: &[DrawnTile],
+    background: Color,
+) -> Result<Image, String> {
+    let (atlas_width, atlas_height) = (atlas.width(), atlas.height());
+    if atlas.data.len() != (atlas_width * atlas_height * 4) as usize {
+        return Err("the tile artwork isn't 8-bit rgba".to_string());
+    }
+    let columns = atlas_width / tile_pixels;
+    let Some(first) = tiles.first() else {
+        return Err("the board is empty".to_string());
+    };
+    let (min, max) = tiles
+        .iter()
+        .fold((first.center, first.center), |(min, max), tile| {
+            (min.min(tile.center), max.max(tile.center))
+        });
+
+    let width = (max.x - min.x).round() as u32 + tile_pixels;
+    let height = (max.y - min.y).round() as u32 + tile_pixels;
+    let mut data = background.as_rgba_u8().repeat((width * height) as usize);
+    for tile in tiles {
+        // images run top down, the board bottom up
+        let left = (tile.center.x - min.x).round() as u32;
+        let top = (max.y - tile.center.y).round() as u32;
+        let atlas_x = tile.texture_index % columns * tile_pixels;
+        let atlas_y = tile.texture_index / columns * tile_pixels;
+        for y in 0..tile_pixels {
+            for x in 0..tile_pixels {
+                // find the artwork pixel that turning lands here
+                let (mut from_x, mut from_y) = (x, y);
+                for _ in 0..tile.quarter_turns % 4 {
+                    (from_x, from_y) = (from_y, tile_pixels - 1 - from_x);
+                }
+                let from = (((atlas_y + from_y) * atlas_width + atlas_x + from_x) * 4) as usize;
+                let to = (((top + y) * width + left + x) * 4) as usize;
+                blend(&mut data[to..to + 4], &atlas.data[from..from + 4]);
+            }
+        }
+    }
+
+    Ok(Image::new(
+        Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+    ))
+}
+
+// Lays an rgba pixel over an opaque one.
+fn blend(under: &mut [u8], over: &[u8]) {
+    let alpha = over[3] as u32;
+    for (under, over) in under.iter_mut().zip(over).take(3) {
+        *under = ((*over as u32 * alpha + *under as u32 * (255 - alpha)) / 255) as u8;
+    }
+    under[3] = 255;
+}
+
+fn save_png(image: Image, path: &str) -> Result<(), String> {
+    let image = image
+        .try_into_dynamic()
+        .map_err(|err| format!("{:?}", err))?;
+    image.save(path).map_err(|err| err.to_string())
+}
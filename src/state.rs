@@ -0,0 +1,23 @@
+use bevy::prelude::*;
+
+#[derive(States, Debug, Clone, Copy, Default, Eq, PartialEq, Hash)]
+pub enum GameState {
+    // The board and players are being set up for a new game.
+    #[default]
+    Setup,
+    Playing,
+    // Gameplay is frozen and the camera roams freely for taking screenshots.
+    Photo,
+    // The board is painted freely, without fog, and exported as a scenario.
+    Editor,
+    // Gameplay is frozen behind the pause menu.
+    Paused,
+    // Somebody has escaped and the game's summary is up.
+    GameOver,
+}
+
+// Whether the board is taking input from the player, as opposed to being
+// looked at through a menu or photo mode.
+pub fn taking_input(state: Res<State<GameState>>) -> bool {
+    matches!(state.get(), GameState::Playing | GameState::Editor)
+}
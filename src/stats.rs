@@ -0,0 +1,291 @@
+use bevy::prelude::*;
+
+use crate::achievements::{Achievement, Achievements};
+use crate::falling::PawnFell;
+use crate::hud::{format_duration, spawn_panel};
+use crate::locale::{parse, Strings};
+use crate::pawn::{Escaped, KeyPickedUp};
+use crate::rules::{Bot, Player, TilePlaced, Turn};
+use crate::save::Format;
+
+pub const DEFAULT_PROFILE: &str = "default";
+
+// Each profile's stats are kept in a file of their own beside the game:
+//
+//     # nightcage stats 1
+//     games-played = 12
+//     wins = 5
+//     # seconds, only there once a game has been won
+//     best-clear = 431.5
+//     best-timed = 388.2
+//     tiles-placed = 208
+//     candles-lost = 17
+//     # games lost in a row since the last win
+//     losing-streak = 2
+const STATS_FORMAT: Format = Format {
+    name: "stats",
+    migrations: &[],
+};
+
+fn stats_path(profile: &str) -> String {
+    format!("nightcage-stats-{}.txt", profile)
+}
+
+// Totals over every finished game played under a profile. Only the local
+// human's placements and falls count towards them.
+#[derive(Resource, Clone, Debug, Default, PartialEq)]
+pub struct Stats {
+    pub profile: String,
+    pub games_played: u32,
+    pub wins: u32,
+    // Quickest win, in seconds.
+    pub best_clear: Option<f32>,
+    // Quickest win in timed mode, counting only time spent playing.
+    pub best_timed: Option<f32>,
+    pub tiles_placed: u32,
+    pub candles_lost: u32,
+    pub losing_streak: u32,
+}
+
+impl Stats {
+    // Loads the profile's stats, starting afresh when it has none yet or
+    // they can't be read, as on the web.
+    pub fn load(profile: &str) -> Stats {
+        Stats::read(profile).unwrap_or_else(|err| {
+            warn!("{}", err);
+            Stats::fresh(profile)
+        })
+    }
+
+    fn fresh(profile: &str) -> Stats {
+        Stats {
+            profile: profile.to_string(),
+            ..default()
+        }
+    }
+
+    fn read(profile: &str) -> Result<Stats, String> {
+        let mut stats = Stats::fresh(profile);
+        let path = stats_path(profile);
+        let source = match std::fs::read_to_string(&path) {
+            Ok(source) => source,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(stats),
+            Err(err) => return Err(format!("failed to read {}: {}", path, err)),
+        };
+        let source = STATS_FORMAT
+            .upgrade(&source)
+            .map_err(|err| format!("{}: {}", path, err))?;
+        let table = parse(&source);
+        let count = |key: &str| match table.get(key) {
+            Some(value) => value
+                .parse::<u32>()
+                .map_err(|_| format!("invalid {} {} in {}", key, value, path)),
+            None => Ok(0),
+        };
+        stats.games_played = count("games-played")?;
+        stats.wins = count("wins")?;
+        stats.tiles_placed = count("tiles-placed")?;
+        stats.candles_lost = count("candles-lost")?;
+        stats.losing_streak = count("losing-streak")?;
+        let seconds = |key: &str| {
+            table
+                .get(key)
+                .map(|value| {
+                    value
+                        .parse::<f32>()
+                        .map_err(|_| format!("invalid {} {} in {}", key, value, path))
+                })
+                .transpose()
+        };
+        stats.best_clear = seconds("best-clear")?;
+        stats.best_timed = seconds("best-timed")?;
+        Ok(stats)
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let mut source = STATS_FORMAT.header();
+        source.push_str(&format!(
+            "games-played = {}\nwins = {}\n",
+            self.games_played, self.wins
+        ));
+        if let Some(best_clear) = self.best_clear {
+            source.push_str(&format!("best-clear = {}\n", best_clear));
+        }
+        if let Some(best_timed) = self.best_timed {
+            source.push_str(&format!("best-timed = {}\n", best_timed));
+        }
+        source.push_str(&format!(
+            "tiles-placed = {}\ncandles-lost = {}\nlosing-streak = {}\n",
+            self.tiles_placed, self.candles_lost, self.losing_streak
+        ));
+        let path = stats_path(&self.profile);
+        std::fs::write(&path, source).map_err(|err| format!("failed to write {}: {}", path, err))
+    }
+}
+
+// The game in progress, added to the profile's stats once somebody escapes.
+#[derive(Resource, Default)]
+pub struct GameTally {
+    // Turns begun, by anyone.
+    pub turns: u32,
+    pub tiles_placed: u32,
+    pub keys_collected: u32,
+    pub candles_lost: u32,
+    // Seconds since startup when the game began.
+    pub started: f32,
+    pub outcome: Option<GameEnded>,
+}
+
+impl GameTally {
+    pub fn ended(&self) -> bool {
+        self.outcome.is_some()
+    }
+}
+
+pub fn start_tally(time: Res<Time>, mut tally: ResMut<GameTally>) {
+    *tally = GameTally {
+        started: time.elapsed_seconds(),
+        ..default()
+    };
+}
+
+#[derive(Component)]
+pub struct StatsScreen;
+
+#[derive(Component)]
+pub struct StatsText;
+
+pub fn spawn_stats_screen(mut commands: Commands) {
+    spawn_panel(&mut commands, StatsScreen, StatsText);
+}
+
+pub fn tally_game(
+    turn: Res<Turn>,
+    mut tally: ResMut<GameTally>,
+    bots_q: Query<&Player, With<Bot>>,
+    mut placed_events: EventReader<TilePlaced>,
+    mut picked_up_events: EventReader<KeyPickedUp>,
+    mut fell_events: EventReader<PawnFell>,
+) {
+    let is_bot = |number: usize| bots_q.iter().any(|player| player.number == number);
+    let placed = placed_events
+        .read()
+        .filter(|placed| !is_bot(placed.player))
+        .count();
+    let picked_up = picked_up_events
+        .read()
+        .filter(|picked_up| !is_bot(picked_up.player))
+        .count();
+    let fell = fell_events
+        .read()
+        .filter(|fell| !is_bot(fell.player))
+        .count();
+    // whatever happens after the game is over doesn't count
+    if tally.ended() {
+        return;
+    }
+    if turn.is_changed() {
+        tally.turns += 1;
+    }
+    tally.tiles_placed += placed as u32;
+    tally.keys_collected += picked_up as u32;
+    tally.candles_lost += fell as u32;
+}
+
+// The end of the game, for anything wrapping it up.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct GameEnded {
+    // Who escaped.
+    pub player: usize,
+    // Whether that was the local human.
+    pub won: bool,
+}
+
+// The first escape ends the game. It's a win when the local human got out.
+pub fn record_game_end(
+    time: Res<Time>,
+    mut tally: ResMut<GameTally>,
+    mut stats: ResMut<Stats>,
+    bots_q: Query<&Player, With<Bot>>,
+    mut escaped_events: EventReader<Escaped>,
+    mut ended_events: EventWriter<GameEnded>,
+) {
+    let Some(escaped) = escaped_events.read().next().copied() else {
+        return;
+    };
+    if tally.ended() {
+        return;
+    }
+    let won = !bots_q.iter().any(|player| player.number == escaped.player);
+    let outcome = GameEnded {
+        player: escaped.player,
+        won,
+    };
+    tally.outcome = Some(outcome);
+
+    stats.games_played += 1;
+    stats.tiles_placed += tally.tiles_placed;
+    stats.candles_lost += tally.candles_lost;
+    stats.losing_streak = if won { 0 } else { stats.losing_streak + 1 };
+    if won {
+        stats.wins += 1;
+        let clear = time.elapsed_seconds() - tally.started;
+        stats.best_clear = Some(stats.best_clear.map_or(clear, |best| best.min(clear)));
+    }
+    if let Err(err) = stats.save() {
+        warn!("{}", err);
+    }
+    ended_events.send(outcome);
+}
+
+// show or hide the stats screen on pressing i
+pub fn toggle_stats_screen(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut screen_q: Query<&mut Visibility, With<StatsScreen>>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::I) {
+        return;
+    }
+    for mut visibility in screen_q.iter_mut() {
+        *visibility = match *visibility {
+            Visibility::Hidden => Visibility::Inherited,
+            _ => Visibility::Hidden,
+        };
+    }
+}
+
+pub fn stats_screen_open(screen_q: Query<&Visibility, With<StatsScreen>>) -> bool {
+    screen_q
+        .iter()
+        .any(|visibility| *visibility != Visibility::Hidden)
+}
+
+pub fn update_stats_screen(
+    stats: Res<Stats>,
+    achievements: Res<Achievements>,
+    strings: Res<Strings>,
+    mut text_q: Query<&mut Text, With<StatsText>>,
+) {
+    if !stats.is_changed() && !achievements.is_changed() && !strings.is_changed() {
+        return;
+    }
+    let best =
+        |best: Option<f32>| best.map_or_else(|| strings.get("stats-no-best"), format_duration);
+    let value = strings.format(
+        "stats-screen",
+        &[
+            ("profile", stats.profile.clone()),
+            ("played", stats.games_played.to_string()),
+            ("wins", stats.wins.to_string()),
+            ("best", best(stats.best_clear)),
+            ("timed", best(stats.best_timed)),
+            ("tiles", stats.tiles_placed.to_string()),
+            ("candles", stats.candles_lost.to_string()),
+            ("unlocked", achievements.unlocked.len().to_string()),
+            ("total", Achievement::ALL.len().to_string()),
+        ],
+    );
+    for mut text in text_q.iter_mut() {
+        text.sections[0].value = value.clone();
+    }
+}
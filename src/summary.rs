@@ -0,0 +1,163 @@
+use bevy::prelude::*;
+
+use crate::assist::{Assistant, Easing};
+use crate::daily::Daily;
+use crate::locale::Strings;
+use crate::pause::{spawn_button, MenuButton};
+use crate::snapshot::BoardRenderer;
+use crate::state::GameState;
+use crate::stats::{GameEnded, GameTally, Stats};
+use crate::timed::Timed;
+
+// Width of the board's thumbnail, in pixels. Its height follows the board's.
+const THUMBNAIL_WIDTH: f32 = 240.0;
+
+#[derive(Component)]
+pub struct Summary;
+
+pub fn end_game(
+    mut ended_events: EventReader<GameEnded>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if ended_events.read().next().is_some() {
+        next_state.set(GameState::GameOver);
+    }
+}
+
+// How the game went, over a picture of the board as it was left.
+pub fn spawn_summary(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    renderer: BoardRenderer,
+    strings: Res<Strings>,
+    tally: Res<GameTally>,
+    (timed, daily): (Res<Timed>, Res<Daily>),
+    (assistant, stats): (Res<Assistant>, Res<Stats>),
+) {
+    let Some(outcome) = tally.outcome else {
+        return;
+    };
+    let title = if outcome.won {
+        strings.get("summary-won")
+    } else {
+        strings.format(
+            "summary-lost",
+            &[("number", (outcome.player + 1).to_string())],
+        )
+    };
+    let mut details = strings.format(
+        "summary-details",
+        &[
+            ("turns", tally.turns.to_string()),
+            ("tiles", tally.tiles_placed.to_string()),
+            ("keys", tally.keys_collected.to_string()),
+            ("candles", tally.candles_lost.to_string()),
+        ],
+    );
+    if timed.enabled {
+        details.push('\n');
+        details.push_str(&timed.results(outcome.won, &strings));
+    }
+    if let (Some(results), Some(share)) = (daily.results(&strings), daily.share(&tally, &strings)) {
+        details.push_str(&format!("\n{}\n{}", results, share));
+    }
+    if let Some(easing) = assistant.offer {
+        details.push('\n');
+        details.push_str(&assistant_offer(easing, stats.losing_streak, &strings));
+    }
+    let thumbnail = match renderer.render(&images) {
+        Ok(board) => {
+            let height = THUMBNAIL_WIDTH * board.height() as f32 / board.width() as f32;
+            Some((images.add(board), height))
+        }
+        Err(err) => {
+            warn!("no thumbnail of the board for the summary: {}", err);
+            None
+        }
+    };
+    let text_style = |font_size: f32| TextStyle {
+        font_size,
+        color: Color::ORANGE,
+        ..default()
+    };
+
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                background_color: Color::BLACK.with_a(0.5).into(),
+                ..default()
+            },
+            // so the board underneath can't be clicked through the summary
+            Interaction::default(),
+            Summary,
+        ))
+        .with_children(|summary| {
+            summary
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        row_gap: Val::Px(12.0),
+                        padding: UiRect::all(Val::Px(16.0)),
+                        ..default()
+                    },
+                    background_color: Color::BLACK.with_a(0.8).into(),
+                    ..default()
+                })
+                .with_children(|panel| {
+                    panel.spawn(TextBundle::from_section(title, text_style(32.0)));
+                    if let Some((image, height)) = thumbnail {
+                        panel.spawn(ImageBundle {
+                            style: Style {
+                                width: Val::Px(THUMBNAIL_WIDTH),
+                                height: Val::Px(height),
+                                ..default()
+                            },
+                            image: UiImage::new(image),
+                            ..default()
+                        });
+                    }
+                    panel.spawn(TextBundle::from_section(details, text_style(24.0)));
+                    if assistant.offer.is_some() {
+                        spawn_button(panel, MenuButton::Ease);
+                    }
+                    spawn_button(panel, MenuButton::Rematch);
+                    spawn_button(panel, MenuButton::NewGame);
+                    spawn_button(panel, MenuButton::Quit);
+                });
+        });
+}
+
+// The assistant's offer to ease the next game, with why and how.
+fn assistant_offer(easing: Easing, losing_streak: u32, strings: &Strings) -> String {
+    let extra_key = strings.get("ease-extra-key");
+    let easing = if easing.slow_wax_eaters {
+        strings.format(
+            "ease-and",
+            &[
+                ("first", extra_key),
+                ("second", strings.get("ease-slow-wax-eaters")),
+            ],
+        )
+    } else {
+        extra_key
+    };
+    strings.format(
+        "summary-ease",
+        &[("losses", losing_streak.to_string()), ("easing", easing)],
+    )
+}
+
+pub fn despawn_summary(mut commands: Commands, summary_q: Query<Entity, With<Summary>>) {
+    for summary in summary_q.iter() {
+        commands.entity(summary).despawn_recursive();
+    }
+}
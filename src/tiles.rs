@@ -0,0 +1,333 @@
+use bevy_ecs_tilemap::prelude::*;
+
+// The four sides of a square tile a corridor can leave through.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Side {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Side {
+    // Clockwise order, so rotating a side is just stepping through this list.
+    pub const ALL: [Side; 4] = [Side::North, Side::East, Side::South, Side::West];
+
+    pub fn opposite(self) -> Side {
+        self.rotated(2)
+    }
+
+    pub fn rotated(self, quarter_turns: u8) -> Side {
+        Side::ALL[(self as usize + quarter_turns as usize) % 4]
+    }
+
+    // The position across this side of `pos`, if it is still on the map.
+    pub fn neighbor(self, pos: &TilePos, map_size: &TilemapSize) -> Option<TilePos> {
+        match self {
+            Side::North if pos.y + 1 < map_size.y => Some(TilePos::new(pos.x, pos.y + 1)),
+            Side::East if pos.x + 1 < map_size.x => Some(TilePos::new(pos.x + 1, pos.y)),
+            Side::South if pos.y > 0 => Some(TilePos::new(pos.x, pos.y - 1)),
+            Side::West if pos.x > 0 => Some(TilePos::new(pos.x - 1, pos.y)),
+            _ => None,
+        }
+    }
+
+    // The position across this side of `pos` on a board whose edges wrap round
+    // to the opposite ones.
+    pub fn wrapped_neighbor(self, pos: &TilePos, map_size: &TilemapSize) -> TilePos {
+        match self {
+            Side::North => TilePos::new(pos.x, (pos.y + 1) % map_size.y),
+            Side::East => TilePos::new((pos.x + 1) % map_size.x, pos.y),
+            Side::South => TilePos::new(pos.x, (pos.y + map_size.y - 1) % map_size.y),
+            Side::West => TilePos::new((pos.x + map_size.x - 1) % map_size.x, pos.y),
+        }
+    }
+
+    const fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+// The set of sides of a tile that have an open corridor.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Passages(u8);
+
+impl Passages {
+    pub const NONE: Passages = Passages(0);
+    pub const ALL: Passages = Passages(0b1111);
+    pub const HEX_ALL: Passages = Passages(0b111111);
+
+    pub const fn from_sides(sides: &[Side]) -> Passages {
+        let mut bits = 0;
+        let mut i = 0;
+        while i < sides.len() {
+            bits |= sides[i].bit();
+            i += 1;
+        }
+        Passages(bits)
+    }
+
+    pub fn contains(self, side: Side) -> bool {
+        self.0 & side.bit() != 0
+    }
+
+    // One bit a side, north's or east's the lowest.
+    pub fn bits(self) -> u8 {
+        self.0
+    }
+
+    pub fn rotated(self, quarter_turns: u8) -> Passages {
+        let turns = quarter_turns % 4;
+        Passages(((self.0 << turns) | (self.0 >> (4 - turns))) & Passages::ALL.0)
+    }
+
+    // Hex boards number their six sides like bevy_ecs_tilemap's `HexDirection`,
+    // counter-clockwise from east.
+    pub const fn from_hex_directions(directions: &[u8]) -> Passages {
+        let mut bits = 0;
+        let mut i = 0;
+        while i < directions.len() {
+            bits |= 1 << directions[i];
+            i += 1;
+        }
+        Passages(bits)
+    }
+
+    pub fn contains_hex_direction(self, direction: u8) -> bool {
+        self.0 & (1 << direction) != 0
+    }
+
+    pub fn rotated_hex(self, steps: u8) -> Passages {
+        let steps = steps % 6;
+        Passages(((self.0 << steps) | (self.0 >> (6 - steps))) & Passages::HEX_ALL.0)
+    }
+
+    pub fn sides(self) -> impl Iterator<Item = Side> {
+        Side::ALL
+            .into_iter()
+            .filter(move |side| self.contains(*side))
+    }
+
+    pub fn hex_directions(self) -> impl Iterator<Item = u8> {
+        (0..6).filter(move |direction| self.contains_hex_direction(*direction))
+    }
+
+    pub fn count(self) -> u32 {
+        self.0.count_ones()
+    }
+
+    // Whether a corridor runs from this tile through `side` into a tile with `other` passages.
+    pub fn connects(self, side: Side, other: Passages) -> bool {
+        self.contains(side) && other.contains(side.opposite())
+    }
+}
+
+// Frames of a tile's artwork, as texture indices into tiles.png, shown in turn
+// for `frame_seconds` each. A single frame is a still tile.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TileAnimation {
+    pub frames: Vec<u32>,
+    pub frame_seconds: f32,
+}
+
+impl TileAnimation {
+    pub fn still(frame: u32) -> TileAnimation {
+        TileAnimation {
+            frames: vec![frame],
+            frame_seconds: 0.0,
+        }
+    }
+
+    pub fn is_animated(&self) -> bool {
+        self.frames.len() > 1
+    }
+
+    // The frame showing `seconds` into the game.
+    pub fn frame_at(&self, seconds: f32) -> u32 {
+        if !self.is_animated() {
+            return self.frames[0];
+        }
+        let frame = (seconds / self.frame_seconds) as usize;
+        self.frames[frame % self.frames.len()]
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TileKind {
+    Empty,
+    Straight,
+    Corner,
+    TJunction,
+    Crossing,
+    DeadEnd,
+    Key,
+    Gate,
+    // A pillar chamber where a wax eater lurks.
+    Pillar,
+}
+
+impl TileKind {
+    // Every kind that can be placed on the board, in the order the next tile cycles through.
+    pub const PLACEABLE: [TileKind; 8] = [
+        TileKind::Straight,
+        TileKind::Corner,
+        TileKind::TJunction,
+        TileKind::Crossing,
+        TileKind::DeadEnd,
+        TileKind::Key,
+        TileKind::Gate,
+        TileKind::Pillar,
+    ];
+
+    // The kind's artwork in the built-in tiles.png, where the wax eater in the
+    // pillar chamber writhes; asset packs can list their own.
+    pub fn animation(self) -> TileAnimation {
+        match self {
+            TileKind::Pillar => TileAnimation {
+                frames: vec![1, 39, 40, 1, 41],
+                frame_seconds: 0.25,
+            },
+            _ => TileAnimation::still(self.still()),
+        }
+    }
+
+    // The kind's artwork as the tile in hand, flickering in the candlelight.
+    // Each kind's three flicker frames follow on from 15 in the order of their
+    // stills, and are shown out of step so the flame doesn't seem to pulse.
+    pub fn held_animation(self) -> TileAnimation {
+        let still = self.still();
+        if still == 0 {
+            return TileAnimation::still(still);
+        }
+        let flicker = 15 + (still - 1) * 3;
+        TileAnimation {
+            frames: vec![
+                still,
+                flicker,
+                still,
+                flicker + 2,
+                flicker + 1,
+                still,
+                flicker,
+                flicker + 2,
+            ],
+            frame_seconds: 0.09,
+        }
+    }
+
+    // Texture index of the kind's still artwork in tiles.png.
+    fn still(self) -> u32 {
+        match self {
+            TileKind::Empty => 0,
+            TileKind::Pillar => 1,
+            TileKind::Straight => 2,
+            TileKind::TJunction => 3,
+            TileKind::Crossing => 4,
+            TileKind::Corner => 5,
+            TileKind::DeadEnd => 6,
+            TileKind::Key => 7,
+            TileKind::Gate => 8,
+        }
+    }
+
+    // Name used for the kind in scenario files.
+    pub fn name(self) -> &'static str {
+        match self {
+            TileKind::Empty => "empty",
+            TileKind::Straight => "straight",
+            TileKind::Corner => "corner",
+            TileKind::TJunction => "t-junction",
+            TileKind::Crossing => "crossing",
+            TileKind::DeadEnd => "dead-end",
+            TileKind::Key => "key",
+            TileKind::Gate => "gate",
+            TileKind::Pillar => "pillar",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<TileKind> {
+        std::iter::once(TileKind::Empty)
+            .chain(TileKind::PLACEABLE)
+            .find(|kind| kind.name() == name)
+    }
+
+    // Passages of the unrotated tile, matching its artwork in tiles.png.
+    pub fn passages(self) -> Passages {
+        use Side::*;
+        match self {
+            TileKind::Empty => Passages::NONE,
+            TileKind::Straight | TileKind::Gate => Passages::from_sides(&[North, South]),
+            TileKind::Corner => Passages::from_sides(&[East, South]),
+            TileKind::TJunction => Passages::from_sides(&[East, South, West]),
+            TileKind::Pillar => Passages::from_sides(&[South, West]),
+            TileKind::Crossing => Passages::ALL,
+            TileKind::DeadEnd | TileKind::Key => Passages::from_sides(&[South]),
+        }
+    }
+
+    // Passages of the tile turned `quarter` from its artwork.
+    pub fn passages_with(self, quarter: Quarter) -> Passages {
+        self.passages().rotated(quarter.turns())
+    }
+
+    // Passages of the unrotated tile on a hex board. Square bends and junctions
+    // become their closest six-sided equivalents.
+    pub fn hex_passages(self) -> Passages {
+        match self {
+            TileKind::Empty => Passages::NONE,
+            TileKind::Straight | TileKind::Gate => Passages::from_hex_directions(&[0, 3]),
+            TileKind::Corner => Passages::from_hex_directions(&[0, 2]),
+            TileKind::TJunction => Passages::from_hex_directions(&[0, 2, 4]),
+            TileKind::Pillar => Passages::from_hex_directions(&[2, 4]),
+            TileKind::Crossing => Passages::HEX_ALL,
+            TileKind::DeadEnd | TileKind::Key => Passages::from_hex_directions(&[0]),
+        }
+    }
+
+    pub fn next(self) -> TileKind {
+        let index = TileKind::PLACEABLE
+            .iter()
+            .position(|kind| *kind == self)
+            .map_or(0, |index| index + 1);
+        TileKind::PLACEABLE[index % TileKind::PLACEABLE.len()]
+    }
+}
+
+// Clockwise quarter turns from a square tile's artwork.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Quarter {
+    #[default]
+    Zero,
+    One,
+    Two,
+    Three,
+}
+
+impl Quarter {
+    pub const ALL: [Quarter; 4] = [Quarter::Zero, Quarter::One, Quarter::Two, Quarter::Three];
+
+    // Turns past a whole one come back round.
+    pub fn from_turns(turns: u8) -> Quarter {
+        Quarter::ALL[turns as usize % 4]
+    }
+
+    pub fn turns(self) -> u8 {
+        self as u8
+    }
+
+    pub fn next(self) -> Quarter {
+        Quarter::from_turns(self.turns() + 1)
+    }
+
+    // The artwork can't be turned, only flipped, so a tile is drawn turned
+    // with the flips that come to the same thing.
+    pub fn flip(self) -> TileFlip {
+        let (x, y, d) = match self {
+            Quarter::Zero => (false, false, false),
+            Quarter::One => (true, false, true),
+            Quarter::Two => (true, true, false),
+            Quarter::Three => (false, true, true),
+        };
+        TileFlip { x, y, d }
+    }
+}
@@ -0,0 +1,257 @@
+use bevy::prelude::*;
+
+use crate::ai::{placements, BoardView};
+use crate::board::NextTileKind;
+use crate::difficulty::Difficulty;
+use crate::hint::Hint;
+use crate::hud::format_duration;
+use crate::inspect::BoardInspector;
+use crate::locale::Strings;
+use crate::pawn::Pawn;
+use crate::rules::{Bot, PlaceTile, Player, Turn, TurnBudget, TurnPassed};
+use crate::stats::{GameEnded, GameTally, Stats};
+
+// Size of the bar the turn's countdown burns down along.
+const BAR_WIDTH: f32 = 240.0;
+const BAR_HEIGHT: f32 = 8.0;
+// Share of the turn left when the bar turns red.
+const BAR_WARNING: f32 = 0.25;
+
+// What becomes of a turn that runs out of time, set with --timeout.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Timeout {
+    // Whatever actions are left go unspent.
+    #[default]
+    Pass,
+    // The tile in hand goes where a hint would put it, and then the turn
+    // passes.
+    Place,
+}
+
+impl Timeout {
+    pub fn from_name(name: &str) -> Option<Timeout> {
+        match name {
+            "pass" => Some(Timeout::Pass),
+            "place" => Some(Timeout::Place),
+            _ => None,
+        }
+    }
+}
+
+// In timed mode every turn is played against a countdown as long as the
+// difficulty allows, and the turn passes on by itself once it runs out. Both
+// clocks only run while the game is being played, not while a menu or screen
+// is up.
+#[derive(Resource)]
+pub struct Timed {
+    pub enabled: bool,
+    pub timeout: Timeout,
+    pub turn: Timer,
+    // Seconds played this game.
+    pub total: f32,
+    // The best time before this game's, once it has been won.
+    best_before: Option<f32>,
+}
+
+impl Timed {
+    pub fn new(enabled: bool, timeout: Timeout, difficulty: Difficulty) -> Self {
+        Self {
+            enabled,
+            timeout,
+            turn: Timer::from_seconds(difficulty.turn_seconds(), TimerMode::Once),
+            total: 0.0,
+            best_before: None,
+        }
+    }
+
+    // How the game went against the clock, for the summary at its end.
+    pub fn results(&self, won: bool, strings: &Strings) -> String {
+        let time = format_duration(self.total);
+        match self.best_before {
+            _ if !won => strings.format("timed-lost", &[("time", time)]),
+            Some(best) if best <= self.total => strings.format(
+                "timed-won",
+                &[("time", time), ("best", format_duration(best))],
+            ),
+            _ => strings.format("timed-new-best", &[("time", time)]),
+        }
+    }
+}
+
+pub fn enabled(timed: Res<Timed>) -> bool {
+    timed.enabled
+}
+
+#[derive(Component)]
+pub struct TimerText;
+
+// The candle burning down as the turn's time runs out.
+#[derive(Component)]
+pub struct TimerBar;
+
+pub fn spawn_timer_text(mut commands: Commands) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(8.0),
+                width: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(|row| {
+            row.spawn(NodeBundle {
+                style: Style {
+                    width: Val::Px(BAR_WIDTH),
+                    height: Val::Px(BAR_HEIGHT),
+                    margin: UiRect::bottom(Val::Px(4.0)),
+                    ..default()
+                },
+                background_color: Color::BLACK.with_a(0.5).into(),
+                ..default()
+            })
+            .with_children(|track| {
+                track.spawn((
+                    NodeBundle {
+                        style: Style {
+                            width: Val::Percent(100.0),
+                            height: Val::Percent(100.0),
+                            ..default()
+                        },
+                        background_color: Color::ORANGE.into(),
+                        ..default()
+                    },
+                    TimerBar,
+                ));
+            });
+            row.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 24.0,
+                        color: Color::ORANGE,
+                        ..default()
+                    },
+                ),
+                TimerText,
+            ));
+        });
+}
+
+pub fn reset_timers(mut timed: ResMut<Timed>, difficulty: Res<Difficulty>) {
+    *timed = Timed::new(timed.enabled, timed.timeout, *difficulty);
+}
+
+pub fn tick_timers(
+    (time, turn, tally): (Res<Time>, Res<Turn>, Res<GameTally>),
+    mut timed: ResMut<Timed>,
+    (hint, next_tile_kind): (Res<Hint>, Res<NextTileKind>),
+    inspector: BoardInspector,
+    (mut place_events, mut passed_events): (EventWriter<PlaceTile>, EventWriter<TurnPassed>),
+    mut players_q: Query<(&Player, &mut TurnBudget, &Pawn), Without<Bot>>,
+) {
+    if tally.ended() {
+        return;
+    }
+    if turn.is_changed() {
+        timed.turn.reset();
+    }
+    timed.total += time.delta_seconds();
+    if !timed.turn.tick(time.delta()).finished() {
+        return;
+    }
+    let Some((_, mut budget, pawn)) = players_q
+        .iter_mut()
+        .find(|(player, ..)| player.number == turn.player)
+    else {
+        return;
+    };
+    if timed.turn.just_finished() {
+        passed_events.send(TurnPassed {
+            player: turn.player,
+        });
+    }
+    // Out of time, a tile put down for the player is paid for with the one
+    // action left to them, and whatever else is left goes unspent. Should it
+    // not go down, the turn passes on the next frame all the same.
+    if timed.turn.just_finished() && timed.timeout == Timeout::Place {
+        let floor = pawn.floor;
+        let placement = hint.0.or_else(|| {
+            let view = BoardView::on_floor(&inspector, floor);
+            placements(&view, next_tile_kind.0).first().copied()
+        });
+        if let Some(placement) = placement {
+            budget.remaining = 1;
+            place_events.send(PlaceTile {
+                pos: placement.pos,
+                floor,
+                kind: next_tile_kind.0,
+                rotation: placement.rotation,
+            });
+            return;
+        }
+    }
+    budget.remaining = 0;
+}
+
+pub fn update_timer_text(
+    timed: Res<Timed>,
+    strings: Res<Strings>,
+    mut text_q: Query<&mut Text, With<TimerText>>,
+) {
+    if !timed.is_changed() {
+        return;
+    }
+    let value = strings.format(
+        "timed-clock",
+        &[
+            ("turn", timed.turn.remaining_secs().ceil().to_string()),
+            ("total", format_duration(timed.total)),
+        ],
+    );
+    for mut text in text_q.iter_mut() {
+        text.sections[0].value = value.clone();
+    }
+}
+
+// The bar shrinks with the time left and turns red near the end.
+pub fn update_timer_bar(
+    timed: Res<Timed>,
+    mut bar_q: Query<(&mut Style, &mut BackgroundColor), With<TimerBar>>,
+) {
+    if !timed.is_changed() {
+        return;
+    }
+    let left = timed.turn.percent_left();
+    let color = if left > BAR_WARNING {
+        Color::ORANGE
+    } else {
+        Color::RED
+    };
+    for (mut style, mut background) in bar_q.iter_mut() {
+        style.width = Val::Percent(left * 100.0);
+        background.0 = color;
+    }
+}
+
+// Keeps the game's time if it's the best win yet.
+pub fn record_time(
+    mut timed: ResMut<Timed>,
+    mut stats: ResMut<Stats>,
+    mut ended_events: EventReader<GameEnded>,
+) {
+    let Some(ended) = ended_events.read().next().copied() else {
+        return;
+    };
+    timed.best_before = stats.best_timed;
+    if !ended.won || stats.best_timed.is_some_and(|best| best <= timed.total) {
+        return;
+    }
+    stats.best_timed = Some(timed.total);
+    if let Err(err) = stats.save() {
+        warn!("{}", err);
+    }
+}
@@ -0,0 +1,189 @@
+use bevy::{input::mouse::MouseButtonInput, prelude::*};
+use bevy_ecs_tilemap::prelude::*;
+
+use crate::board::{tile_center, HighlightedLabel, IlluminatedLabel, TileType};
+use crate::floor::ShownFloor;
+use crate::inspect::BoardInspector;
+use crate::locale::Strings;
+use crate::pawn::{escapes, Keys, Pawn};
+use crate::rules::TilePlaced;
+
+// Scenario the tutorial is played on. The steps below point at its tiles.
+pub const TUTORIAL_SCENARIO: &str = "tutorial";
+
+const TARGET_RADIUS: f32 = 0.4;
+
+// What the player has to do to move on from a step.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Goal {
+    Hover,
+    Rotate,
+    PlaceAt(u32, u32),
+    LightTile,
+    PickUpKey,
+    EscapeThroughGate,
+}
+
+struct Step {
+    // Key of the instructions in the string table.
+    text: &'static str,
+    // The cell to draw the player's eye to, if any.
+    target: Option<(u32, u32)>,
+    goal: Goal,
+}
+
+const STEPS: [Step; 6] = [
+    Step {
+        text: "tutorial-hover",
+        target: None,
+        goal: Goal::Hover,
+    },
+    Step {
+        text: "tutorial-rotate",
+        target: None,
+        goal: Goal::Rotate,
+    },
+    Step {
+        text: "tutorial-place",
+        target: Some((2, 2)),
+        goal: Goal::PlaceAt(2, 2),
+    },
+    Step {
+        text: "tutorial-light",
+        target: Some((1, 2)),
+        goal: Goal::LightTile,
+    },
+    Step {
+        text: "tutorial-pick-up-key",
+        target: Some((2, 3)),
+        goal: Goal::PickUpKey,
+    },
+    Step {
+        text: "tutorial-escape",
+        target: Some((3, 2)),
+        goal: Goal::EscapeThroughGate,
+    },
+];
+
+const COMPLETE_TEXT: &str = "tutorial-complete";
+
+// Progress through the tutorial, when the game was started with --tutorial.
+#[derive(Resource, Default)]
+pub struct Tutorial {
+    active: bool,
+    step: usize,
+}
+
+impl Tutorial {
+    pub fn new(active: bool) -> Self {
+        Self { active, step: 0 }
+    }
+
+    // Back to the first step, for a new game.
+    pub fn restart(&mut self) {
+        self.step = 0;
+    }
+
+    fn current(&self) -> Option<&'static Step> {
+        STEPS.get(self.step)
+    }
+}
+
+pub fn running(tutorial: Res<Tutorial>) -> bool {
+    tutorial.active
+}
+
+#[derive(Component)]
+pub struct TutorialText;
+
+pub fn spawn_tutorial_text(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 20.0,
+                color: Color::ORANGE,
+                ..default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(8.0),
+            left: Val::Px(8.0),
+            max_width: Val::Px(480.0),
+            ..default()
+        }),
+        TutorialText,
+    ));
+}
+
+// Moves on to the next step once the player does what the current one asks.
+pub fn advance_tutorial(
+    mut tutorial: ResMut<Tutorial>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut mouse_button_input_events: EventReader<MouseButtonInput>,
+    mut placed_events: EventReader<TilePlaced>,
+    tiles_q: Query<(Has<TileType>, Has<HighlightedLabel>, Has<IlluminatedLabel>)>,
+    pawns_q: Query<(&Pawn, &Keys)>,
+    inspector: BoardInspector,
+) {
+    let rotated = mouse_button_input_events.read().any(|mouse_button_input| {
+        mouse_button_input.button == MouseButton::Right
+            && mouse_button_input.state.is_pressed()
+            && !keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight])
+    });
+    let placed: Vec<TilePos> = placed_events.read().map(|placed| placed.pos).collect();
+
+    let Some(step) = tutorial.current() else {
+        return;
+    };
+    let done = match step.goal {
+        Goal::Hover => tiles_q.iter().any(|(_, highlighted, _)| highlighted),
+        Goal::Rotate => rotated,
+        Goal::PlaceAt(x, y) => placed.contains(&TilePos { x, y }),
+        Goal::LightTile => tiles_q
+            .iter()
+            .any(|(placed, _, illuminated)| placed && illuminated),
+        Goal::PickUpKey => pawns_q.iter().any(|(_, keys)| keys.0 > 0),
+        Goal::EscapeThroughGate => pawns_q.iter().any(|(pawn, keys)| {
+            inspector
+                .tile_info_on(pawn.floor, &pawn.pos)
+                .is_some_and(|info| escapes(info.kind, keys.0))
+        }),
+    };
+    if done {
+        tutorial.step += 1;
+    }
+}
+
+pub fn draw_tutorial_target(
+    mut gizmos: Gizmos,
+    time: Res<Time>,
+    tutorial: Res<Tutorial>,
+    tilemap_q: Query<(&TilemapGridSize, &TilemapType, &Transform), With<ShownFloor>>,
+) {
+    let Some((x, y)) = tutorial.current().and_then(|step| step.target) else {
+        return;
+    };
+    // pulse so the marker stands out from the lit tiles
+    let pulse = 1.0 + 0.1 * (time.elapsed_seconds() * 4.0).sin();
+    for (grid_size, map_type, map_transform) in tilemap_q.iter() {
+        let center = tile_center(&TilePos { x, y }, grid_size, map_type, map_transform);
+        let radius = grid_size.x * map_transform.scale.x * TARGET_RADIUS * pulse;
+        gizmos.circle_2d(center, radius, Color::LIME_GREEN);
+    }
+}
+
+pub fn update_tutorial_text(
+    tutorial: Res<Tutorial>,
+    strings: Res<Strings>,
+    mut text_q: Query<&mut Text, With<TutorialText>>,
+) {
+    if !tutorial.is_changed() {
+        return;
+    }
+    let value = tutorial.current().map_or(COMPLETE_TEXT, |step| step.text);
+    for mut text in text_q.iter_mut() {
+        text.sections[0].value = strings.get(value);
+    }
+}
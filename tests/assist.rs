@@ -0,0 +1,44 @@
+use nightcage::assist::{suggest, Assistant, Easing};
+use nightcage::difficulty::Difficulty;
+
+#[test]
+fn the_assistant_only_offers_easing_after_a_losing_streak() {
+    for difficulty in [Difficulty::Easy, Difficulty::Normal, Difficulty::Nightmare] {
+        assert_eq!(suggest(0, difficulty), None);
+        assert_eq!(suggest(2, difficulty), None);
+        let easing = suggest(3, difficulty).expect("easing offered after three losses");
+        assert_eq!(easing.extra_keys, 1);
+        assert_eq!(
+            easing.slow_wax_eaters,
+            difficulty == Difficulty::Nightmare,
+            "{:?}",
+            difficulty
+        );
+        assert!(suggest(6, difficulty).is_some_and(|easing| easing.slow_wax_eaters));
+    }
+    let slow = Easing {
+        slow_wax_eaters: true,
+        ..Easing::default()
+    };
+    assert!(slow.stirring_seconds(2.0) > Easing::default().stirring_seconds(2.0));
+}
+
+#[test]
+fn an_offer_only_eases_the_next_game_once_taken_up() {
+    let mut assistant = Assistant::default();
+    assistant.evaluate(false, 3, Difficulty::Normal);
+    assert!(assistant.offer.is_some());
+    assert_eq!(assistant.take(), Easing::default());
+    assert_eq!(assistant.offer, None);
+
+    assistant.evaluate(false, 4, Difficulty::Normal);
+    assistant.accepted = true;
+    assert_eq!(assistant.take().extra_keys, 1);
+    assert_eq!(assistant.take(), Easing::default());
+
+    assistant.evaluate(true, 0, Difficulty::Normal);
+    assert_eq!(assistant.offer, None);
+    assistant.enabled = false;
+    assistant.evaluate(false, 9, Difficulty::Nightmare);
+    assert_eq!(assistant.offer, None);
+}
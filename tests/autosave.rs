@@ -0,0 +1,44 @@
+use bevy_ecs_tilemap::prelude::*;
+use nightcage::autosave::Snapshot;
+use nightcage::tiles::TileKind;
+
+#[test]
+fn autosaves_read_back_as_written() {
+    let source = "# nightcage autosave 1
+saved 4
+seed 1234
+difficulty nightmare
+board 5x5 square 2
+bots 1
+turn 1 9
+nerve 2
+pawn 0 2 2 0 1
+pawn 1 0 4 1 0
+hand corner
+held
+bag straight dead-end
+discard key
+tile 2 2 0 crossing 0
+tile 0 4 1 gate 3
+monster 4 4 1
+scenario
+# nightcage scenario 1
+board 5x5 square
+tile 2 2 crossing 0
+start 2 2
+floor 1
+";
+    let snapshot = Snapshot::parse(source).unwrap();
+    assert_eq!(snapshot.saved, 4);
+    assert_eq!(snapshot.bag, vec![TileKind::Straight, TileKind::DeadEnd]);
+    assert!(snapshot.held.is_empty());
+    assert_eq!(snapshot.monsters, vec![(1, TilePos { x: 4, y: 4 })]);
+    assert_eq!(
+        snapshot.scenario.as_ref().map(|scenario| scenario.floors),
+        Some(2)
+    );
+    assert_eq!(snapshot.to_string(), source);
+    assert!(
+        Snapshot::parse("# nightcage autosave 1\nboard 5x5 square 1\nmonster 5 0 0\n").is_err()
+    );
+}
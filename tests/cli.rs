@@ -0,0 +1,32 @@
+use nightcage::cli::Args;
+
+fn parse(args: &[&str]) -> Result<Args, String> {
+    Args::parse(args.iter().map(|arg| arg.to_string()))
+}
+
+#[test]
+fn sizes_and_counts_past_what_a_game_can_hold_are_turned_away() {
+    for args in [
+        &["--board", "65"][..],
+        &["--board", "9x1000000"],
+        &["--board", "0x9"],
+        &["--floors", "9"],
+        &["--floors", "0"],
+        &["--bots", "4"],
+        &["--bots", "-1"],
+    ] {
+        assert!(parse(args).is_err(), "{:?}", args);
+    }
+    let args = parse(&["--board", "64x1", "--floors", "8", "--bots", "3"]).unwrap();
+    assert_eq!((args.board.size.x, args.board.size.y), (64, 1));
+    assert_eq!(args.board.floors, 8);
+    assert_eq!(args.players.bots, 3);
+}
+
+#[test]
+fn the_tutorial_cannot_be_given_another_scenario() {
+    assert!(parse(&["--tutorial", "--scenario", "maze"]).is_err());
+    assert!(parse(&["--scenario", "maze", "--tutorial"]).is_err());
+    let args = parse(&["--tutorial"]).unwrap();
+    assert!(args.tutorial && args.scenario.is_some());
+}
@@ -0,0 +1,25 @@
+use nightcage::board::BoardConfig;
+use nightcage::daily::{DailyChallenge, Date};
+
+#[test]
+fn daily_challenges_follow_the_calendar() {
+    for (days, date) in [
+        (0, "1970-01-01"),
+        (19_723, "2024-01-01"),
+        (19_782, "2024-02-29"),
+        (19_783, "2024-03-01"),
+        (20_741, "2026-10-15"),
+    ] {
+        assert_eq!(Date::from_days(days).to_string(), date);
+    }
+
+    let today = DailyChallenge::new(Date::from_days(20_741), BoardConfig::default());
+    assert_eq!(
+        today,
+        DailyChallenge::new(Date::from_days(20_741), BoardConfig::default())
+    );
+    assert_ne!(
+        today.seed,
+        DailyChallenge::new(Date::from_days(20_742), BoardConfig::default()).seed
+    );
+}
@@ -0,0 +1,31 @@
+use bevy_ecs_tilemap::prelude::*;
+use nightcage::board::BoardConfig;
+use nightcage::floor::{random_stairs, stairs_meet};
+use nightcage::rng::{GameRng, Seed};
+use nightcage::scenario::Scenario;
+
+// Stairs are laid out for this many seeds.
+const CASES: u64 = 200;
+
+#[test]
+fn no_two_flights_of_stairs_end_on_the_same_cell() {
+    let board = BoardConfig {
+        size: TilemapSize { x: 2, y: 1 },
+        floors: 6,
+        ..BoardConfig::default()
+    };
+    for seed in 0..CASES {
+        let stairs = random_stairs(&board, &mut GameRng::new(Seed(seed)));
+        for (i, flight) in stairs.iter().enumerate() {
+            assert!(
+                stairs[i + 1..]
+                    .iter()
+                    .all(|other| !stairs_meet(flight, other)),
+                "seed {}: {:?}",
+                seed,
+                stairs
+            );
+        }
+    }
+    assert!(Scenario::parse("board 5x5 square\nstairs 2 2\nfloor 1\nstairs 2 2\n").is_err());
+}
@@ -0,0 +1,41 @@
+use bevy_ecs_tilemap::prelude::*;
+use nightcage::board::BoardConfig;
+use nightcage::difficulty::Difficulty;
+use nightcage::generator::{generate, solve};
+use nightcage::house_rules::RuleSet;
+use nightcage::rng::Seed;
+use nightcage::scenario::Scenario;
+use nightcage::simulation::Simulation;
+
+#[test]
+fn generated_boards_can_be_won_from_their_seed() {
+    let board = BoardConfig {
+        size: TilemapSize { x: 7, y: 7 },
+        ..BoardConfig::default()
+    };
+    for (seed, difficulty) in [
+        (1, Difficulty::Easy),
+        (2, Difficulty::Normal),
+        (3, Difficulty::Nightmare),
+    ] {
+        let scenario = generate(Seed(seed), difficulty, &board).unwrap();
+        assert_eq!(
+            Scenario::parse(&scenario.to_string()).as_ref(),
+            Ok(&scenario)
+        );
+        let play = || {
+            Simulation::new(
+                board,
+                Some(&scenario),
+                1,
+                difficulty,
+                RuleSet::default(),
+                Seed(seed),
+            )
+        };
+        let actions = solve(&mut play(), 400).expect("the generated board is won");
+        let mut replayed = play();
+        assert_eq!(replayed.run(actions.iter().copied()), actions.len());
+        assert!(replayed.escaped(), "seed {}", seed);
+    }
+}
@@ -0,0 +1,33 @@
+use bevy_ecs_tilemap::prelude::*;
+use nightcage::pawn::{occupants, Pawn};
+use nightcage::rules::Player;
+
+#[test]
+fn occupants_are_the_players_standing_on_each_cell() {
+    let pos = |x, y| TilePos { x, y };
+    let players: Vec<Player> = (0..4).map(|number| Player { number }).collect();
+    let pawns = [
+        Pawn {
+            pos: pos(1, 1),
+            floor: 0,
+        },
+        Pawn {
+            pos: pos(2, 1),
+            floor: 0,
+        },
+        Pawn {
+            pos: pos(1, 1),
+            floor: 1,
+        },
+        Pawn {
+            pos: pos(1, 1),
+            floor: 0,
+        },
+    ];
+    // listed out of order, as a query would hand them over
+    let standing = occupants([3, 1, 0, 2].map(|player| (&players[player], &pawns[player])));
+    assert_eq!(standing.get(&(0, pos(1, 1))), Some(&vec![0, 3]));
+    assert_eq!(standing.get(&(0, pos(2, 1))), Some(&vec![1]));
+    assert_eq!(standing.get(&(1, pos(1, 1))), Some(&vec![2]));
+    assert_eq!(standing.get(&(0, pos(0, 0))), None);
+}
@@ -0,0 +1,33 @@
+use bevy_ecs_tilemap::prelude::*;
+use nightcage::board::neighbors;
+use nightcage::path::board_distances;
+
+#[test]
+fn paths_take_the_fewest_steps() {
+    let size = TilemapSize { x: 5, y: 4 };
+    let start = TilePos { x: 1, y: 2 };
+    for wrap in [false, true] {
+        let paths = board_distances([start], &size, &TilemapType::Square, wrap);
+        for pos in (0..size.y).flat_map(|y| (0..size.x).map(move |x| TilePos { x, y })) {
+            let span = |from: u32, to: u32, side: u32| {
+                let apart = from.abs_diff(to);
+                if wrap {
+                    apart.min(side - apart)
+                } else {
+                    apart
+                }
+            };
+            let steps = span(start.x, pos.x, size.x) + span(start.y, pos.y, size.y);
+            assert_eq!(paths.steps(&pos), Some(steps), "{:?}", pos);
+
+            let path = paths.path_to(&pos).unwrap();
+            assert_eq!(path.len() as u32, steps);
+            assert_eq!(path.last().unwrap_or(&start), &pos);
+            let mut from = start;
+            for step in path {
+                assert!(neighbors(&from, &size, &TilemapType::Square, wrap).contains(&step));
+                from = step;
+            }
+        }
+    }
+}
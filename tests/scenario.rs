@@ -0,0 +1,43 @@
+use bevy_ecs_tilemap::prelude::*;
+use nightcage::scenario::{Scenario, SCENARIO_FORMAT};
+
+#[test]
+fn upper_floors_are_kept_when_a_scenario_is_written_out() {
+    let source = "# nightcage scenario 1
+board 5x5 square
+tile 2 2 crossing 0
+stairs 2 2
+start 2 2
+floor 1
+tile 2 2 dead-end 3
+stairs 0 4
+floor 2
+tile 0 4 gate 1
+";
+    let scenario = Scenario::parse(source).unwrap();
+    assert_eq!(scenario.floors, 3);
+    assert_eq!(
+        scenario.stairs,
+        vec![(0, TilePos { x: 2, y: 2 }), (1, TilePos { x: 0, y: 4 })]
+    );
+    assert_eq!(scenario.to_string(), source);
+    assert!(Scenario::parse("board 5x5 square\nfloor 1\nstart 2 2\n").is_err());
+}
+
+#[test]
+fn scenarios_load_from_every_version_up_to_this_one() {
+    let board = "board 3x3 square\ntile 1 1 crossing 0\nstart 1 1\n";
+    let current = Scenario::parse(&format!("{}{}", SCENARIO_FORMAT.header(), board)).unwrap();
+    assert_eq!(Scenario::parse(board).unwrap(), current);
+    for header in [
+        format!("# nightcage scenario {}\n", SCENARIO_FORMAT.version() + 1),
+        "# nightcage scenario 0\n".to_string(),
+        "# nightcage stats 1\n".to_string(),
+    ] {
+        assert!(
+            Scenario::parse(&format!("{}{}", header, board)).is_err(),
+            "{}",
+            header
+        );
+    }
+}
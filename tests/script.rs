@@ -0,0 +1,25 @@
+use nightcage::script::{self, Effect, Hook};
+
+#[test]
+fn tile_scripts_only_run_the_effects_they_are_given() {
+    let script =
+        script::parse("# a generous pillar\non place grant 1\n\non light key 3\non enter redraw\n")
+            .unwrap();
+    assert_eq!(
+        script,
+        vec![
+            (Hook::Place, Effect::Grant(1)),
+            (Hook::Light, Effect::Key(3)),
+            (Hook::Enter, Effect::Redraw),
+        ]
+    );
+    for source in [
+        "on place grant 4",
+        "on place grant 0",
+        "on fall nerve 1",
+        "on enter spawn",
+        "grant 1",
+    ] {
+        assert!(script::parse(source).is_err(), "{}", source);
+    }
+}
@@ -0,0 +1,50 @@
+use bevy_ecs_tilemap::prelude::*;
+use nightcage::board::BoardShape;
+use nightcage::difficulty::Difficulty;
+use nightcage::house_rules::RuleSet;
+use nightcage::nerve::NerveAction;
+use nightcage::rng::Seed;
+use nightcage::share::{Move, Replay};
+use nightcage::tiles::TileKind;
+
+#[test]
+fn share_codes_carry_a_game_and_its_moves() {
+    let replay = Replay {
+        seed: Seed(u64::MAX - 7),
+        difficulty: Difficulty::Nightmare,
+        size: TilemapSize { x: 9, y: 20 },
+        shape: BoardShape::Hex,
+        floors: 2,
+        bots: 3,
+        rules: RuleSet {
+            no_collapse: true,
+            wrap_around: true,
+            ..RuleSet::default()
+        },
+        moves: vec![
+            Move::Turn {
+                pos: TilePos { x: 8, y: 19 },
+                floor: 1,
+            },
+            Move::Place {
+                pos: TilePos { x: 8, y: 19 },
+                floor: 1,
+                kind: TileKind::Pillar,
+                rotation: 5,
+            },
+            Move::Walk(TilePos { x: 0, y: 14 }),
+            Move::PassKey(TilePos { x: 1, y: 14 }),
+            Move::Stairs,
+            Move::Swap,
+            Move::Nerve(NerveAction::Redraw),
+            Move::Pass,
+        ],
+    };
+    let code = replay.encode();
+    assert!(code.len() < 48, "{} is too long to paste", code);
+    assert_eq!(Replay::decode(&code).as_ref(), Ok(&replay));
+    let link = format!("https://example.com/nightcage/#{}", code);
+    assert_eq!(Replay::decode(&link).as_ref(), Ok(&replay));
+    assert!(Replay::decode(&code[..code.len() / 2]).is_err());
+    assert!(Replay::decode("not a code!").is_err());
+}
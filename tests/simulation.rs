@@ -0,0 +1,490 @@
+use bevy_ecs_tilemap::prelude::*;
+use nightcage::board::{neighbors, BoardConfig, BoardShape};
+use nightcage::darkness::land_hits;
+use nightcage::difficulty::Difficulty;
+use nightcage::house_rules::RuleSet;
+use nightcage::nerve::NerveAction;
+use nightcage::rng::Seed;
+use nightcage::scenario::{Scenario, ScenarioTile};
+use nightcage::share::{Move, Replay};
+use nightcage::simulation::{Action, Simulation};
+use nightcage::tiles::TileKind;
+
+// Each property is checked against this many randomly generated games.
+const CASES: u64 = 200;
+const ACTIONS_PER_GAME: usize = 60;
+
+// A square board of random size with random tiles on about half of it. The
+// player starts on a placed tile so nobody falls while the game is set up.
+fn random_scenario(rng: &mut fastrand::Rng) -> Scenario {
+    let side = rng.u32(3..=7);
+    let size = TilemapSize { x: side, y: side };
+    let mut tiles = Vec::new();
+    for y in 0..side {
+        for x in 0..side {
+            if rng.bool() {
+                tiles.push(ScenarioTile {
+                    pos: TilePos { x, y },
+                    floor: 0,
+                    kind: TileKind::PLACEABLE[rng.usize(..TileKind::PLACEABLE.len())],
+                    rotation: rng.u8(0..4),
+                });
+            }
+        }
+    }
+    let start = TilePos {
+        x: rng.u32(0..side),
+        y: rng.u32(0..side),
+    };
+    tiles.retain(|tile| tile.pos != start);
+    tiles.push(ScenarioTile {
+        pos: start,
+        floor: 0,
+        kind: TileKind::Crossing,
+        rotation: 0,
+    });
+    Scenario {
+        size,
+        shape: BoardShape::Square,
+        floors: 1,
+        tiles,
+        stairs: Vec::new(),
+        player_starts: vec![start],
+        monsters: Vec::new(),
+        next: None,
+        bag: Vec::new(),
+    }
+}
+
+fn simulate(scenario: &Scenario, players: usize, seed: u64) -> Simulation {
+    Simulation::new(
+        BoardConfig::default(),
+        Some(scenario),
+        players,
+        Difficulty::Normal,
+        RuleSet::default(),
+        Seed(seed),
+    )
+}
+
+fn random_pos(rng: &mut fastrand::Rng, size: &TilemapSize) -> TilePos {
+    TilePos {
+        x: rng.u32(0..size.x),
+        y: rng.u32(0..size.y),
+    }
+}
+
+// Mostly moves and placements, with the occasional other action mixed in.
+fn random_action(rng: &mut fastrand::Rng, sim: &Simulation) -> Action {
+    let pawn = sim.pawns()[sim.turn.player].pos;
+    match rng.u8(0..10) {
+        0..=3 => Action::Place(random_pos(rng, &sim.size)),
+        4..=6 => {
+            let around = neighbors(&pawn, &sim.size, &sim.map_type, sim.wraps());
+            Action::Move(around[rng.usize(..around.len())])
+        }
+        7 => Action::Rotate,
+        8 => Action::EndTurn,
+        _ if rng.bool() => Action::ExtraAction,
+        _ => Action::Redraw,
+    }
+}
+
+fn sorted_tiles(sim: &Simulation) -> Vec<(TilePos, TileKind, u8)> {
+    let mut tiles: Vec<_> = sim.tiles().collect();
+    tiles.sort_by_key(|(pos, ..)| (pos.y, pos.x));
+    tiles
+}
+
+// A quarter turn clockwise of the whole board.
+fn rotate_pos(pos: &TilePos, size: &TilemapSize) -> TilePos {
+    TilePos {
+        x: pos.y,
+        y: size.x - 1 - pos.x,
+    }
+}
+
+#[test]
+fn illumination_turns_with_the_board() {
+    for seed in 0..CASES {
+        let mut rng = fastrand::Rng::with_seed(seed);
+        let scenario = random_scenario(&mut rng);
+        let mut turned = scenario.clone();
+        for tile in &mut turned.tiles {
+            tile.pos = rotate_pos(&tile.pos, &scenario.size);
+            tile.rotation = (tile.rotation + 1) % 4;
+        }
+        turned.player_starts = vec![rotate_pos(&scenario.player_starts[0], &scenario.size)];
+        let sim = simulate(&scenario, 1, seed);
+        let turned_sim = simulate(&turned, 1, seed);
+
+        for pos in sorted_tiles(&sim).iter().map(|(pos, ..)| *pos) {
+            for kind in TileKind::PLACEABLE {
+                for rotation in 0..4 {
+                    let mut expected: Vec<TilePos> = sim
+                        .illuminated_by(&pos, kind, rotation)
+                        .iter()
+                        .map(|lit| rotate_pos(lit, &sim.size))
+                        .collect();
+                    let mut lit = turned_sim.illuminated_by(
+                        &rotate_pos(&pos, &sim.size),
+                        kind,
+                        (rotation + 1) % 4,
+                    );
+                    expected.sort_by_key(|pos| (pos.y, pos.x));
+                    lit.sort_by_key(|pos| (pos.y, pos.x));
+                    assert_eq!(
+                        lit, expected,
+                        "seed {}: {:?} turned {} over {:?}",
+                        seed, kind, rotation, pos
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn placing_never_removes_the_tiles_it_lights() {
+    for seed in 0..CASES {
+        let mut rng = fastrand::Rng::with_seed(seed);
+        let mut sim = simulate(&random_scenario(&mut rng), 2, seed);
+        for _ in 0..ACTIONS_PER_GAME {
+            let action = random_action(&mut rng, &sim);
+            let Action::Place(pos) = action else {
+                sim.apply(action);
+                continue;
+            };
+            let lit: Vec<(TilePos, TileKind)> = sim
+                .illuminated(&pos)
+                .into_iter()
+                .map(|lit| (lit, sim.tile(&lit)))
+                .filter(|(_, kind)| *kind != TileKind::Empty)
+                .collect();
+            sim.apply(action);
+            for (lit, kind) in lit {
+                // lighting a key under a pawn has it picked up, leaving a dead end
+                let picked_up = kind == TileKind::Key && sim.tile(&lit) == TileKind::DeadEnd;
+                assert!(
+                    sim.tile(&lit) == kind || picked_up,
+                    "seed {}: placing at {:?} removed {:?}",
+                    seed,
+                    pos,
+                    lit
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn wax_eaters_only_wake_at_the_dark_frontier() {
+    for seed in 0..CASES {
+        let mut rng = fastrand::Rng::with_seed(seed);
+        let mut sim = simulate(&random_scenario(&mut rng), 2, seed);
+        for _ in 0..ACTIONS_PER_GAME {
+            let before = sim.monsters().to_vec();
+            sim.apply(random_action(&mut rng, &sim));
+            for pos in sim.monsters().iter().filter(|pos| !before.contains(pos)) {
+                let beside_placed = neighbors(pos, &sim.size, &sim.map_type, sim.wraps())
+                    .iter()
+                    .any(|neighbor| sim.tile(neighbor) != TileKind::Empty);
+                assert!(
+                    sim.tile(pos) == TileKind::Empty && beside_placed,
+                    "seed {}: a wax eater woke at {:?}, away from the dark frontier",
+                    seed,
+                    pos
+                );
+                assert!(
+                    sim.pawns().iter().all(|pawn| pawn.pos != *pos),
+                    "seed {}: a wax eater woke on a pawn at {:?}",
+                    seed,
+                    pos
+                );
+            }
+            assert!(sim.monsters().len() <= Difficulty::Normal.max_wax_eaters().max(before.len()));
+        }
+    }
+}
+
+#[test]
+fn nerve_shrugs_off_wax_eater_hits_until_it_runs_out() {
+    let size = TilemapSize { x: 5, y: 5 };
+    let board = (&size, &TilemapType::Square, false);
+    let mut pawns = vec![
+        (TilePos { x: 1, y: 2 }, 2),
+        (TilePos { x: 3, y: 2 }, 1),
+        (TilePos { x: 0, y: 0 }, 1),
+    ];
+    let mut nerve = 1;
+    let hits = land_hits(board, &[TilePos { x: 2, y: 2 }], &mut pawns, &mut nerve);
+    assert_eq!(hits, vec![(0, true), (1, false)]);
+    assert_eq!(nerve, 0);
+    let keys: Vec<u32> = pawns.iter().map(|(_, keys)| *keys).collect();
+    assert_eq!(keys, vec![2, 0, 1]);
+}
+
+#[test]
+fn escaping_takes_a_key_to_a_gate() {
+    for seed in 0..CASES {
+        let mut rng = fastrand::Rng::with_seed(seed);
+        let mut sim = simulate(&random_scenario(&mut rng), 2, seed);
+        for _ in 0..ACTIONS_PER_GAME {
+            sim.apply(random_action(&mut rng, &sim));
+            let on_gate_with_key = sim
+                .pawns()
+                .iter()
+                .any(|pawn| sim.tile(&pawn.pos) == TileKind::Gate && pawn.keys > 0);
+            assert_eq!(sim.escaped(), on_gate_with_key, "seed {}", seed);
+        }
+    }
+}
+
+#[test]
+fn keys_are_handed_along_corridors_a_pawn_could_walk() {
+    let mut handed = 0;
+    for rotation in 0..4 {
+        let tile = |x, kind| ScenarioTile {
+            pos: TilePos { x, y: 0 },
+            floor: 0,
+            kind,
+            rotation,
+        };
+        let scenario = Scenario {
+            size: TilemapSize { x: 2, y: 1 },
+            shape: BoardShape::Square,
+            floors: 1,
+            tiles: vec![tile(0, TileKind::Key), tile(1, TileKind::Crossing)],
+            stairs: Vec::new(),
+            player_starts: vec![TilePos { x: 0, y: 0 }, TilePos { x: 1, y: 0 }],
+            monsters: Vec::new(),
+            next: None,
+            bag: Vec::new(),
+        };
+        let neighbor = TilePos { x: 1, y: 0 };
+        let mut walker = simulate(&scenario, 2, 0);
+        let mut giver = simulate(&scenario, 2, 0);
+        let walks = walker.apply(Action::Move(neighbor));
+        // the key is only found when its corridor joins the crossing's
+        assert_eq!(giver.pawns()[0].keys, walks as u32, "rotation {}", rotation);
+        assert!(!giver.apply(Action::PassKey(TilePos { x: 0, y: 0 })));
+
+        assert_eq!(
+            giver.apply(Action::PassKey(neighbor)),
+            walks,
+            "rotation {}",
+            rotation
+        );
+        if walks {
+            assert_eq!(giver.pawns()[0].keys, 0);
+            assert_eq!(giver.pawns()[1].keys, 1);
+            assert!(!giver.apply(Action::PassKey(neighbor)));
+            handed += 1;
+        }
+    }
+    assert_eq!(handed, 1);
+}
+
+#[test]
+fn no_tile_collapses_under_the_no_collapse_rule() {
+    let rules = RuleSet {
+        no_collapse: true,
+        ..RuleSet::default()
+    };
+    assert_eq!(RuleSet::parse(&rules.to_source()), Ok(rules));
+    for seed in 0..CASES {
+        let mut rng = fastrand::Rng::with_seed(seed);
+        let scenario = random_scenario(&mut rng);
+        let board = BoardConfig::default();
+        let mut sim = Simulation::new(
+            board,
+            Some(&scenario),
+            2,
+            Difficulty::Normal,
+            rules,
+            Seed(seed),
+        );
+        for _ in 0..ACTIONS_PER_GAME {
+            let action = random_action(&mut rng, &sim);
+            let placed: Vec<TilePos> = sim.tiles().map(|(pos, ..)| pos).collect();
+            let allowed = sim.apply(action);
+            if let Action::Place(pos) = action {
+                assert!(!(allowed && placed.contains(&pos)), "seed {}", seed);
+            }
+            for pos in &placed {
+                assert_ne!(sim.tile(pos), TileKind::Empty, "seed {}", seed);
+            }
+        }
+    }
+}
+
+#[test]
+fn tutorial_is_won_by_carrying_the_key_to_the_gate() {
+    let scenario = Scenario::load("tutorial").unwrap();
+    let to_gate = [
+        Action::Place(TilePos { x: 2, y: 2 }),
+        Action::Move(TilePos { x: 2, y: 1 }),
+        Action::Move(TilePos { x: 2, y: 2 }),
+    ];
+
+    let mut without_key = simulate(&scenario, 1, 0);
+    without_key.run(to_gate);
+    assert!(without_key.apply(Action::Move(TilePos { x: 3, y: 2 })));
+    assert!(!without_key.escaped());
+
+    let mut with_key = simulate(&scenario, 1, 0);
+    with_key.run(to_gate);
+    with_key.run([
+        Action::Move(TilePos { x: 2, y: 3 }),
+        Action::Move(TilePos { x: 2, y: 2 }),
+    ]);
+    assert_eq!(with_key.pawns()[0].keys, 1);
+    assert!(!with_key.escaped());
+    assert!(with_key.apply(Action::Move(TilePos { x: 3, y: 2 })));
+    assert!(with_key.escaped());
+}
+
+#[test]
+fn same_seed_and_actions_play_out_the_same() {
+    for seed in 0..CASES {
+        let mut rng = fastrand::Rng::with_seed(seed);
+        let scenario = random_scenario(&mut rng);
+        let mut first = simulate(&scenario, 3, seed);
+        let mut second = simulate(&scenario, 3, seed);
+        for _ in 0..ACTIONS_PER_GAME {
+            let action = random_action(&mut rng, &first);
+            assert_eq!(first.apply(action), second.apply(action), "seed {}", seed);
+        }
+        assert_eq!(sorted_tiles(&first), sorted_tiles(&second), "seed {}", seed);
+        assert_eq!(first.pawns(), second.pawns(), "seed {}", seed);
+        assert_eq!(first.hand, second.hand, "seed {}", seed);
+    }
+}
+
+#[test]
+fn wrapped_boards_join_opposite_edges() {
+    for (x, y) in [(1, 1), (2, 3), (4, 4), (5, 2)] {
+        let size = TilemapSize { x, y };
+        for pos in (0..y).flat_map(|y| (0..x).map(move |x| TilePos { x, y })) {
+            for neighbor in neighbors(&pos, &size, &TilemapType::Square, true) {
+                assert!(
+                    neighbors(&neighbor, &size, &TilemapType::Square, true).contains(&pos),
+                    "{:?} on a {}x{} board",
+                    pos,
+                    x,
+                    y
+                );
+            }
+        }
+    }
+
+    let corridor = |x| ScenarioTile {
+        pos: TilePos { x, y: 0 },
+        floor: 0,
+        kind: TileKind::Straight,
+        rotation: 1,
+    };
+    let scenario = Scenario {
+        size: TilemapSize { x: 3, y: 1 },
+        shape: BoardShape::Square,
+        floors: 1,
+        tiles: vec![corridor(0), corridor(2)],
+        stairs: Vec::new(),
+        player_starts: vec![TilePos { x: 0, y: 0 }],
+        monsters: Vec::new(),
+        next: None,
+        bag: Vec::new(),
+    };
+    let across = Action::Move(TilePos { x: 2, y: 0 });
+    assert!(!simulate(&scenario, 1, 0).apply(across));
+    let rules = RuleSet {
+        wrap_around: true,
+        ..RuleSet::default()
+    };
+    let mut wrapped = Simulation::new(
+        BoardConfig::default(),
+        Some(&scenario),
+        1,
+        Difficulty::Normal,
+        rules,
+        Seed(0),
+    );
+    assert!(wrapped.apply(across));
+}
+
+#[test]
+fn shared_games_play_back_to_the_same_board() {
+    for seed in 0..CASES {
+        let mut rng = fastrand::Rng::with_seed(seed);
+        let mut played = Simulation::new(
+            BoardConfig::default(),
+            None,
+            2,
+            Difficulty::Normal,
+            RuleSet::default(),
+            Seed(seed),
+        );
+        let mut moves = Vec::new();
+        for _ in 0..ACTIONS_PER_GAME {
+            let action = random_action(&mut rng, &played);
+            let play = match action {
+                Action::Place(pos) => Move::Place {
+                    pos,
+                    floor: 0,
+                    kind: played.hand,
+                    rotation: played.hand_rotation,
+                },
+                Action::Rotate => Move::Turn {
+                    pos: played.pawns()[played.turn.player].pos,
+                    floor: 0,
+                },
+                Action::Move(to) => Move::Walk(to),
+                Action::PassKey(to) => Move::PassKey(to),
+                Action::EndTurn => Move::Pass,
+                Action::ExtraAction => Move::Nerve(NerveAction::ExtraAction),
+                Action::Redraw => Move::Nerve(NerveAction::Redraw),
+                Action::Swap => Move::Swap,
+            };
+            if played.apply(action) {
+                moves.push(play);
+            }
+        }
+
+        let board = BoardConfig::default();
+        let replay = Replay {
+            seed: Seed(seed),
+            difficulty: Difficulty::Normal,
+            size: board.size,
+            shape: board.shape,
+            floors: board.floors,
+            bots: 1,
+            rules: RuleSet::default(),
+            moves,
+        };
+        let replay = Replay::decode(&replay.encode()).expect("share code reads back");
+        let mut watched = Simulation::new(
+            BoardConfig {
+                size: replay.size,
+                shape: replay.shape,
+                floors: replay.floors,
+                ..board
+            },
+            None,
+            replay.bots + 1,
+            replay.difficulty,
+            replay.rules,
+            replay.seed,
+        );
+        for play in &replay.moves {
+            assert!(watched.play(*play), "seed {} refused {:?}", seed, play);
+        }
+        assert_eq!(
+            sorted_tiles(&played),
+            sorted_tiles(&watched),
+            "seed {}",
+            seed
+        );
+        assert_eq!(played.pawns(), watched.pawns(), "seed {}", seed);
+        assert_eq!(played.hand, watched.hand, "seed {}", seed);
+    }
+}
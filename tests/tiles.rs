@@ -0,0 +1,105 @@
+use bevy_ecs_tilemap::prelude::*;
+use nightcage::pack::TilePack;
+use nightcage::tiles::{Passages, Quarter, Side, TileKind};
+
+#[test]
+fn a_tile_turned_a_quarter_turns_its_passages_with_it() {
+    use Side::*;
+    let turned = [
+        (TileKind::Corner, Quarter::Zero, &[East, South][..]),
+        (TileKind::Corner, Quarter::One, &[South, West]),
+        (TileKind::Corner, Quarter::Two, &[West, North]),
+        (TileKind::Corner, Quarter::Three, &[North, East]),
+        (TileKind::Straight, Quarter::One, &[East, West]),
+        (TileKind::Straight, Quarter::Two, &[North, South]),
+        (TileKind::TJunction, Quarter::One, &[North, South, West]),
+        (TileKind::Pillar, Quarter::Three, &[East, South]),
+        (TileKind::DeadEnd, Quarter::One, &[West]),
+        (TileKind::Key, Quarter::Three, &[East]),
+        (
+            TileKind::Crossing,
+            Quarter::One,
+            &[North, East, South, West],
+        ),
+    ];
+    for (kind, quarter, sides) in turned {
+        assert_eq!(
+            kind.passages_with(quarter),
+            Passages::from_sides(sides),
+            "{:?} at {:?}",
+            kind,
+            quarter
+        );
+    }
+    assert_eq!(Quarter::from_turns(5), Quarter::One);
+    assert_eq!(Quarter::Three.next(), Quarter::Zero);
+}
+
+// Where the artwork's `side` is drawn with `flip`: the diagonal flip swaps it
+// across the anti-diagonal, then it's mirrored left to right and top to bottom.
+fn flipped(side: Side, flip: TileFlip) -> Side {
+    use Side::*;
+    let side = match side {
+        North if flip.d => West,
+        West if flip.d => North,
+        East if flip.d => South,
+        South if flip.d => East,
+        side => side,
+    };
+    let side = match side {
+        East if flip.x => West,
+        West if flip.x => East,
+        side => side,
+    };
+    match side {
+        North if flip.y => South,
+        South if flip.y => North,
+        side => side,
+    }
+}
+
+#[test]
+fn a_turned_tile_is_drawn_flipped_to_match_its_passages() {
+    for quarter in Quarter::ALL {
+        for side in Side::ALL {
+            assert_eq!(
+                flipped(side, quarter.flip()),
+                side.rotated(quarter.turns()),
+                "{:?} at {:?}",
+                side,
+                quarter
+            );
+        }
+        for kind in TileKind::PLACEABLE {
+            let drawn: Vec<Side> = kind
+                .passages()
+                .sides()
+                .map(|side| flipped(side, quarter.flip()))
+                .collect();
+            assert_eq!(
+                Passages::from_sides(&drawn),
+                kind.passages_with(quarter),
+                "{:?} at {:?}",
+                kind,
+                quarter
+            );
+        }
+    }
+}
+
+#[test]
+fn the_built_in_artwork_flickers_in_hand_and_the_wax_eater_writhes() {
+    // tiles.png is 1920 by 1080, so 15 columns of 8 whole rows
+    let atlas = 15 * 8;
+    let pack = TilePack::default();
+    for kind in TileKind::PLACEABLE {
+        let (on_board, in_hand) = (pack.animation(kind), pack.held_animation(kind));
+        assert!(in_hand.is_animated(), "{:?}", kind);
+        assert_eq!(in_hand.frames[0], on_board.frames[0], "{:?}", kind);
+        for frame in on_board.frames.iter().chain(&in_hand.frames) {
+            assert!(*frame < atlas, "{:?} frame {}", kind, frame);
+        }
+    }
+    assert!(pack.animation(TileKind::Pillar).is_animated());
+    assert!(!pack.animation(TileKind::Straight).is_animated());
+}